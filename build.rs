@@ -0,0 +1,558 @@
+// turns the vanilla data generator reports in reports/<version>/ into rust enums, see src/registry.rs.
+// the reports come from running a server jar with
+//   java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports
+// and copying generated/reports/blocks.json and registries.json over. a missing file or registry
+// gives an empty enum, so a version can be added with only part of its reports.
+// entity_metadata.json and block_physics.json are not vanilla reports, they're written by hand,
+// see metadata_fields and block_physics, and may only name entity types and blocks the reports next
+// to them have. en_us.json is the client's language file, see language and version_language
+
+use {
+    serde_json::Value,
+    std::{
+        collections::BTreeMap,
+        env,
+        fmt::Write,
+        fs,
+        path::Path,
+    },
+};
+
+// registry in registries.json => the enum it becomes
+const REGISTRIES: &[(&str, &str)] = &[
+    ("minecraft:entity_type", "EntityType"),
+    ("minecraft:item", "Item"),
+    ("minecraft:particle_type", "Particle"),
+    ("minecraft:sound_event", "SoundEvent"),
+];
+
+fn main(){
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=reports");
+    println!("cargo:rerun-if-changed=tests/fixtures/blocks");
+
+    let mut versions: Vec<String> = fs::read_dir("reports")
+        .map(|entries| entries.filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect())
+        .unwrap_or_default();
+    // by number, 1.20.10 comes after 1.20.4
+    versions.sort_by_key(|version| version.split('.').map(|part| part.parse::<u32>().unwrap_or(0)).collect::<Vec<_>>());
+
+    let languages: Vec<usize> = (0..versions.len())
+        .filter(|&index| Path::new("reports").join(&versions[index]).join("en_us.json").is_file())
+        .collect();
+
+    let mut out = String::new();
+    let mut all_entries = Vec::new();
+    for (index, version) in versions.iter().enumerate(){
+        let dir = Path::new("reports").join(version);
+        let registries = read_json(&dir.join("registries.json"));
+        let blocks = read_json(&dir.join("blocks.json"));
+        let physics = read_json(&dir.join("block_physics.json"));
+        check_physics(&dir.join("blocks.json"), &blocks, &physics);
+
+        writeln!(out, "// Minecraft {}", version).unwrap();
+        writeln!(out, "#[allow(clippy::all)]").unwrap();
+        writeln!(out, "pub mod v{} {{", version.replace('.', "_")).unwrap();
+        writeln!(out, "    use super::*;\n").unwrap();
+        writeln!(out, "    pub const VERSION: &str = {:?};\n", version).unwrap();
+        let mut version_entries = Vec::new();
+        for (registry, name) in REGISTRIES{
+            let entries = registry_entries(&registries, registry);
+            registry_enum(&mut out, name, &entries);
+            version_entries.push(entries);
+        }
+        all_entries.push((version.as_str(), version_entries));
+        let entity_types = registry_entries(&registries, "minecraft:entity_type");
+        metadata_fields(&mut out, &read_json(&dir.join("entity_metadata.json")), &entity_types);
+        blocks_enum(&mut out, &blocks, &physics);
+        // translation keys hardly change between versions, one without an en_us.json borrows the
+        // closest version's, the older one on a tie
+        match languages.iter().min_by_key(|&&other| other.abs_diff(index)){
+            Some(&other) if other != index => writeln!(out, "    pub use super::v{}::LANGUAGE;", versions[other].replace('.', "_")).unwrap(),
+            _ => language(&mut out, &read_json(&dir.join("en_us.json"))),
+        }
+        writeln!(out, "}}\n").unwrap();
+    }
+    version_registries(&mut out, &all_entries);
+    version_metadata(&mut out, &all_entries);
+    version_blocks(&mut out, &versions);
+    version_language(&mut out, &versions);
+    test_blocks(&mut out);
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("registries.rs");
+    fs::write(path, out).unwrap();
+}
+
+fn read_json(path: &Path) -> Value{
+    match fs::read_to_string(path){
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| panic!("{} is not valid json: {}", path.display(), err)),
+        Err(_) => Value::Null,
+    }
+}
+
+// assets/minecraft/lang/en_us.json from the client jar, or the part of it that's checked in.
+// (translation key, english text) sorted by key so lookups can binary search
+fn language(out: &mut String, language: &Value){
+    let mut entries: Vec<(&String, &str)> = language.as_object()
+        .map(|entries| entries.iter()
+            .map(|(key, text)| (key, text.as_str().unwrap_or_else(|| panic!("translation {} is not a string", key))))
+            .collect())
+        .unwrap_or_default();
+    entries.sort();
+    writeln!(out, "    pub const LANGUAGE: &[(&str, &str)] = &[").unwrap();
+    for (key, text) in entries{
+        writeln!(out, "        ({:?}, {:?}),", key, text).unwrap();
+    }
+    writeln!(out, "    ];").unwrap();
+}
+
+// the LANGUAGE table translate components are looked up in for a version
+fn version_language(out: &mut String, versions: &[String]){
+    writeln!(out, "pub fn language(version: ProtocolVersion) -> &'static [(&'static str, &'static str)] {{").unwrap();
+    writeln!(out, "    match version {{").unwrap();
+    for version in versions{
+        let module = version.replace('.', "_");
+        writeln!(out, "        ProtocolVersion::V{} => v{}::LANGUAGE,", module, module).unwrap();
+    }
+    writeln!(out, "    }}\n}}\n").unwrap();
+}
+
+// a block in block_physics.json that blocks.json doesn't have is a typo or a block that was
+// renamed or removed, its physics would silently not apply to anything
+fn check_physics(blocks_path: &Path, blocks: &Value, physics: &Value){
+    let Some(entries) = physics["blocks"].as_object() else{
+        return;
+    };
+    let unknown: Vec<&str> = entries.keys().filter(|key| blocks.get(key.as_str()).is_none()).map(String::as_str).collect();
+    if !unknown.is_empty(){
+        panic!("the block_physics.json next to {} has blocks that aren't in it: {}", blocks_path.display(), unknown.join(", "));
+    }
+}
+
+// with_block_kind!(version, B => body) runs body with B as the Block of that version's module,
+// for what is generic over BlockKind like Physics::tick. every ProtocolVersion needs reports/
+fn version_blocks(out: &mut String, versions: &[String]){
+    writeln!(out, "macro_rules! with_block_kind {{").unwrap();
+    writeln!(out, "    ($version:expr, $block:ident => $body:expr) => {{").unwrap();
+    writeln!(out, "        match $version {{").unwrap();
+    for version in versions{
+        let module = version.replace('.', "_");
+        writeln!(out, "            $crate::version::ProtocolVersion::V{} => {{ type $block = $crate::registry::v{}::Block; $body }},", module, module).unwrap();
+    }
+    writeln!(out, "        }}\n    }};\n}}").unwrap();
+    writeln!(out, "pub(crate) use with_block_kind;\n").unwrap();
+}
+
+// tests/fixtures/blocks/, the blocks the physics and pathfinder tests build their worlds from
+fn test_blocks(out: &mut String){
+    let dir = Path::new("tests/fixtures/blocks");
+    let blocks = read_json(&dir.join("blocks.json"));
+    let physics = read_json(&dir.join("block_physics.json"));
+    check_physics(&dir.join("blocks.json"), &blocks, &physics);
+    writeln!(out, "#[cfg(test)]").unwrap();
+    writeln!(out, "#[allow(clippy::all)]").unwrap();
+    writeln!(out, "pub mod test_blocks {{").unwrap();
+    writeln!(out, "    use super::*;\n").unwrap();
+    blocks_enum(out, &blocks, &physics);
+    writeln!(out, "}}\n").unwrap();
+}
+
+// a version and the (protocol ID, identifier) entries of each of REGISTRIES in it
+type VersionEntries<'a> = (&'a str, Vec<Vec<(u32, String)>>);
+
+// the registry enums of the newest version, what the rest of the crate uses, and what they are
+// called in the others. every version in reports/ has to be a ProtocolVersion
+fn version_registries(out: &mut String, versions: &[VersionEntries<'_>]){
+    let Some((latest, latest_entries)) = versions.last() else{
+        return;
+    };
+    let names: Vec<&str> = REGISTRIES.iter().map(|(_, name)| *name).collect();
+    writeln!(out, "pub use v{}::{{{}}};\n", latest.replace('.', "_"), names.join(", ")).unwrap();
+
+    for (index, (_, name)) in REGISTRIES.iter().enumerate(){
+        let variants = variant_names(latest_entries[index].iter().map(|(_, key)| key.as_str()));
+        let variant = |key: &str| latest_entries[index].iter().zip(&variants).find(|((_, other), _)| other == key).map(|(_, variant)| variant);
+
+        writeln!(out, "#[allow(clippy::all)]").unwrap();
+        writeln!(out, "impl {} {{", name).unwrap();
+        writeln!(out, "    // None for IDs the version doesn't have, or for what newer versions took out").unwrap();
+        writeln!(out, "    #[allow(unreachable_patterns)]").unwrap();
+        writeln!(out, "    pub fn from_version_id(version: ProtocolVersion, id: u32) -> Option<Self> {{").unwrap();
+        writeln!(out, "    match version {{").unwrap();
+        for (version, entries) in versions{
+            writeln!(out, "        ProtocolVersion::V{} => match id {{", version.replace('.', "_")).unwrap();
+            for (id, key) in &entries[index]{
+                if let Some(variant) = variant(key){
+                    writeln!(out, "            {} => Some({}::{}),", id, name, variant).unwrap();
+                }
+            }
+            writeln!(out, "            _ => None,\n        }},").unwrap();
+        }
+        writeln!(out, "        _ => None,\n    }}\n    }}\n").unwrap();
+
+        writeln!(out, "    // None if the version doesn't have it yet").unwrap();
+        writeln!(out, "    #[allow(unreachable_patterns)]").unwrap();
+        writeln!(out, "    pub fn version_id(self, version: ProtocolVersion) -> Option<u32> {{").unwrap();
+        writeln!(out, "    match version {{").unwrap();
+        for (version, entries) in versions{
+            writeln!(out, "        ProtocolVersion::V{} => match self {{", version.replace('.', "_")).unwrap();
+            for (id, key) in &entries[index]{
+                if let Some(variant) = variant(key){
+                    writeln!(out, "            {}::{} => Some({}),", name, variant, id).unwrap();
+                }
+            }
+            writeln!(out, "            _ => None,\n        }},").unwrap();
+        }
+        writeln!(out, "        _ => None,\n    }}\n    }}").unwrap();
+        writeln!(out, "}}\n").unwrap();
+    }
+}
+
+// (protocol ID, identifier) sorted by ID
+fn registry_entries(registries: &Value, registry: &str) -> Vec<(u32, String)>{
+    let mut entries: Vec<(u32, String)> = registries[registry]["entries"].as_object()
+        .map(|entries| entries.iter().map(|(key, entry)| {
+            let id = entry["protocol_id"].as_u64().unwrap_or_else(|| panic!("{} in {} has no protocol_id", key, registry));
+            (id as u32, key.clone())
+        }).collect())
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+// minecraft:area_effect_cloud => AreaEffectCloud, block.note_block.bell => BlockNoteBlockBell
+fn variant_name(key: &str) -> String{
+    let key = key.strip_prefix("minecraft:").unwrap_or(key);
+    let mut name = String::new();
+    for word in key.split(|char: char| !char.is_ascii_alphanumeric()).filter(|word| !word.is_empty()){
+        let mut chars = word.chars();
+        if let Some(first) = chars.next(){
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.starts_with(|char: char| char.is_ascii_digit()){
+        name.insert(0, 'N');
+    }
+    name
+}
+
+fn variant_names<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<String>{
+    let mut seen = BTreeMap::new();
+    keys.map(|key| {
+        let name = variant_name(key);
+        if let Some(other) = seen.insert(name.clone(), key){
+            panic!("{} and {} both turn into {}", other, key, name);
+        }
+        name
+    }).collect()
+}
+
+fn registry_enum(out: &mut String, name: &str, entries: &[(u32, String)]){
+    let variants = variant_names(entries.iter().map(|(_, key)| key.as_str()));
+
+    writeln!(out, "    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "    pub enum {} {{", name).unwrap();
+    for variant in &variants{
+        writeln!(out, "        {},", variant).unwrap();
+    }
+    writeln!(out, "    }}\n").unwrap();
+
+    writeln!(out, "    impl {} {{", name).unwrap();
+    writeln!(out, "        pub const ALL: &'static [{}] = &[{}];\n", name, variants.iter().map(|variant| format!("{}::{}", name, variant)).collect::<Vec<_>>().join(", ")).unwrap();
+
+    writeln!(out, "        pub fn from_id(id: u32) -> Option<Self> {{").unwrap();
+    writeln!(out, "        match id {{").unwrap();
+    for ((id, _), variant) in entries.iter().zip(&variants){
+        writeln!(out, "            {} => Some({}::{}),", id, name, variant).unwrap();
+    }
+    writeln!(out, "            _ => None,\n        }}\n        }}\n").unwrap();
+
+    writeln!(out, "        pub fn id(self) -> u32 {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for ((id, _), variant) in entries.iter().zip(&variants){
+        writeln!(out, "            {}::{} => {},", name, variant, id).unwrap();
+    }
+    writeln!(out, "        }}\n        }}\n").unwrap();
+
+    writeln!(out, "        pub fn name(self) -> &'static str {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for ((_, key), variant) in entries.iter().zip(&variants){
+        writeln!(out, "            {}::{} => {:?},", name, variant, key).unwrap();
+    }
+    writeln!(out, "        }}\n        }}\n").unwrap();
+
+    writeln!(out, "        pub fn from_name(name: &str) -> Option<Self> {{").unwrap();
+    writeln!(out, "        Self::ALL.iter().copied().find(|entry| entry.name() == name || entry.name().strip_prefix(\"minecraft:\") == Some(name))").unwrap();
+    writeln!(out, "        }}\n").unwrap();
+
+    writeln!(out, "        pub fn display_name(self) -> String {{").unwrap();
+    writeln!(out, "        display_name(self.name())").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+}
+
+// entity_metadata.json has classes, each with a parent and the fields it adds after the ones of
+// its parents, and which class every entity type is. without the file every type has no fields
+fn metadata_fields(out: &mut String, metadata: &Value, entity_types: &[(u32, String)]){
+    let empty = serde_json::Map::new();
+    let classes = metadata["classes"].as_object().unwrap_or(&empty);
+    let entities = metadata["entities"].as_object().unwrap_or(&empty);
+
+    // abstract classes only matter through their children
+    for class in classes.keys().filter(|class| entities.values().any(|other| other.as_str() == Some(class.as_str()))){
+        let mut chain = vec![class.as_str()];
+        while let Some(parent) = classes[*chain.last().unwrap()]["parent"].as_str(){
+            assert!(classes.contains_key(parent), "metadata class {} has an unknown parent {}", chain.last().unwrap(), parent);
+            assert!(!chain.contains(&parent), "metadata class {} is its own parent", parent);
+            chain.push(parent);
+        }
+
+        let mut fields: Vec<(String, String)> = Vec::new();
+        for class in chain.iter().rev(){
+            for field in classes[*class]["fields"].as_array().map(Vec::as_slice).unwrap_or_default(){
+                let (Some(name), Some(kind)) = (field[0].as_str(), field[1].as_str()) else{
+                    panic!("metadata class {} has a field that isn't [name, kind]", class);
+                };
+                assert!(fields.iter().all(|(other, _)| other != name), "metadata class {} has two {} fields", chain[0], name);
+                fields.push((name.to_string(), kind.to_string()));
+            }
+        }
+
+        let fields: Vec<String> = fields.iter().enumerate().map(|(index, (name, kind))| {
+            format!("MetadataField {{ index: {}, name: {:?}, kind: MetadataKind::{} }}", index, name, variant_name(kind))
+        }).collect();
+        writeln!(out, "    const METADATA_{}: &[MetadataField] = &[{}];", class.to_ascii_uppercase(), fields.join(", ")).unwrap();
+    }
+
+    // a function and not a method, EntityType::metadata_fields is the one that takes a version
+    let variants = variant_names(entity_types.iter().map(|(_, key)| key.as_str()));
+    writeln!(out, "\n    // by index, what SetEntityMetadata entries mean for this type").unwrap();
+    writeln!(out, "    pub fn metadata_fields(kind: EntityType) -> &'static [MetadataField] {{").unwrap();
+    writeln!(out, "    match kind {{").unwrap();
+    for (key, class) in entities{
+        let class = class.as_str().unwrap_or_else(|| panic!("metadata class of {} is not a string", key));
+        assert!(classes.contains_key(class), "{} has an unknown metadata class {}", key, class);
+        let Some(variant) = entity_types.iter().zip(&variants).find(|((_, other), _)| other == key).map(|(_, variant)| variant) else{
+            panic!("{} in entity_metadata.json is not an entity type", key);
+        };
+        writeln!(out, "        EntityType::{} => METADATA_{},", variant, class.to_ascii_uppercase()).unwrap();
+    }
+    if entities.len() < entity_types.len(){
+        writeln!(out, "        _ => &[],").unwrap();
+    }
+    writeln!(out, "    }}\n    }}\n").unwrap();
+}
+
+// the metadata fields of the newest version's EntityType on a server of any version, through
+// the metadata_fields of that version's module
+fn version_metadata(out: &mut String, versions: &[VersionEntries<'_>]){
+    if versions.is_empty(){
+        return;
+    }
+    writeln!(out, "#[allow(clippy::all)]").unwrap();
+    writeln!(out, "impl EntityType {{").unwrap();
+    writeln!(out, "    // by index, what SetEntityMetadata entries mean for this type on a server of that version").unwrap();
+    writeln!(out, "    #[allow(unreachable_patterns)]").unwrap();
+    writeln!(out, "    pub fn metadata_fields(self, version: ProtocolVersion) -> &'static [MetadataField] {{").unwrap();
+    writeln!(out, "    match version {{").unwrap();
+    for (version, _) in versions{
+        let module = format!("v{}", version.replace('.', "_"));
+        writeln!(out, "        ProtocolVersion::V{} => self.version_id(version).and_then({}::EntityType::from_id).map_or(&[], {}::metadata_fields),", version.replace('.', "_"), module, module).unwrap();
+    }
+    writeln!(out, "        _ => &[],\n    }}\n    }}\n").unwrap();
+
+    writeln!(out, "    pub fn metadata_field(self, version: ProtocolVersion, name: &str) -> Option<MetadataField> {{").unwrap();
+    writeln!(out, "    self.metadata_fields(version).iter().copied().find(|field| field.name == name)").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}\n").unwrap();
+}
+
+struct BlockReport{
+    key: String,
+    properties: Vec<(String, Vec<String>)>,
+    min_state: u32,
+    max_state: u32,
+    default_state: u32,
+}
+
+fn blocks_enum(out: &mut String, blocks: &Value, physics: &Value){
+    let mut reports = Vec::new();
+    if let Some(blocks) = blocks.as_object(){
+        for (key, block) in blocks{
+            reports.push(block_report(key, block));
+        }
+    }
+    reports.sort_by_key(|report| report.min_state);
+
+    let mut property_names: Vec<&str> = reports.iter().flat_map(|report| report.properties.iter().map(|(name, _)| name.as_str())).collect();
+    property_names.sort();
+    property_names.dedup();
+    let property_variants = variant_names(property_names.iter().copied());
+
+    writeln!(out, "    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "    pub enum PropertyName {{").unwrap();
+    for variant in &property_variants{
+        writeln!(out, "        {},", variant).unwrap();
+    }
+    writeln!(out, "    }}\n").unwrap();
+
+    writeln!(out, "    impl PropertyName {{").unwrap();
+    writeln!(out, "        pub fn name(self) -> &'static str {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for (name, variant) in property_names.iter().zip(&property_variants){
+        writeln!(out, "            PropertyName::{} => {:?},", variant, name).unwrap();
+    }
+    writeln!(out, "        }}\n        }}").unwrap();
+    writeln!(out, "    }}\n").unwrap();
+
+    let variants = variant_names(reports.iter().map(|report| report.key.as_str()));
+    writeln!(out, "    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+    writeln!(out, "    pub enum Block {{").unwrap();
+    for variant in &variants{
+        writeln!(out, "        {},", variant).unwrap();
+    }
+    writeln!(out, "    }}\n").unwrap();
+
+    writeln!(out, "    impl Block {{").unwrap();
+    writeln!(out, "        pub const ALL: &'static [Block] = &[{}];\n", variants.iter().map(|variant| format!("Block::{}", variant)).collect::<Vec<_>>().join(", ")).unwrap();
+
+    writeln!(out, "        pub fn from_state(state: u32) -> Option<Self> {{").unwrap();
+    writeln!(out, "        match state {{").unwrap();
+    for (report, variant) in reports.iter().zip(&variants){
+        writeln!(out, "            {}..={} => Some(Block::{}),", report.min_state, report.max_state, variant).unwrap();
+    }
+    writeln!(out, "            _ => None,\n        }}\n        }}\n").unwrap();
+
+    writeln!(out, "        pub fn name(self) -> &'static str {{").unwrap();
+    writeln!(out, "        self.info().name\n        }}\n").unwrap();
+
+    writeln!(out, "        pub fn from_name(name: &str) -> Option<Self> {{").unwrap();
+    writeln!(out, "        Self::ALL.iter().copied().find(|block| block.name() == name || block.name().strip_prefix(\"minecraft:\") == Some(name))").unwrap();
+    writeln!(out, "        }}\n").unwrap();
+
+    writeln!(out, "        pub fn display_name(self) -> String {{").unwrap();
+    writeln!(out, "        display_name(self.name())\n        }}\n").unwrap();
+
+    writeln!(out, "        pub fn info(self) -> &'static BlockInfo<PropertyName> {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    for (report, variant) in reports.iter().zip(&variants){
+        let properties: Vec<String> = report.properties.iter().map(|(name, values)| {
+            format!("(PropertyName::{}, &[{}])", variant_name(name), values.iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>().join(", "))
+        }).collect();
+        writeln!(out, "            Block::{} => &BlockInfo {{ name: {:?}, min_state: {}, max_state: {}, default_state: {}, properties: &[{}] }},",
+            variant, report.key, report.min_state, report.max_state, report.default_state, properties.join(", ")).unwrap();
+    }
+    writeln!(out, "        }}\n        }}\n").unwrap();
+
+    block_physics(out, &reports, &variants, physics);
+    writeln!(out, "    }}\n").unwrap();
+
+    writeln!(out, "    pub type BlockState = GenericBlockState<Block>;\n").unwrap();
+    writeln!(out, "    impl BlockKind for Block {{").unwrap();
+    writeln!(out, "        type Property = PropertyName;").unwrap();
+    writeln!(out, "        fn from_state(state: u32) -> Option<Self> {{ Block::from_state(state) }}").unwrap();
+    writeln!(out, "        fn info(self) -> &'static BlockInfo<PropertyName> {{ Block::info(self) }}").unwrap();
+    writeln!(out, "        fn physics(self) -> &'static BlockPhysics<PropertyName> {{ Block::physics(self) }}").unwrap();
+    writeln!(out, "        fn property_name(property: PropertyName) -> &'static str {{ property.name() }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+}
+
+// block_physics.json has friction, speed and jump factors, fluids and collision shapes for the
+// blocks that aren't plain full cubes. blocks it has that aren't in the blocks report are skipped,
+// see missing_blocks
+fn block_physics(out: &mut String, reports: &[BlockReport], variants: &[String], physics: &Value){
+    let empty = serde_json::Map::new();
+    let entries = physics["blocks"].as_object().unwrap_or(&empty);
+
+    writeln!(out, "        pub fn physics(self) -> &'static BlockPhysics<PropertyName> {{").unwrap();
+    writeln!(out, "        match self {{").unwrap();
+    let mut covered = 0;
+    for (report, variant) in reports.iter().zip(variants){
+        let Some(entry) = entries.get(&report.key) else{
+            continue;
+        };
+        covered += 1;
+        let float = |name: &str, default: f64| entry[name].as_f64().unwrap_or(default);
+        let fluid = match entry["fluid"].as_str(){
+            None => "None".to_string(),
+            Some("water") => "Some(Fluid::Water)".to_string(),
+            Some("lava") => "Some(Fluid::Lava)".to_string(),
+            Some(other) => panic!("{} has an unknown fluid {}", report.key, other),
+        };
+        let stuck = match entry["stuck"].as_array(){
+            Some(stuck) => format!("Some([{:?}, {:?}, {:?}])", stuck[0].as_f64().unwrap(), stuck[1].as_f64().unwrap(), stuck[2].as_f64().unwrap()),
+            None => "None".to_string(),
+        };
+        let shape = match &entry["shape"]{
+            Value::Null => "None".to_string(),
+            Value::String(shape) if shape == "empty" => "Some(&[])".to_string(),
+            Value::Array(rules) => format!("Some(&[{}])", rules.iter().map(|rule| shape_rule(report, rule)).collect::<Vec<_>>().join(", ")),
+            other => panic!("{} has a shape that is neither \"empty\" nor a list of rules: {}", report.key, other),
+        };
+        writeln!(out, "            Block::{} => &BlockPhysics {{ friction: {:?}_f32, speed_factor: {:?}_f32, jump_factor: {:?}_f32, bounce: {:?}, sticky: {}, climbable: {}, fluid: {}, stuck: {}, shape: {} }},",
+            variant, float("friction", 0.6), float("speed_factor", 1.0), float("jump_factor", 1.0), float("bounce", 0.0),
+            entry["sticky"].as_bool().unwrap_or(false), entry["climbable"].as_bool().unwrap_or(false), fluid, stuck, shape).unwrap();
+    }
+    if covered < reports.len(){
+        writeln!(out, "            _ => &BlockPhysics::DEFAULT,").unwrap();
+    }
+    writeln!(out, "        }}\n        }}").unwrap();
+}
+
+// {"when": {property: "value|other value"}, "boxes": [[x1, y1, z1, x2, y2, z2] in pixels]}
+fn shape_rule(report: &BlockReport, rule: &Value) -> String{
+    let empty = serde_json::Map::new();
+    let when: Vec<String> = rule["when"].as_object().unwrap_or(&empty).iter().map(|(name, values)| {
+        let Some((_, known)) = report.properties.iter().find(|(other, _)| other == name) else{
+            panic!("{} has a shape for a property it doesn't have: {}", report.key, name);
+        };
+        let values: Vec<&str> = values.as_str().unwrap_or_else(|| panic!("{} {} in a shape isn't a string", report.key, name)).split('|').collect();
+        for value in &values{
+            assert!(known.iter().any(|other| other == value), "{} has a shape for {}={}, which it can't be", report.key, name, value);
+        }
+        format!("(PropertyName::{}, &[{}])", variant_name(name), values.iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>().join(", "))
+    }).collect();
+    let boxes: Vec<String> = rule["boxes"].as_array().unwrap_or_else(|| panic!("{} has a shape rule without boxes", report.key)).iter().map(|bounds| {
+        let bounds: Vec<String> = bounds.as_array().filter(|bounds| bounds.len() == 6)
+            .unwrap_or_else(|| panic!("{} has a box that isn't 6 numbers", report.key))
+            .iter().map(|value| format!("{:?}", value.as_f64().unwrap() / 16.0)).collect();
+        format!("[{}]", bounds.join(", "))
+    }).collect();
+    format!("ShapeRule {{ when: &[{}], boxes: &[{}] }}", when.join(", "), boxes.join(", "))
+}
+
+fn block_report(key: &str, block: &Value) -> BlockReport{
+    let properties: Vec<(String, Vec<String>)> = block["properties"].as_object()
+        .map(|properties| properties.iter().map(|(name, values)| {
+            let values = values.as_array().unwrap_or_else(|| panic!("{} property {} has no values", key, name))
+                .iter().map(|value| value.as_str().unwrap_or_default().to_string()).collect();
+            (name.clone(), values)
+        }).collect())
+        .unwrap_or_default();
+
+    let states = block["states"].as_array().unwrap_or_else(|| panic!("{} has no states", key));
+    let ids: Vec<u32> = states.iter().map(|state| state["id"].as_u64().unwrap_or_else(|| panic!("{} has a state without an id", key)) as u32).collect();
+    let min_state = *ids.iter().min().unwrap_or_else(|| panic!("{} has no states", key));
+    let max_state = *ids.iter().max().unwrap_or(&min_state);
+    let default_state = states.iter().find(|state| state["default"].as_bool() == Some(true))
+        .and_then(|state| state["id"].as_u64())
+        .map_or(min_state, |id| id as u32);
+
+    // src/registry.rs works out a state's properties from its offset, with the last property
+    // changing fastest. that is how vanilla numbers them, but it's cheap to make sure
+    for (state, id) in states.iter().zip(&ids){
+        let mut offset = 0;
+        for (name, values) in &properties{
+            let value = state["properties"][name].as_str().unwrap_or_else(|| panic!("{} state {} has no {}", key, id, name));
+            let index = values.iter().position(|other| other == value).unwrap_or_else(|| panic!("{} state {} has an unknown {}: {}", key, id, name, value));
+            offset = offset * values.len() + index;
+        }
+        assert_eq!(min_state + offset as u32, *id, "{} state {} is not where its properties say it should be", key, id);
+    }
+
+    BlockReport{key: key.to_string(), properties, min_state, max_state, default_state}
+}
@@ -0,0 +1,294 @@
+use {
+    tokio::{
+        io::{
+            AsyncReadExt,
+            AsyncWriteExt,
+        },
+        net::{
+            TcpStream,
+            ToSocketAddrs,
+            tcp::{
+                OwnedReadHalf,
+                OwnedWriteHalf,
+            },
+        },
+    },
+    crate::{
+        codec::{
+            Encode,
+            Packet,
+            ProtocolError,
+            packet_body,
+        },
+        encryption::{
+            Decryptor,
+            Encryptor,
+            StreamCipher,
+        },
+        frame::{
+            FrameBuffer,
+            MAX_FRAME_SIZE,
+            PacketCodec,
+            READ_CHUNK,
+        },
+    }
+};
+
+// the tokio version of connection::Connection, same framing, compression and encryption
+// (it uses the same FrameBuffer and PacketCodec) but nothing here blocks a thread, so one
+// runtime can drive thousands of these. into_split gives independent read and write halves
+pub struct AsyncConnection{
+    reader: PacketReadHalf,
+    writer: PacketWriteHalf,
+}
+
+impl AsyncConnection{
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, ProtocolError>{
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(AsyncConnection::from_stream(stream))
+    }
+
+    pub fn from_stream(stream: TcpStream) -> Self{
+        let (read, write) = stream.into_split();
+        AsyncConnection{
+            reader: PacketReadHalf{inner: read, frames: FrameBuffer::new(MAX_FRAME_SIZE), codec: PacketCodec::new(), decryptor: None},
+            writer: PacketWriteHalf{inner: write, codec: PacketCodec::new(), encryptor: None},
+        }
+    }
+
+    pub fn set_compression(&mut self, threshold: i32){
+        self.reader.set_compression(threshold);
+        self.writer.set_compression(threshold);
+    }
+
+    pub fn is_encrypted(&self) -> bool{
+        self.writer.encryptor.is_some()
+    }
+
+    // everything after Encryption Response is encrypted in both directions
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) -> Result<(), ProtocolError>{
+        if !self.reader.frames.buffered().is_empty(){
+            return Err(ProtocolError::InvalidValue("server sent data before encryption was turned on".to_string()));
+        }
+
+        let (encryptor, decryptor) = StreamCipher::new(shared_secret).split();
+        self.writer.encryptor = Some(encryptor);
+        self.reader.decryptor = Some(decryptor);
+        Ok(())
+    }
+
+    pub async fn send<P: Packet + Encode>(&mut self, packet: &P) -> Result<(), ProtocolError>{
+        self.writer.send(packet).await
+    }
+
+    pub async fn send_raw(&mut self, packet: &[u8]) -> Result<(), ProtocolError>{
+        self.writer.send_raw(packet).await
+    }
+
+    pub async fn receive(&mut self) -> Result<(i32, Vec<u8>), ProtocolError>{
+        self.reader.receive().await
+    }
+
+    pub fn into_split(self) -> (PacketReadHalf, PacketWriteHalf){
+        (self.reader, self.writer)
+    }
+}
+
+pub struct PacketReadHalf{
+    inner: OwnedReadHalf,
+    frames: FrameBuffer,
+    codec: PacketCodec,
+    decryptor: Option<Decryptor>,
+}
+
+impl PacketReadHalf{
+    // after a split each half keeps its own copy of the codec, so both have to be told
+    pub fn set_compression(&mut self, threshold: i32){
+        self.codec.set_compression(threshold);
+    }
+
+    pub fn codec(&self) -> &PacketCodec{
+        &self.codec
+    }
+
+    // next packet as (packet ID, body), cancel safe since partial reads stay in the buffer
+    pub async fn receive(&mut self) -> Result<(i32, Vec<u8>), ProtocolError>{
+        let mut chunk = [0u8; READ_CHUNK];
+        loop{
+            if let Some(frame) = self.frames.next_frame()?{
+                return self.codec.decode_frame(&frame);
+            }
+
+            let size = self.inner.read(&mut chunk).await?;
+            if size == 0{
+                return Err(self.frames.eof_error());
+            }
+            if let Some(decryptor) = &mut self.decryptor{
+                decryptor.decrypt(&mut chunk[..size]);
+            }
+            self.frames.extend(&chunk[..size]);
+        }
+    }
+}
+
+pub struct PacketWriteHalf{
+    inner: OwnedWriteHalf,
+    codec: PacketCodec,
+    encryptor: Option<Encryptor>,
+}
+
+impl PacketWriteHalf{
+    pub fn set_compression(&mut self, threshold: i32){
+        self.codec.set_compression(threshold);
+    }
+
+    pub fn codec(&self) -> &PacketCodec{
+        &self.codec
+    }
+
+    pub async fn send<P: Packet + Encode>(&mut self, packet: &P) -> Result<(), ProtocolError>{
+        self.send_raw(&packet_body(packet)?).await
+    }
+
+    // packet ID + body that was already encoded somewhere else
+    pub async fn send_raw(&mut self, packet: &[u8]) -> Result<(), ProtocolError>{
+        let mut frame = self.codec.encode_packet(packet)?;
+        if let Some(encryptor) = &mut self.encryptor{
+            encryptor.encrypt(&mut frame);
+        }
+        self.inner.write_all(&frame).await?;
+        Ok(())
+    }
+
+    pub async fn shutdown(&mut self) -> Result<(), ProtocolError>{
+        self.inner.shutdown().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        std::time::Duration,
+        tokio::net::TcpListener,
+    };
+
+    const SECRET: [u8; 16] = *b"0123456789abcdef";
+
+    async fn pair() -> (AsyncConnection, TcpStream){
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(AsyncConnection::connect(addr), listener.accept());
+        (client.unwrap(), server.unwrap().0)
+    }
+
+    // packet ID + body, the big one is well over the compression threshold
+    fn packets() -> Vec<Vec<u8>>{
+        let big: Vec<u8> = [0x24].into_iter().chain((0..5000).map(|i| (i % 7) as u8)).collect();
+        vec![vec![0x00], vec![0x12, 1, 2, 3], big, vec![0x7f; 64]]
+    }
+
+    async fn round_trip(client: AsyncConnection, server: AsyncConnection){
+        let (mut client_read, mut client_write) = client.into_split();
+        let (mut server_read, mut server_write) = server.into_split();
+
+        for packet in packets(){
+            client_write.send_raw(&packet).await.unwrap();
+        }
+        for packet in packets(){
+            let (id, body) = server_read.receive().await.unwrap();
+            assert_eq!(id, packet[0] as i32);
+            assert_eq!(body, packet[1..]);
+            server_write.send_raw(&packet).await.unwrap();
+        }
+        for packet in packets(){
+            let (id, body) = client_read.receive().await.unwrap();
+            assert_eq!(id, packet[0] as i32);
+            assert_eq!(body, packet[1..]);
+        }
+
+        client_write.shutdown().await.unwrap();
+        assert!(server_read.receive().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn compressed_round_trip(){
+        let (mut client, server) = pair().await;
+        let mut server = AsyncConnection::from_stream(server);
+        client.set_compression(256);
+        server.set_compression(256);
+        round_trip(client, server).await;
+    }
+
+    #[tokio::test]
+    async fn encrypted_round_trip(){
+        let (mut client, server) = pair().await;
+        let mut server = AsyncConnection::from_stream(server);
+        client.set_compression(256);
+        server.set_compression(256);
+        client.enable_encryption(&SECRET).unwrap();
+        server.enable_encryption(&SECRET).unwrap();
+        assert!(client.is_encrypted());
+        round_trip(client, server).await;
+    }
+
+    // the server side is a plain socket here, so what goes over the wire can be checked
+    // and a frame can arrive in as many pieces as we like
+    #[tokio::test]
+    async fn frames_split_across_writes(){
+        let (mut client, mut server) = pair().await;
+        client.set_compression(256);
+        client.enable_encryption(&SECRET).unwrap();
+        let (mut client_read, mut client_write) = client.into_split();
+
+        let mut codec = PacketCodec::new();
+        codec.set_compression(256);
+        let (mut encryptor, mut decryptor) = StreamCipher::new(&SECRET).split();
+
+        let mut wire = Vec::new();
+        for packet in packets(){
+            wire.extend(codec.encode_packet(&packet).unwrap());
+        }
+        encryptor.encrypt(&mut wire);
+
+        let reader = tokio::spawn(async move {
+            let mut received = Vec::new();
+            for _ in packets(){
+                received.push(client_read.receive().await.unwrap());
+            }
+            received
+        });
+        let mut rest = &wire[..];
+        for size in [1, 1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 377, 610].into_iter().cycle(){
+            let (piece, remaining) = rest.split_at(size.min(rest.len()));
+            server.write_all(piece).await.unwrap();
+            server.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            rest = remaining;
+            if rest.is_empty(){
+                break;
+            }
+        }
+        let received = reader.await.unwrap();
+        for (packet, (id, body)) in packets().into_iter().zip(received){
+            assert_eq!(id, packet[0] as i32);
+            assert_eq!(body, packet[1..]);
+        }
+
+        // and the other way, decrypted and decoded by hand
+        let big = packets().swap_remove(2);
+        client_write.send_raw(&big).await.unwrap();
+        client_write.shutdown().await.unwrap();
+        let mut raw = Vec::new();
+        server.read_to_end(&mut raw).await.unwrap();
+        assert!(raw.len() < big.len() / 4, "{} bytes for a {} byte packet, not compressed", raw.len(), big.len());
+        decryptor.decrypt(&mut raw);
+        let mut frames = FrameBuffer::new(MAX_FRAME_SIZE);
+        frames.extend(&raw);
+        let (id, body) = codec.decode_frame(&frames.next_frame().unwrap().unwrap()).unwrap();
+        assert_eq!(id, 0x24);
+        assert_eq!(body, big[1..]);
+    }
+}
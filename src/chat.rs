@@ -0,0 +1,196 @@
+use {
+    std::time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+    crate::codec::ProtocolError,
+};
+
+// how many of the last messages every chat message acknowledges
+pub const LAST_SEEN_MESSAGES: usize = 20;
+// the vanilla client sends Message Acknowledgment once it has seen more messages than this
+// without sending one of its own
+pub const ACKNOWLEDGE_AFTER: i32 = 64;
+
+// vanilla's LastSeenMessagesTracker. every signed Player Chat goes in, every chat message or
+// command we send says which of the last 20 we saw and how many came in since the previous one.
+// the server kicks us when that doesn't add up, signed chat or not
+#[derive(Debug, Clone)]
+pub struct LastSeenMessages{
+    tracked: Vec<Option<Box<[u8; 256]>>>, // ring buffer, the oldest at tail
+    tail: usize,
+    offset: i32, // messages since the last update we sent
+    last: Option<Box<[u8; 256]>>,
+}
+
+impl Default for LastSeenMessages{
+    fn default() -> Self{
+        LastSeenMessages{
+            tracked: vec![None; LAST_SEEN_MESSAGES],
+            tail: 0,
+            offset: 0,
+            last: None,
+        }
+    }
+}
+
+impl LastSeenMessages{
+    // a message signature from Player Chat, false if it's the same as the last one
+    pub fn add(&mut self, signature: &[u8; 256]) -> bool{
+        if self.last.as_deref() == Some(signature){
+            return false;
+        }
+        self.last = Some(Box::new(*signature));
+        self.tracked[self.tail] = Some(Box::new(*signature));
+        self.tail = (self.tail + 1) % LAST_SEEN_MESSAGES;
+        self.offset += 1;
+        true
+    }
+
+    // messages seen since we last told the server
+    pub fn offset(&self) -> i32{
+        self.offset
+    }
+
+    // the count for Message Acknowledgment, Some once there are too many unacknowledged ones
+    pub fn acknowledgment(&mut self) -> Option<i32>{
+        (self.offset > ACKNOWLEDGE_AFTER).then(|| std::mem::take(&mut self.offset))
+    }
+
+    // the message count and acknowledged bits for Chat Message / Chat Command, starts counting again
+    pub fn update(&mut self) -> (i32, [u8; 3]){
+        let offset = std::mem::take(&mut self.offset);
+        let mut acknowledged = [0; 3];
+        for i in 0..LAST_SEEN_MESSAGES{
+            if self.tracked[(self.tail + i) % LAST_SEEN_MESSAGES].is_some(){
+                acknowledged[i / 8] |= 1 << (i % 8);
+            }
+        }
+        (offset, acknowledged)
+    }
+
+    // the signatures update acknowledges, oldest first. signing a message needs them
+    pub fn signatures(&self) -> impl Iterator<Item = &[u8; 256]>{
+        (0..LAST_SEEN_MESSAGES).filter_map(|i| self.tracked[(self.tail + i) % LAST_SEEN_MESSAGES].as_deref())
+    }
+}
+
+// what the vanilla server accepts in a chat message: 256 characters at most, no section signs,
+// control characters or DEL (it kicks us for those)
+pub fn check_message(message: &str) -> Result<(), ProtocolError>{
+    let length = message.chars().count();
+    if length > 256{
+        return Err(ProtocolError::StringTooLong{max: 256, length});
+    }
+    if let Some(illegal) = message.chars().find(|&c| c == '§' || c < ' ' || c == '\u{7f}'){
+        return Err(ProtocolError::InvalidValue(format!("illegal character {:?} in chat message", illegal)));
+    }
+    Ok(())
+}
+
+// for the timestamp field of chat packets
+pub fn timestamp() -> i64{
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn signature(n: u32) -> [u8; 256]{
+        let mut signature = [0; 256];
+        signature[..4].copy_from_slice(&n.to_be_bytes());
+        signature
+    }
+
+    #[test]
+    fn newest_messages_are_the_highest_bits(){
+        let mut last_seen = LastSeenMessages::default();
+        assert_eq!(last_seen.update(), (0, [0, 0, 0]));
+
+        for n in 0..3{
+            assert!(last_seen.add(&signature(n)));
+        }
+        // the bitset starts at the oldest slot of the ring buffer, which is still empty
+        assert_eq!(last_seen.update(), (3, [0x00, 0x00, 0b1110]));
+        // acknowledged messages stay acknowledged, only the count starts again
+        assert_eq!(last_seen.update(), (0, [0x00, 0x00, 0b1110]));
+
+        let signatures: Vec<u32> = last_seen.signatures().map(|signature| u32::from_be_bytes(signature[..4].try_into().unwrap())).collect();
+        assert_eq!(signatures, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn ring_buffer_wraps_after_20(){
+        let mut last_seen = LastSeenMessages::default();
+        for n in 0..LAST_SEEN_MESSAGES as u32 - 1{
+            last_seen.add(&signature(n));
+        }
+        assert_eq!(last_seen.update(), (19, [0xFE, 0xFF, 0x0F]));
+
+        last_seen.add(&signature(19));
+        assert_eq!(last_seen.update(), (1, [0xFF, 0xFF, 0x0F]));
+
+        // 27 messages in total, the 7 oldest fell out and the bitset never grows past 20 bits
+        for n in 20..27{
+            last_seen.add(&signature(n));
+        }
+        assert_eq!(last_seen.update(), (7, [0xFF, 0xFF, 0x0F]));
+        let signatures: Vec<u32> = last_seen.signatures().map(|signature| u32::from_be_bytes(signature[..4].try_into().unwrap())).collect();
+        assert_eq!(signatures, (7..27).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn duplicate_signatures(){
+        let mut last_seen = LastSeenMessages::default();
+        assert!(last_seen.add(&signature(1)));
+        // the same signature twice in a row is only tracked once
+        assert!(!last_seen.add(&signature(1)));
+        assert_eq!(last_seen.offset(), 1);
+
+        // but it counts again once something else came in between
+        assert!(last_seen.add(&signature(2)));
+        assert!(last_seen.add(&signature(1)));
+        assert_eq!(last_seen.update(), (3, [0x00, 0x00, 0b1110]));
+        assert!(!last_seen.add(&signature(1)));
+        assert_eq!(last_seen.offset(), 0);
+    }
+
+    #[test]
+    fn acknowledgment_threshold(){
+        let mut last_seen = LastSeenMessages::default();
+        for n in 0..ACKNOWLEDGE_AFTER as u32{
+            last_seen.add(&signature(n));
+            assert_eq!(last_seen.acknowledgment(), None, "after {} messages", n + 1);
+        }
+        assert_eq!(last_seen.offset(), 64);
+
+        // the 65th unacknowledged message is one too many
+        last_seen.add(&signature(64));
+        assert_eq!(last_seen.acknowledgment(), Some(65));
+        assert_eq!(last_seen.offset(), 0);
+        assert_eq!(last_seen.acknowledgment(), None);
+
+        // sending a chat message resets the count too
+        for n in 100..160{
+            last_seen.add(&signature(n));
+        }
+        assert_eq!(last_seen.update().0, 60);
+        for n in 200..210{
+            last_seen.add(&signature(n));
+        }
+        assert_eq!(last_seen.acknowledgment(), None);
+    }
+
+    #[test]
+    fn message_rules(){
+        assert!(check_message("hello world").is_ok());
+        assert!(check_message(&"a".repeat(256)).is_ok());
+        assert!(check_message(&"é".repeat(256)).is_ok());
+        assert!(matches!(check_message(&"a".repeat(257)), Err(ProtocolError::StringTooLong{max: 256, length: 257})));
+        for illegal in ["§cred", "new\nline", "tab\t", "del\u{7f}", "\0"]{
+            assert!(matches!(check_message(illegal), Err(ProtocolError::InvalidValue(_))), "{:?}", illegal);
+        }
+        assert!(timestamp() > 0);
+    }
+}
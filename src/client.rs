@@ -0,0 +1,1247 @@
+use {
+    std::{
+        sync::{
+            Arc,
+            Mutex,
+            MutexGuard,
+        },
+        time::{
+            Duration,
+            SystemTime,
+        },
+    },
+    tokio::{
+        sync::mpsc,
+        task::JoinHandle,
+        time::{
+            MissedTickBehavior,
+            interval,
+            timeout,
+        },
+    },
+    crate::{
+        async_connection::{
+            AsyncConnection,
+            PacketReadHalf,
+            PacketWriteHalf,
+        },
+        chat,
+        codec::{
+            ConnectionState,
+            Encode,
+            Packet,
+            PacketReader,
+            ProtocolError,
+            Vec3,
+            packet_body_for,
+            play_packet_body,
+        },
+        encryption,
+        event::{
+            Event,
+            Handler,
+        },
+        microsoft::{
+            DeviceCodeCallback,
+            MicrosoftAuth,
+        },
+        pathfinder::{
+            Goal,
+            NavigatorAction,
+        },
+        player::{
+            LocalPlayer,
+            MovePacket,
+        },
+        packets::{
+            AcknowledgeBlockChange,
+            AcknowledgeConfiguration,
+            AcknowledgeFinishConfiguration,
+            BlockUpdate,
+            ChunkBatchFinished,
+            ChunkBatchReceived,
+            ChatCommand,
+            ChatMessage,
+            ChunkData,
+            ConfirmTeleportation,
+            DisguisedChat,
+            EncryptionRequest,
+            EncryptionResponse,
+            EntityRotation,
+            FeatureFlags,
+            Handshake,
+            JoinGame,
+            KeepAlive,
+            KeepAliveResponse,
+            LoginAcknowledged,
+            LoginDisconnect,
+            LoginPluginRequest,
+            LoginPluginResponse,
+            LoginStart,
+            LoginSuccess,
+            MessageAcknowledgment,
+            NextState,
+            PingRequest,
+            PingResponse,
+            PlayDisconnect,
+            PlayPing,
+            PlayPong,
+            PlayerChat,
+            PlayerInfoRemove,
+            PlayerInfoUpdate,
+            PluginMessage,
+            RegistryData,
+            RemoveEntities,
+            ResourcePack,
+            ResourcePackResponse,
+            ResourcePackResult,
+            Respawn,
+            SetCompression,
+            SetEntityMetadata,
+            SetEntityVelocity,
+            SetHeadRotation,
+            SpawnEntity,
+            SpawnPlayer,
+            SynchronizePlayerPosition,
+            StatusRequest,
+            StatusResponse,
+            SwingArm,
+            SystemChat,
+            TeleportEntity,
+            UnloadChunk,
+            UpdateEntityPosition,
+            UpdateEntityPositionRotation,
+            UpdateLight,
+            UpdateSectionBlocks,
+            UpdateTags,
+        },
+        registry::with_block_kind,
+        session::{
+            Account,
+            SessionServer,
+        },
+        state::{
+            ClientState,
+            PlayerInfo,
+        },
+        text::TextComponent,
+        version::{
+            Clientbound,
+            Configuration,
+            PlayPacket,
+            ProtocolVersion,
+        },
+    }
+};
+
+pub const DEFAULT_USERNAME: &str = "rust_bot";
+// what we ask for in Chunk Batch Received, the vanilla client works this out from how fast it
+// can mesh chunks, we only have to parse them
+const CHUNKS_PER_TICK: f32 = 25.0;
+// the game runs at 20 ticks a second and servers expect movement packets at that rate
+const TICK: Duration = Duration::from_millis(50);
+
+// how we prove who we are to the server
+#[derive(Debug, Clone)]
+pub enum AuthMode{
+    // no account, only works on servers with online-mode=false
+    Offline,
+    // an account we already have an access token for
+    Online{account: Account, session_server: SessionServer},
+    // logs in through microsoft first (see microsoft::MicrosoftAuth), on_code gets the device code
+    // if the user has to log in in the browser
+    Microsoft{auth: MicrosoftAuth, session_server: SessionServer, on_code: DeviceCodeCallback},
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerStatus{
+    pub json: String,
+    pub latency: Duration,
+    pub protocol: Option<i32>, // version.protocol from the json
+    pub version_name: Option<String>, // version.name, what the server list shows
+    pub players: Option<(i32, i32)>, // (online, max)
+    pub description: TextComponent, // the message of the day
+}
+
+impl ServerStatus{
+    // None if the server runs a version we can't talk to
+    pub fn version(&self) -> Option<ProtocolVersion>{
+        ProtocolVersion::from_protocol(self.protocol?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientBuilder{
+    host: String,
+    port: u16,
+    username: String,
+    version: Option<ProtocolVersion>,
+    auth: AuthMode,
+    connect_timeout: Duration,
+    login_timeout: Duration,
+    read_timeout: Duration,
+}
+
+impl ClientBuilder{
+    pub fn new(host: &str, port: u16) -> Self{
+        ClientBuilder{
+            host: host.to_string(),
+            port,
+            username: DEFAULT_USERNAME.to_string(),
+            version: None,
+            auth: AuthMode::Offline,
+            connect_timeout: Duration::from_secs(10),
+            login_timeout: Duration::from_secs(30),
+            read_timeout: Duration::from_secs(30),
+        }
+    }
+
+    // ignored for online and microsoft auth, the account decides the name there
+    pub fn username(mut self, username: &str) -> Self{
+        self.username = username.to_string();
+        self
+    }
+
+    // pins the version, without this connect asks the server which one it runs first
+    pub fn version(mut self, version: ProtocolVersion) -> Self{
+        self.version = Some(version);
+        self
+    }
+
+    pub fn auth(mut self, auth: AuthMode) -> Self{
+        self.auth = auth;
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self{
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    // how long the whole login (including the session join) may take before we give up
+    pub fn login_timeout(mut self, login_timeout: Duration) -> Self{
+        self.login_timeout = login_timeout;
+        self
+    }
+
+    // the watchdog, if the server sends nothing for this long we give up on it. vanilla servers
+    // send a keep alive every 15 seconds, so anything much shorter than that will misfire
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self{
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    // asks the server for its status json and measures the ping, doesn't log in
+    pub async fn status(&self) -> Result<ServerStatus, ProtocolError>{
+        let mut conn = self.open().await?;
+        timeout(self.login_timeout, async {
+            let protocol_version = self.version.unwrap_or_default().protocol();
+            conn.send(&Handshake{protocol_version, server_address: self.host.clone(), server_port: self.port, next_state: NextState::Status}).await?;
+            conn.send(&StatusRequest).await?;
+
+            let (_, packet) = conn.receive().await?;
+            let status = PacketReader::new(&packet).read::<StatusResponse>()?;
+
+            let sent = SystemTime::now();
+            let payload = sent.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as i64);
+            conn.send(&PingRequest{payload}).await?;
+
+            let (_, packet) = conn.receive().await?;
+            let pong = PacketReader::new(&packet).read::<PingResponse>()?;
+            if pong.payload != payload{
+                return Err(ProtocolError::InvalidValue(format!("pong payload ({}) doesn't match the ping ({})", pong.payload, payload)));
+            }
+
+            let json = serde_json::from_str::<serde_json::Value>(&status.json).unwrap_or_default();
+            let players = &json["players"];
+            Ok(ServerStatus{
+                latency: sent.elapsed().unwrap_or_default(),
+                protocol: json["version"]["protocol"].as_i64().map(|protocol| protocol as i32),
+                version_name: json["version"]["name"].as_str().map(str::to_string),
+                players: players["online"].as_i64().zip(players["max"].as_i64()).map(|(online, max)| (online as i32, max as i32)),
+                description: json.get("description").and_then(|description| TextComponent::from_value(description).ok()).unwrap_or_default(),
+                json: status.json,
+            })
+        }).await.map_err(|_| timed_out("status request"))?
+    }
+
+    // connects, logs in and hands back a client in the play state
+    pub async fn connect(self) -> Result<Client, ProtocolError>{
+        let account = match &self.auth{
+            AuthMode::Offline => None,
+            AuthMode::Online{account, session_server} => Some((account.clone(), session_server.clone())),
+            AuthMode::Microsoft{auth, session_server, on_code} => {
+                let (auth, on_code) = (auth.clone(), on_code.clone());
+                // ureq blocks, so keep it off the runtime threads
+                let account = tokio::task::spawn_blocking(move || auth.login(|code| on_code.call(code))).await
+                    .map_err(|err| ProtocolError::Auth(format!("microsoft login task failed: {}", err)))??;
+                Some((account, session_server.clone()))
+            }
+        };
+
+        let version = match self.version{
+            Some(version) => version,
+            None => self.detect_version().await?,
+        };
+
+        let mut conn = self.open().await?;
+        let success = timeout(self.login_timeout, self.login(&mut conn, account, version)).await
+            .map_err(|_| timed_out("login"))??;
+
+        let (reader, writer) = conn.into_split();
+        Ok(Client::start(reader, writer, success.username, success.uuid, version, self.read_timeout))
+    }
+
+    // the version the server says it runs in its status
+    async fn detect_version(&self) -> Result<ProtocolVersion, ProtocolError>{
+        let status = self.status().await?;
+        match (status.protocol, status.version()){
+            (_, Some(version)) => Ok(version),
+            (Some(protocol), None) => {
+                let supported = ProtocolVersion::ALL.iter().map(|version| version.name()).collect::<Vec<_>>().join(", ");
+                Err(ProtocolError::InvalidValue(format!("server runs protocol {}, supported versions are {}", protocol, supported)))
+            },
+            (None, None) => Err(ProtocolError::InvalidValue("server status doesn't say which version it runs, pin one with ClientBuilder::version".to_string())),
+        }
+    }
+
+    async fn open(&self) -> Result<AsyncConnection, ProtocolError>{
+        timeout(self.connect_timeout, AsyncConnection::connect((self.host.as_str(), self.port))).await
+            .map_err(|_| timed_out("connect"))?
+    }
+
+    async fn login(&self, conn: &mut AsyncConnection, account: Option<(Account, SessionServer)>, version: ProtocolVersion) -> Result<LoginSuccess, ProtocolError>{
+        conn.send(&Handshake{protocol_version: version.protocol(), server_address: self.host.clone(), server_port: self.port, next_state: NextState::Login}).await?;
+        let login_start = match &account{
+            Some((account, _)) => LoginStart{username: account.username.clone(), uuid: Some(account.uuid)},
+            None => LoginStart{username: self.username.clone(), uuid: None},
+        };
+        conn.send_raw(&packet_body_for(&login_start, version)?).await?;
+
+        loop{
+            let (id, packet) = conn.receive().await?;
+            let mut reader = PacketReader::new(&packet);
+
+            match id{
+                0x0 => return Err(ProtocolError::Disconnected(reader.read::<LoginDisconnect>()?.reason.to_plain(version))),
+                0x1 => { // encryption request
+                    let (account, session) = match &account{
+                        Some(online) => online.clone(),
+                        None => return Err(ProtocolError::Auth("server is in online mode, an account is needed to join".to_string())),
+                    };
+                    let request = reader.read::<EncryptionRequest>()?;
+
+                    let secret = encryption::generate_shared_secret();
+                    let hash = encryption::server_hash(&request.server_id, &secret, &request.public_key);
+                    tokio::task::spawn_blocking(move || session.join(&account, &hash)).await
+                        .map_err(|err| ProtocolError::Auth(format!("session join task failed: {}", err)))??;
+
+                    conn.send(&EncryptionResponse{
+                        shared_secret: encryption::rsa_encrypt(&request.public_key, &secret)?,
+                        verify_token: encryption::rsa_encrypt(&request.public_key, &request.verify_token)?,
+                    }).await?;
+                    conn.enable_encryption(&secret)?;
+                },
+                0x2 => {
+                    let success = reader.read::<LoginSuccess>()?;
+                    if version.has_configuration(){
+                        // the rest happens in the play loop, see Session::handle_configuration
+                        conn.send(&LoginAcknowledged).await?;
+                    }
+                    return Ok(success);
+                },
+                0x3 => conn.set_compression(reader.read::<SetCompression>()?.threshold),
+                0x4 => { // login plugin request, we don't understand any custom login flows
+                    let request = reader.read::<LoginPluginRequest>()?;
+                    conn.send(&LoginPluginResponse{message_id: request.message_id, data: None}).await?;
+                },
+                id => return Err(ProtocolError::UnknownPacket{state: ConnectionState::Login, id}),
+            }
+        }
+    }
+}
+
+// a cheap, cloneable way to send packets to the server. sending never waits, packets are
+// queued and written out by the connection's writer task in order
+#[derive(Debug, Clone)]
+pub struct ClientHandle{
+    commands: mpsc::UnboundedSender<Command>,
+    version: ProtocolVersion,
+}
+
+#[derive(Debug)]
+enum Command{
+    Packet(Vec<u8>),
+    Disconnect,
+}
+
+impl ClientHandle{
+    pub fn send<P: PlayPacket + Encode>(&self, packet: &P) -> Result<(), ProtocolError>{
+        self.send_raw(play_packet_body(packet, self.version)?)
+    }
+
+    // for the configuration state, where packets have the same ID in every version
+    pub fn send_configuration<P: Packet + Encode>(&self, packet: &P) -> Result<(), ProtocolError>{
+        self.send_raw(packet_body_for(packet, self.version)?)
+    }
+
+    pub fn version(&self) -> ProtocolVersion{
+        self.version
+    }
+
+    // packet ID + body that was already encoded somewhere else
+    pub fn send_raw(&self, packet: Vec<u8>) -> Result<(), ProtocolError>{
+        self.commands.send(Command::Packet(packet)).map_err(|_| ProtocolError::ConnectionClosed)
+    }
+
+    // closes the connection once everything queued before this was written
+    pub fn disconnect(&self){
+        let _ = self.commands.send(Command::Disconnect);
+    }
+
+    pub fn is_connected(&self) -> bool{
+        !self.commands.is_closed()
+    }
+}
+
+// a logged in connection. events come out of next_event (or a Handler given to run),
+// packets go in through send
+pub struct Client{
+    username: String,
+    uuid: u128,
+    handle: ClientHandle,
+    state: Arc<Mutex<ClientState>>,
+    events: mpsc::UnboundedReceiver<Event>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Client{
+    pub fn builder(host: &str, port: u16) -> ClientBuilder{
+        ClientBuilder::new(host, port)
+    }
+
+    fn start(reader: PacketReadHalf, writer: PacketWriteHalf, username: String, uuid: u128, version: ProtocolVersion, read_timeout: Duration) -> Self{
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let handle = ClientHandle{commands: commands_tx, version};
+        let mut client_state = ClientState::new(uuid);
+        if version.has_configuration(){
+            client_state.connection_state = ConnectionState::Configuration;
+        }
+        let state = Arc::new(Mutex::new(client_state));
+
+        let session = Session{handle: handle.clone(), state: state.clone()};
+        let tasks = vec![
+            tokio::spawn(write_loop(writer, commands)),
+            tokio::spawn(play_loop(reader, events_tx.clone(), session.clone(), read_timeout)),
+            tokio::spawn(tick_loop(session, events_tx)),
+        ];
+        Client{username, uuid, handle, state, events, tasks}
+    }
+
+    pub fn username(&self) -> &str{
+        &self.username
+    }
+
+    pub fn uuid(&self) -> u128{
+        self.uuid
+    }
+
+    pub fn version(&self) -> ProtocolVersion{
+        self.handle.version
+    }
+
+    pub fn protocol_version(&self) -> i32{
+        self.handle.version.protocol()
+    }
+
+    pub fn handle(&self) -> ClientHandle{
+        self.handle.clone()
+    }
+
+    // don't hold on to this across an await, the play loop needs it to apply every packet
+    pub fn state(&self) -> MutexGuard<'_, ClientState>{
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // our ping as the server shows it on the tab list, see ClientState::tab_list_latency
+    pub fn tab_list_latency(&self) -> Option<Duration>{
+        self.state().tab_list_latency()
+    }
+
+    // walks to the goal, Event::GoalReached or Event::NoPath tells how it went.
+    // ClientState::navigator has the path and the movement settings
+    pub fn goto<G: Goal + 'static>(&self, goal: G){
+        self.state().navigator.set_goal(goal);
+    }
+
+    // stops walking wherever we are
+    pub fn stop(&self){
+        self.state().navigator.stop();
+    }
+
+    // sends a chat message, unsigned. the server has to run with enforce-secure-profile=false
+    pub fn chat(&self, message: &str) -> Result<(), ProtocolError>{
+        chat::check_message(message)?;
+        let (message_count, acknowledged) = self.state().last_seen.update();
+        self.handle.send(&ChatMessage{
+            message: message.to_owned(),
+            timestamp: chat::timestamp(),
+            salt: rand::random(),
+            signature: None,
+            message_count,
+            acknowledged,
+        })
+    }
+
+    // runs a command, with or without the leading slash
+    pub fn command(&self, command: &str) -> Result<(), ProtocolError>{
+        let command = command.strip_prefix('/').unwrap_or(command);
+        chat::check_message(command)?;
+        let (message_count, acknowledged) = self.state().last_seen.update();
+        self.handle.send(&ChatCommand{
+            command: command.to_owned(),
+            timestamp: chat::timestamp(),
+            salt: rand::random(),
+            argument_signatures: Vec::new(),
+            message_count,
+            acknowledged,
+        })
+    }
+
+    // None once the connection is closed and every event was read
+    pub async fn next_event(&mut self) -> Option<Event>{
+        self.events.recv().await
+    }
+
+    // feeds every event to the handler until the connection is gone
+    pub async fn run<H: Handler>(mut self, mut handler: H){
+        while let Some(event) = self.events.recv().await{
+            handler.on_event(&self.handle, &event);
+        }
+    }
+
+    pub fn send<P: PlayPacket + Encode>(&self, packet: &P) -> Result<(), ProtocolError>{
+        self.handle.send(packet)
+    }
+
+    // packet ID + body that was already encoded somewhere else
+    pub fn send_raw(&self, packet: Vec<u8>) -> Result<(), ProtocolError>{
+        self.handle.send_raw(packet)
+    }
+
+    pub fn disconnect(&self){
+        self.handle.disconnect();
+    }
+}
+
+impl Drop for Client{
+    fn drop(&mut self){
+        for task in &self.tasks{
+            task.abort();
+        }
+    }
+}
+
+async fn write_loop(mut writer: PacketWriteHalf, mut commands: mpsc::UnboundedReceiver<Command>){
+    while let Some(command) = commands.recv().await{
+        match command{
+            Command::Packet(packet) => {
+                if writer.send_raw(&packet).await.is_err(){
+                    break; // the read side will notice and report the disconnect
+                }
+            },
+            Command::Disconnect => break,
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+async fn play_loop(mut reader: PacketReadHalf, events: mpsc::UnboundedSender<Event>, session: Session, read_timeout: Duration){
+    loop{
+        let mut new_events = Vec::new();
+        match timeout(read_timeout, reader.receive()).await{
+            Ok(Ok((id, data))) => {
+                if let Err(err) = session.handle_packet(id, data, &mut new_events){
+                    new_events.push(Event::Disconnected{reason: err.to_string()});
+                }
+            },
+            Ok(Err(err)) => new_events.push(Event::Disconnected{reason: err.to_string()}),
+            Err(_) => new_events.push(Event::Disconnected{reason: format!("timed out, the server sent nothing for {:?}", read_timeout)}),
+        }
+
+        for event in new_events{
+            let done = matches!(event, Event::Disconnected{..});
+            if events.send(event).is_err() || done{
+                session.handle.disconnect();
+                return; // nobody is listening anymore, or there is nothing left to listen to
+            }
+        }
+    }
+}
+
+// moves us and tells the server where we are every tick until the connection is gone
+async fn tick_loop(session: Session, events: mpsc::UnboundedSender<Event>){
+    let mut ticks = interval(TICK);
+    // a late tick isn't made up for, vanilla doesn't either
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    while session.handle.is_connected(){
+        ticks.tick().await;
+        let mut new_events = Vec::new();
+        if session.tick(&mut new_events).is_err(){
+            break;
+        }
+        for event in new_events{
+            if events.send(event).is_err(){
+                return;
+            }
+        }
+    }
+}
+
+// what the play loop works with: the client state it keeps up to date and a handle
+// for the packets the client answers on its own (keep alive, ping). despite the name the play
+// loop also goes through the configuration state on 1.20.2+
+#[derive(Clone)]
+struct Session{
+    handle: ClientHandle,
+    state: Arc<Mutex<ClientState>>,
+}
+
+impl Session{
+    fn state(&self) -> MutexGuard<'_, ClientState>{
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn tick(&self, events: &mut Vec<Event>) -> Result<(), ProtocolError>{
+        let (packet, actions) = {
+            let mut guard = self.state();
+            let state = &mut *guard;
+            if state.connection_state != ConnectionState::Play{
+                return Ok(());
+            }
+            let Some(player) = &mut state.player else{
+                return Ok(());
+            };
+            // like vanilla, we stand still until the chunk we're in arrived
+            let block = player.position.block();
+            if !state.world.is_loaded(block.x.div_euclid(16), block.z.div_euclid(16)){
+                return Ok(());
+            }
+            let actions = with_block_kind!(self.handle.version, B => {
+                let actions = state.navigator.tick::<B>(&mut state.world, &state.entities, player);
+                state.physics.tick::<B>(&state.world, player, player.inputs);
+                actions
+            });
+            (player.tick(), actions)
+        };
+
+        for action in actions{
+            match action{
+                NavigatorAction::Dig(packet) => {
+                    self.handle.send(&packet)?;
+                    self.handle.send(&SwingArm{hand: 0})?;
+                },
+                NavigatorAction::Place(packet) => {
+                    self.handle.send(&packet)?;
+                    self.handle.send(&SwingArm{hand: 0})?;
+                },
+                NavigatorAction::GoalReached => events.push(Event::GoalReached),
+                NavigatorAction::NoPath => events.push(Event::NoPath),
+            }
+        }
+        match packet{
+            Some(MovePacket::Position(packet)) => self.handle.send(&packet),
+            Some(MovePacket::PositionRotation(packet)) => self.handle.send(&packet),
+            Some(MovePacket::Rotation(packet)) => self.handle.send(&packet),
+            Some(MovePacket::OnGround(packet)) => self.handle.send(&packet),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_packet(&self, id: i32, data: Vec<u8>, events: &mut Vec<Event>) -> Result<(), ProtocolError>{
+        let connection_state = self.state().connection_state;
+        if connection_state == ConnectionState::Configuration{
+            return self.handle_configuration(id, data, events);
+        }
+
+        let mut reader = PacketReader::with_version(&data, self.handle.version);
+        let packet = match self.handle.version.clientbound(id){
+            Some(packet) => packet,
+            None => {
+                events.push(Event::Packet{id, data});
+                return Ok(());
+            }
+        };
+        match packet{
+            Clientbound::SpawnEntity => {
+                let spawn = reader.read::<SpawnEntity>()?;
+                self.state().entities.spawn(&spawn);
+                events.push(Event::EntitySpawned(spawn));
+            },
+            Clientbound::SpawnPlayer => {
+                let spawn = reader.read::<SpawnPlayer>()?;
+                self.state().entities.spawn_player(&spawn);
+                events.push(Event::PlayerSpawned(spawn));
+            },
+            Clientbound::UpdateEntityPosition => {
+                let update = reader.read::<UpdateEntityPosition>()?;
+                if let Some(entity) = self.state().entities.get_mut(update.entity_id){
+                    entity.move_by(update.dx, update.dy, update.dz);
+                    entity.on_ground = update.on_ground;
+                }
+            },
+            Clientbound::UpdateEntityPositionRotation => {
+                let update = reader.read::<UpdateEntityPositionRotation>()?;
+                if let Some(entity) = self.state().entities.get_mut(update.entity_id){
+                    entity.move_by(update.dx, update.dy, update.dz);
+                    entity.yaw = update.yaw;
+                    entity.pitch = update.pitch;
+                    entity.on_ground = update.on_ground;
+                }
+            },
+            Clientbound::UpdateEntityRotation => {
+                let update = reader.read::<EntityRotation>()?;
+                if let Some(entity) = self.state().entities.get_mut(update.entity_id){
+                    entity.yaw = update.yaw;
+                    entity.pitch = update.pitch;
+                    entity.on_ground = update.on_ground;
+                }
+            },
+            Clientbound::SetHeadRotation => {
+                let update = reader.read::<SetHeadRotation>()?;
+                if let Some(entity) = self.state().entities.get_mut(update.entity_id){
+                    entity.head_yaw = update.head_yaw;
+                }
+            },
+            Clientbound::TeleportEntity => {
+                let teleport = reader.read::<TeleportEntity>()?;
+                if let Some(entity) = self.state().entities.get_mut(teleport.entity_id){
+                    entity.teleport(teleport.position);
+                    entity.yaw = teleport.yaw;
+                    entity.pitch = teleport.pitch;
+                    entity.on_ground = teleport.on_ground;
+                }
+            },
+            Clientbound::SetEntityVelocity => {
+                let update = reader.read::<SetEntityVelocity>()?;
+                if let Some(entity) = self.state().entities.get_mut(update.entity_id){
+                    entity.set_velocity(update.velocity_x, update.velocity_y, update.velocity_z);
+                }
+            },
+            Clientbound::SetEntityMetadata => {
+                let update = reader.read::<SetEntityMetadata>()?;
+                if let Some(entity) = self.state().entities.get_mut(update.entity_id){
+                    entity.set_metadata(update.entries);
+                }
+            },
+            Clientbound::RemoveEntities => {
+                let remove = reader.read::<RemoveEntities>()?;
+                let mut state = self.state();
+                for id in &remove.entity_ids{
+                    state.entities.remove(*id);
+                }
+                events.push(Event::EntitiesRemoved(remove.entity_ids));
+            },
+            Clientbound::SynchronizePlayerPosition => {
+                let sync = reader.read::<SynchronizePlayerPosition>()?;
+                let reply = {
+                    let mut state = self.state();
+                    state.navigator.replan(); // wherever the path was going, it starts somewhere else now
+                    state.player.get_or_insert_with(|| LocalPlayer::new(Vec3::ZERO, 0.0, 0.0)).teleport(&sync)
+                };
+                self.handle.send(&ConfirmTeleportation{teleport_id: sync.teleport_id})?;
+                self.handle.send(&reply)?;
+                events.push(Event::Teleported{position: reply.position, yaw: reply.yaw, pitch: reply.pitch});
+            },
+            Clientbound::EntityAnimation => events.push(Event::Animation(reader.read()?)),
+            Clientbound::Disconnect => events.push(Event::Disconnected{reason: reader.read::<PlayDisconnect>()?.reason.to_plain(self.handle.version)}),
+            Clientbound::SystemChat => {
+                let chat = reader.read::<SystemChat>()?;
+                events.push(Event::Chat{sender: None, name: None, message: chat.content, overlay: chat.overlay});
+            },
+            Clientbound::PlayerChat => {
+                let chat = reader.read::<PlayerChat>()?;
+                let (name, acknowledgment) = {
+                    let mut state = self.state();
+                    let acknowledgment = match &chat.signature{
+                        Some(signature) if state.last_seen.add(signature) => state.last_seen.acknowledgment(),
+                        _ => None,
+                    };
+                    (state.player_name(chat.sender).map(str::to_owned), acknowledgment)
+                };
+                if let Some(message_count) = acknowledgment{
+                    self.handle.send(&MessageAcknowledgment{message_count})?;
+                }
+                // what the server changed the message to is what vanilla shows
+                let message = match chat.unsigned_content{
+                    Some(content) => content,
+                    None => TextComponent::text(chat.message),
+                };
+                events.push(Event::Chat{sender: Some(chat.sender), name: name.or(Some(chat.sender_name.to_plain(self.handle.version))), message, overlay: false});
+            },
+            Clientbound::DisguisedChat => {
+                let chat = reader.read::<DisguisedChat>()?;
+                events.push(Event::Chat{sender: None, name: Some(chat.sender_name.to_plain(self.handle.version)), message: chat.message, overlay: false});
+            },
+            Clientbound::Login => {
+                let join_game = reader.read::<JoinGame>()?;
+                events.push(Event::Joined{entity_id: join_game.entity_id, game_mode: join_game.game_mode, dimension: join_game.dimension_name.clone()});
+                let mut state = self.state();
+                if let Some(codec) = &join_game.registry_codec{
+                    state.registry_codec = Some(codec.clone());
+                }
+                state.world = state.dimension_world(&join_game.dimension_type);
+                state.entities.clear();
+                state.player = None; // until the server says where we spawned
+                state.join_game = Some(join_game);
+            },
+            Clientbound::Respawn => {
+                let respawn = reader.read::<Respawn>()?;
+                let mut state = self.state();
+                // the chunks of the dimension we came from are gone, the new one may be a different height too
+                let new_world = match &state.join_game{
+                    Some(join_game) if join_game.dimension_name != respawn.dimension_name => Some(state.dimension_world(&respawn.dimension_type)),
+                    _ => None,
+                };
+                if let Some(world) = new_world{
+                    state.world = world;
+                    state.entities.clear();
+                }
+                state.player = None; // a Synchronize Player Position follows
+                if let Some(join_game) = &mut state.join_game{
+                    join_game.dimension_type = respawn.dimension_type;
+                    join_game.dimension_name = respawn.dimension_name.clone();
+                    join_game.game_mode = respawn.game_mode;
+                    join_game.previous_game_mode = respawn.previous_game_mode;
+                    join_game.death_location = respawn.death_location;
+                }
+                events.push(Event::Respawned{dimension: respawn.dimension_name});
+            },
+            Clientbound::ChunkData => {
+                let chunk = reader.read::<ChunkData>()?;
+                self.state().world.load_chunk(chunk)?;
+            },
+            Clientbound::UpdateLight => {
+                let update = reader.read::<UpdateLight>()?;
+                self.state().world.update_light(update.x, update.z, update.light);
+            },
+            Clientbound::BlockUpdate => {
+                let update = reader.read::<BlockUpdate>()?;
+                let mut guard = self.state();
+                let state = &mut *guard;
+                if let Some(change) = state.world.server_block_update(update.position, update.state){
+                    with_block_kind!(self.handle.version, B => state.navigator.block_changed::<B>(&state.world, change.position));
+                    events.push(Event::BlockChanged(change));
+                }
+            },
+            Clientbound::UpdateSectionBlocks => {
+                let update = reader.read::<UpdateSectionBlocks>()?;
+                let mut guard = self.state();
+                let state = &mut *guard;
+                for (position, block) in update.blocks{
+                    if let Some(change) = state.world.server_block_update(position, block){
+                        with_block_kind!(self.handle.version, B => state.navigator.block_changed::<B>(&state.world, change.position));
+                        events.push(Event::BlockChanged(change));
+                    }
+                }
+            },
+            Clientbound::AcknowledgeBlockChange => {
+                let ack = reader.read::<AcknowledgeBlockChange>()?;
+                let mut guard = self.state();
+                let state = &mut *guard;
+                let changes = state.world.acknowledge(ack.sequence);
+                for change in &changes{
+                    with_block_kind!(self.handle.version, B => state.navigator.block_changed::<B>(&state.world, change.position));
+                }
+                events.extend(changes.into_iter().map(Event::BlockChanged));
+            },
+            Clientbound::ChunkBatchFinished => {
+                reader.read::<ChunkBatchFinished>()?;
+                self.handle.send(&ChunkBatchReceived{chunks_per_tick: CHUNKS_PER_TICK})?;
+            },
+            Clientbound::UnloadChunk => {
+                let unload = reader.read::<UnloadChunk>()?;
+                self.state().world.unload_chunk(unload.x, unload.z);
+            },
+            Clientbound::KeepAlive => {
+                let keep_alive = reader.read::<KeepAlive>()?;
+                self.handle.send(&KeepAliveResponse{id: keep_alive.id})?;
+            },
+            Clientbound::Ping => {
+                let ping = reader.read::<PlayPing>()?;
+                self.handle.send(&PlayPong{id: ping.id})?;
+            },
+            Clientbound::PlayerInfoUpdate => {
+                let update = reader.read::<PlayerInfoUpdate>()?;
+                let mut state = self.state();
+                for entry in &update.entries{
+                    state.players.entry(entry.uuid).or_insert_with(|| PlayerInfo::new(entry.uuid)).update(entry);
+                }
+            },
+            Clientbound::PlayerInfoRemove => {
+                let remove = reader.read::<PlayerInfoRemove>()?;
+                let mut state = self.state();
+                for uuid in &remove.uuids{
+                    state.players.remove(uuid);
+                }
+            },
+            Clientbound::PluginMessage => {
+                let message = reader.read::<PluginMessage>()?;
+                events.push(Event::PluginMessage{channel: message.channel, data: message.data});
+            },
+            Clientbound::FeatureFlags => self.state().feature_flags = reader.read::<FeatureFlags>()?.flags,
+            Clientbound::UpdateTags => self.state().tags.extend(reader.read::<UpdateTags>()?.tags),
+            Clientbound::StartConfiguration => {
+                // the server wants to configure us again (a proxy switching servers does this), Login (play)
+                // comes again after it so the world gets reset there
+                self.handle.send(&AcknowledgeConfiguration)?;
+                self.state().connection_state = ConnectionState::Configuration;
+            },
+            Clientbound::BundleDelimiter => {}, // we apply every packet as it comes anyway
+            _ => events.push(Event::Packet{id, data}),
+        }
+        Ok(())
+    }
+
+    fn handle_configuration(&self, id: i32, data: Vec<u8>, events: &mut Vec<Event>) -> Result<(), ProtocolError>{
+        let mut reader = PacketReader::with_version(&data, self.handle.version);
+        let packet = match self.handle.version.configuration(id){
+            Some(packet) => packet,
+            None => return Err(ProtocolError::UnknownPacket{state: ConnectionState::Configuration, id}),
+        };
+        match packet{
+            Configuration::PluginMessage => {
+                let message = reader.read::<PluginMessage>()?;
+                events.push(Event::PluginMessage{channel: message.channel, data: message.data});
+            },
+            Configuration::Disconnect => events.push(Event::Disconnected{reason: reader.read::<PlayDisconnect>()?.reason.to_plain(self.handle.version)}),
+            Configuration::FinishConfiguration => {
+                self.handle.send_configuration(&AcknowledgeFinishConfiguration)?;
+                self.state().connection_state = ConnectionState::Play;
+            },
+            Configuration::KeepAlive => {
+                let keep_alive = reader.read::<KeepAlive>()?;
+                self.handle.send_configuration(&KeepAliveResponse{id: keep_alive.id})?;
+            },
+            Configuration::Ping => {
+                let ping = reader.read::<PlayPing>()?;
+                self.handle.send_configuration(&PlayPong{id: ping.id})?;
+            },
+            Configuration::RegistryData => self.state().registry_codec = Some(reader.read::<RegistryData>()?.registry_codec),
+            Configuration::ResourcePack => {
+                // we never download it, but declining gets us kicked from servers that require one
+                let pack = reader.read::<ResourcePack>()?;
+                self.handle.send_configuration(&ResourcePackResponse{uuid: pack.uuid, result: ResourcePackResult::Accepted})?;
+                self.handle.send_configuration(&ResourcePackResponse{uuid: pack.uuid, result: ResourcePackResult::Loaded})?;
+            },
+            Configuration::RemoveResourcePack => {},
+            Configuration::FeatureFlags => self.state().feature_flags = reader.read::<FeatureFlags>()?.flags,
+            Configuration::UpdateTags => self.state().tags.extend(reader.read::<UpdateTags>()?.tags),
+        }
+        Ok(())
+    }
+}
+
+fn timed_out(what: &str) -> ProtocolError{
+    ProtocolError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, format!("{} timed out", what)))
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        tokio::net::TcpListener,
+        crate::{
+            codec::{
+                varint_write,
+                write_string,
+            },
+            packets::test_login,
+            version::{
+                Clientbound,
+                Serverbound,
+            },
+        },
+    };
+
+    const UUID: u128 = 0x5627dd98e6be3c21b8a8e92344183641;
+    const COMPRESSION: i32 = 256;
+
+    // the server side of a test, checks what the client sends and answers with whatever the
+    // test wants to send next
+    struct Script{
+        conn: AsyncConnection,
+        version: ProtocolVersion,
+    }
+
+    impl Script{
+        async fn expect(&mut self, id: i32) -> Vec<u8>{
+            let (received, data) = timeout(Duration::from_secs(5), self.conn.receive()).await
+                .unwrap_or_else(|_| panic!("the client didn't send 0x{:02x}", id))
+                .unwrap();
+            assert_eq!(received, id, "expected 0x{:02x}", id);
+            data
+        }
+
+        async fn send(&mut self, id: i32, body: &[u8]){
+            self.conn.send_raw(&[&varint_write(id)[..], body].concat()).await.unwrap();
+        }
+
+        async fn send_play(&mut self, packet: Clientbound, body: &[u8]){
+            self.send(self.version.clientbound_id(packet).unwrap(), body).await;
+        }
+
+        // the client closing its side is what a server sees when it disconnects
+        async fn expect_closed(&mut self){
+            let closed = timeout(Duration::from_secs(5), async {
+                loop{
+                    if self.conn.receive().await.is_err(){
+                        return;
+                    }
+                }
+            }).await;
+            assert!(closed.is_ok(), "the client didn't close the connection");
+        }
+    }
+
+    // what a vanilla server in offline mode does up to the configuration (1.20.2+) or play state,
+    // checking the handshake and Login Start on the way
+    async fn accept_login(listener: TcpListener, version: ProtocolVersion) -> Script{
+        let (stream, _) = listener.accept().await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut script = Script{conn: AsyncConnection::from_stream(stream), version};
+
+        let handshake = script.expect(0x00).await;
+        let mut reader = PacketReader::new(&handshake);
+        assert_eq!(reader.read_varint().unwrap(), version.protocol());
+        assert_eq!(reader.read_string().unwrap(), "127.0.0.1");
+        assert_eq!(reader.read_u16().unwrap(), port);
+        assert_eq!(reader.read_varint().unwrap(), 2); // login
+        assert_eq!(reader.remaining(), 0);
+
+        let login_start = script.expect(0x00).await;
+        let mut reader = PacketReader::new(&login_start);
+        assert_eq!(reader.read_string().unwrap(), "bot");
+        if version.has_configuration(){
+            // the uuid isn't optional anymore, offline servers ignore it
+            assert_eq!(reader.read_uuid().unwrap(), 0);
+        } else {
+            assert!(!reader.read_bool().unwrap(), "an offline login sent a uuid");
+        }
+        assert_eq!(reader.remaining(), 0);
+
+        script.send(0x03, &varint_write(COMPRESSION)).await; // Set Compression
+        script.conn.set_compression(COMPRESSION);
+
+        let success = [&UUID.to_be_bytes()[..], &[0x03], b"bot", &[0x00]].concat();
+        script.send(0x02, &success).await; // Login Success
+        if version.has_configuration(){
+            script.expect(0x03).await; // Login Acknowledged
+        }
+        script
+    }
+
+    async fn connect(version: ProtocolVersion, read_timeout: Duration) -> (Client, Script){
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let builder = Client::builder("127.0.0.1", port).username("bot").version(version).read_timeout(read_timeout);
+        let (client, script) = tokio::join!(builder.connect(), accept_login(listener, version));
+        (client.unwrap(), script)
+    }
+
+    async fn next_event(client: &mut Client) -> Option<Event>{
+        timeout(Duration::from_secs(5), client.next_event()).await.expect("no event from the client")
+    }
+
+    async fn all_tasks_finish(client: &Client){
+        let finished = timeout(Duration::from_secs(5), async {
+            while !client.tasks.iter().all(JoinHandle::is_finished){
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await;
+        assert!(finished.is_ok(), "not every task of the client finished");
+    }
+
+    #[tokio::test]
+    async fn offline_login(){
+        for &version in ProtocolVersion::ALL{
+            let (mut client, mut script) = connect(version, Duration::from_secs(30)).await;
+            assert_eq!(client.username(), "bot");
+            assert_eq!(client.uuid(), UUID);
+            assert_eq!(client.version(), version);
+
+            if version.has_configuration(){
+                assert_eq!(client.state().connection_state, ConnectionState::Configuration);
+                script.send(0x02, &[]).await; // Finish Configuration
+                script.expect(0x02).await; // Acknowledge Finish Configuration
+            }
+            script.send_play(Clientbound::Login, &test_login(version)).await;
+
+            match next_event(&mut client).await{
+                Some(Event::Joined{entity_id, ..}) => assert_eq!(entity_id, 300),
+                other => panic!("{}: expected to join, got {:?}", version, other),
+            }
+            assert_eq!(client.state().connection_state, ConnectionState::Play);
+            assert_eq!(client.state().entity_id(), Some(300));
+
+            client.disconnect();
+            script.expect_closed().await;
+            drop(script); // like a real server, it closes its side too
+            assert!(matches!(next_event(&mut client).await, Some(Event::Disconnected{..})));
+            assert!(next_event(&mut client).await.is_none());
+            all_tasks_finish(&client).await;
+            assert!(!client.handle().is_connected());
+            assert!(matches!(client.send_raw(vec![0x00]), Err(ProtocolError::ConnectionClosed)));
+        }
+    }
+
+    #[tokio::test]
+    async fn keep_alive_is_echoed(){
+        for &version in ProtocolVersion::ALL{
+            let (mut client, mut script) = connect(version, Duration::from_secs(30)).await;
+            let id: i64 = -0x0123456789abcdef;
+            if version.has_configuration(){
+                script.send(0x03, &id.to_be_bytes()).await; // Keep Alive in the configuration state
+                assert_eq!(script.expect(0x03).await, id.to_be_bytes());
+                script.send(0x02, &[]).await;
+                script.expect(0x02).await;
+            }
+            script.send_play(Clientbound::Login, &test_login(version)).await;
+            assert!(matches!(next_event(&mut client).await, Some(Event::Joined{..})));
+
+            for id in [1i64, i64::MAX, id]{
+                script.send_play(Clientbound::KeepAlive, &id.to_be_bytes()).await;
+                let response = script.expect(version.serverbound_id(Serverbound::KeepAlive).unwrap()).await;
+                assert_eq!(response, id.to_be_bytes(), "{}", version);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn read_watchdog(){
+        let read_timeout = Duration::from_millis(300);
+        let (mut client, mut script) = connect(ProtocolVersion::V1_20_1, read_timeout).await;
+        script.send_play(Clientbound::Login, &test_login(ProtocolVersion::V1_20_1)).await;
+        assert!(matches!(next_event(&mut client).await, Some(Event::Joined{..})));
+
+        // a server that keeps talking is fine for much longer than the timeout
+        let started = tokio::time::Instant::now();
+        for id in 0..8i64{
+            tokio::time::sleep(read_timeout / 3).await;
+            script.send_play(Clientbound::KeepAlive, &id.to_be_bytes()).await;
+            script.expect(ProtocolVersion::V1_20_1.serverbound_id(Serverbound::KeepAlive).unwrap()).await;
+        }
+        assert!(started.elapsed() > read_timeout * 2);
+        assert!(client.handle().is_connected());
+
+        // one that goes quiet isn't
+        let quiet = tokio::time::Instant::now();
+        match next_event(&mut client).await{
+            Some(Event::Disconnected{reason}) => assert_eq!(reason, "timed out, the server sent nothing for 300ms"),
+            other => panic!("expected the watchdog to disconnect, got {:?}", other),
+        }
+        assert!(quiet.elapsed() >= read_timeout);
+        assert!(quiet.elapsed() < read_timeout * 4);
+        script.expect_closed().await;
+        all_tasks_finish(&client).await;
+    }
+
+    // answers a status request with the json, like a server running whatever version it says
+    async fn answer_status(listener: &TcpListener, json: &str){
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut script = Script{conn: AsyncConnection::from_stream(stream), version: ProtocolVersion::DEFAULT};
+        let handshake = script.expect(0x00).await;
+        assert_eq!(handshake.last(), Some(&1)); // status
+        assert!(script.expect(0x00).await.is_empty());
+        let mut response = Vec::new();
+        write_string(&mut response, json, 32767).unwrap();
+        script.send(0x00, &response).await;
+        let ping = script.expect(0x01).await;
+        script.send(0x01, &ping).await;
+    }
+
+    #[tokio::test]
+    async fn version_from_status(){
+        for &version in ProtocolVersion::ALL{
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let json = format!(r#"{{"version":{{"name":"Paper {}","protocol":{}}},"players":{{"online":1,"max":20}},"description":"hi"}}"#, version.name(), version.protocol());
+            let server = async {
+                answer_status(&listener, &json).await;
+                accept_login(listener, version).await
+            };
+            let (client, _script) = tokio::join!(Client::builder("127.0.0.1", port).username("bot").connect(), server);
+            assert_eq!(client.unwrap().version(), version);
+        }
+
+        // a version we can't talk to is turned down before logging in
+        for (json, error) in [
+            (r#"{"version":{"name":"1.20.6","protocol":766},"description":""}"#, "server runs protocol 766, supported versions are 1.19.4, 1.20.1, 1.20.2, 1.20.4"),
+            (r#"{"description":"no version"}"#, "server status doesn't say which version it runs, pin one with ClientBuilder::version"),
+        ]{
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let (client, _) = tokio::join!(Client::builder("127.0.0.1", port).connect(), answer_status(&listener, json));
+            match client{
+                Err(ProtocolError::InvalidValue(message)) => assert_eq!(message, error),
+                Err(err) => panic!("expected the version to be turned down, got {}", err),
+                Ok(_) => panic!("connected to a server running an unsupported version"),
+            }
+            // and it didn't try to log in
+            assert!(timeout(Duration::from_millis(100), listener.accept()).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn configuration(){
+        // Registry Data is nameless nbt since 1.20.2, the fixture has the empty root name of 1.20.1
+        let codec = include_bytes!("../tests/fixtures/nbt/registry_codec_1.20.1.nbt");
+        let registry_data = [&[0x0a][..], &codec[3..]].concat();
+        let pack_uuid: u128 = 0x0123456789abcdef0123456789abcdef;
+
+        for version in [ProtocolVersion::V1_20_2, ProtocolVersion::V1_20_4]{
+            let (mut client, mut script) = connect(version, Duration::from_secs(30)).await;
+            script.send(0x05, &registry_data).await;
+
+            script.send(0x03, &42i64.to_be_bytes()).await; // Keep Alive
+            assert_eq!(script.expect(0x03).await, 42i64.to_be_bytes());
+
+            let mut pack = Vec::new();
+            if version >= ProtocolVersion::V1_20_4{
+                pack.extend_from_slice(&pack_uuid.to_be_bytes());
+            }
+            write_string(&mut pack, "https://example.com/pack.zip", 32767).unwrap();
+            write_string(&mut pack, "", 40).unwrap();
+            pack.extend_from_slice(&[0x01, 0x00]); // forced, no prompt
+            script.send(if version >= ProtocolVersion::V1_20_4{ 0x07 } else { 0x06 }, &pack).await;
+
+            // accepted, then loaded, for the pack the server asked about
+            let (accepted, loaded) = (script.expect(0x05).await, script.expect(0x05).await);
+            if version >= ProtocolVersion::V1_20_4{
+                assert_eq!(accepted, [&pack_uuid.to_be_bytes()[..], &[0x03]].concat());
+                assert_eq!(loaded, [&pack_uuid.to_be_bytes()[..], &[0x00]].concat());
+            } else {
+                assert_eq!(accepted, [0x03]);
+                assert_eq!(loaded, [0x00]);
+            }
+            assert_eq!(client.state().connection_state, ConnectionState::Configuration);
+
+            script.send(0x02, &[]).await; // Finish Configuration
+            assert!(script.expect(0x02).await.is_empty());
+            assert_eq!(client.state().connection_state, ConnectionState::Play);
+            assert!(client.state().registry_codec.is_some());
+
+            // play packets are play packets now, and the world gets its height from the codec
+            script.send_play(Clientbound::Login, &test_login(version)).await;
+            match next_event(&mut client).await{
+                Some(Event::Joined{dimension, ..}) => assert_eq!(dimension, "minecraft:the_end"),
+                other => panic!("{}: expected to join, got {:?}", version, other),
+            }
+            let height = client.state().world.height();
+            assert_eq!(height, 256);
+            script.send_play(Clientbound::KeepAlive, &7i64.to_be_bytes()).await;
+            assert_eq!(script.expect(version.serverbound_id(Serverbound::KeepAlive).unwrap()).await, 7i64.to_be_bytes());
+        }
+    }
+}
@@ -0,0 +1,654 @@
+use {
+    std::{
+        error::Error,
+        fmt,
+        io,
+        ops::{
+            Add,
+            Mul,
+            Sub,
+        },
+    },
+    crate::version::{
+        PlayPacket,
+        ProtocolVersion,
+    },
+};
+
+// everything that can go wrong while turning bytes into packets (or the other way around)
+#[derive(Debug)]
+pub enum ProtocolError{
+    UnexpectedEof{needed: usize, remaining: usize},
+    VarIntTooBig,
+    VarLongTooBig,
+    NegativeLength(i32),
+    StringTooLong{max: usize, length: usize},
+    FrameTooLarge{size: usize, max: usize},
+    InvalidValue(String),
+    UnknownPacket{state: ConnectionState, id: i32},
+    Disconnected(String),
+    Auth(String),
+    ConnectionClosed,
+    Io(io::Error),
+}
+
+impl fmt::Display for ProtocolError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            ProtocolError::UnexpectedEof{needed, remaining} => write!(f, "unexpected end of packet, needed {} bytes but only {} left", needed, remaining),
+            ProtocolError::VarIntTooBig => write!(f, "VarInt too big"),
+            ProtocolError::VarLongTooBig => write!(f, "VarLong too big"),
+            ProtocolError::NegativeLength(len) => write!(f, "negative length: {}", len),
+            ProtocolError::StringTooLong{max, length} => write!(f, "string too long, max: {}, length: {}", max, length),
+            ProtocolError::FrameTooLarge{size, max} => write!(f, "frame too large, max: {}, size: {}", max, size),
+            ProtocolError::InvalidValue(msg) => write!(f, "invalid value: {}", msg),
+            ProtocolError::UnknownPacket{state, id} => write!(f, "unknown packet in {} state, id: {:#x}", state, id),
+            ProtocolError::Disconnected(reason) => write!(f, "disconnected, reason: {}", reason),
+            ProtocolError::Auth(msg) => write!(f, "authentication failed: {}", msg),
+            ProtocolError::ConnectionClosed => write!(f, "connection closed by the server"),
+            ProtocolError::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl Error for ProtocolError{
+    fn source(&self) -> Option<&(dyn Error + 'static)>{
+        match self{
+            ProtocolError::Io(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for ProtocolError{
+    fn from(err: io::Error) -> Self{
+        ProtocolError::Io(err)
+    }
+}
+
+// where a connection is in the protocol, the same packet ID means something else in each of them.
+// Configuration only exists since 1.20.2, it sits between Login and Play there
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionState{
+    Handshaking,
+    Status,
+    Login,
+    Configuration,
+    Play,
+}
+
+impl ConnectionState{
+    pub fn name(&self) -> &'static str{
+        match self{
+            ConnectionState::Handshaking => "handshaking",
+            ConnectionState::Status => "status",
+            ConnectionState::Login => "login",
+            ConnectionState::Configuration => "configuration",
+            ConnectionState::Play => "play",
+        }
+    }
+}
+
+impl fmt::Display for ConnectionState{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{}", self.name())
+    }
+}
+
+// a packet with a fixed id in its protocol state
+pub trait Packet{
+    const ID: i32;
+}
+
+// writes the fields of a packet (without the packet ID or length)
+pub trait Encode{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>;
+
+    // packets whose layout changed between versions override this, encode is then the default version
+    fn encode_for(&self, buf: &mut Vec<u8>, _version: ProtocolVersion) -> Result<(), ProtocolError>{
+        self.encode(buf)
+    }
+}
+
+// reads the fields of a packet from the body that comes after the packet ID. packets whose
+// layout changed between versions look at reader.version()
+pub trait Decode: Sized{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>;
+}
+
+// packet ID + fields, ready to be framed
+pub fn packet_body<P: Packet + Encode>(packet: &P) -> Result<Vec<u8>, ProtocolError>{
+    let mut buf = varint_write(P::ID);
+    packet.encode(&mut buf)?;
+    Ok(buf)
+}
+
+// same as packet_body but laid out for a specific version
+pub fn packet_body_for<P: Packet + Encode>(packet: &P, version: ProtocolVersion) -> Result<Vec<u8>, ProtocolError>{
+    let mut buf = varint_write(P::ID);
+    packet.encode_for(&mut buf, version)?;
+    Ok(buf)
+}
+
+// play packets don't have a fixed ID, fails if the packet doesn't exist in the version
+pub fn play_packet_body<P: PlayPacket + Encode>(packet: &P, version: ProtocolVersion) -> Result<Vec<u8>, ProtocolError>{
+    let id = version.serverbound_id(P::PACKET)
+        .ok_or_else(|| ProtocolError::InvalidValue(format!("{:?} doesn't exist in {}", P::PACKET, version)))?;
+    let mut buf = varint_write(id);
+    packet.encode_for(&mut buf, version)?;
+    Ok(buf)
+}
+
+// length prefixed packet, only valid before compression is turned on
+pub fn packer(packet: Vec<u8>) -> Vec<u8>{
+    let mut buff = varint_write(packet.len() as i32);
+    buff.extend_from_slice(packet.as_slice());
+    buff
+}
+
+// negative numbers are sent as their two's complement, so they always take the full 5 bytes
+pub fn varint_write(number: i32) -> Vec<u8>{
+    let mut data: Vec<u8> = Vec::with_capacity(5);
+    let mut num = number as u32;
+
+    while num >= 0x80{
+        data.push((num & 0x7F) as u8 | 0x80);
+        num >>= 7;
+    }
+    data.push(num as u8);
+    data
+}
+
+// same as varint_write but for i64, taking up to 10 bytes
+pub fn varlong_write(number: i64) -> Vec<u8>{
+    let mut data: Vec<u8> = Vec::with_capacity(10);
+    let mut num = number as u64;
+
+    while num >= 0x80{
+        data.push((num & 0x7F) as u8 | 0x80);
+        num >>= 7;
+    }
+    data.push(num as u8);
+    data
+}
+
+pub fn write_string(buff: &mut Vec<u8>, data: &str, max: usize) -> Result<(), ProtocolError>{
+    if data.chars().count() > max{
+        return Err(ProtocolError::StringTooLong{max, length: data.chars().count()});
+    }
+
+    buff.extend_from_slice(varint_write(data.len() as i32).as_slice());
+    buff.extend_from_slice(data.as_bytes());
+    Ok(())
+}
+
+// a block position, sent packed into one long as x (26 bits), z (26 bits), y (12 bits)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BlockPos{
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl BlockPos{
+    pub fn new(x: i32, y: i32, z: i32) -> Self{
+        BlockPos{x, y, z}
+    }
+
+    pub fn from_packed(packed: i64) -> Self{
+        BlockPos{
+            x: (packed >> 38) as i32,
+            y: (packed << 52 >> 52) as i32,
+            z: (packed << 26 >> 38) as i32,
+        }
+    }
+
+    pub fn packed(&self) -> i64{
+        ((self.x as i64 & 0x3FFFFFF) << 38) | ((self.z as i64 & 0x3FFFFFF) << 12) | (self.y as i64 & 0xFFF)
+    }
+}
+
+impl Encode for BlockPos{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(&self.packed().to_be_bytes());
+        Ok(())
+    }
+}
+
+impl Decode for BlockPos{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(BlockPos::from_packed(reader.read_i64()?))
+    }
+}
+
+// a position (or velocity) in blocks, sent as three doubles
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vec3{
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vec3{
+    pub const ZERO: Vec3 = Vec3{x: 0.0, y: 0.0, z: 0.0};
+
+    pub fn new(x: f64, y: f64, z: f64) -> Self{
+        Vec3{x, y, z}
+    }
+
+    pub fn length_squared(&self) -> f64{
+        self.x * self.x + self.y * self.y + self.z * self.z
+    }
+
+    pub fn length(&self) -> f64{
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance_squared(&self, other: Vec3) -> f64{
+        (*self - other).length_squared()
+    }
+
+    pub fn distance(&self, other: Vec3) -> f64{
+        self.distance_squared(other).sqrt()
+    }
+
+    // the block this position is in
+    pub fn block(&self) -> BlockPos{
+        BlockPos::new(self.x.floor() as i32, self.y.floor() as i32, self.z.floor() as i32)
+    }
+}
+
+impl Add for Vec3{
+    type Output = Vec3;
+
+    fn add(self, other: Vec3) -> Vec3{
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3{
+    type Output = Vec3;
+
+    fn sub(self, other: Vec3) -> Vec3{
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Vec3{
+    type Output = Vec3;
+
+    fn mul(self, factor: f64) -> Vec3{
+        Vec3::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Encode for Vec3{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(&self.x.to_be_bytes());
+        buf.extend_from_slice(&self.y.to_be_bytes());
+        buf.extend_from_slice(&self.z.to_be_bytes());
+        Ok(())
+    }
+}
+
+impl Decode for Vec3{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(Vec3::new(reader.read_f64()?, reader.read_f64()?, reader.read_f64()?))
+    }
+}
+
+// walks over a packet without copying it, every read moves the cursor forward
+// and fails with UnexpectedEof instead of reading past the end
+pub struct PacketReader<'a>{
+    data: &'a [u8],
+    pos: usize,
+    version: ProtocolVersion,
+}
+
+impl<'a> PacketReader<'a>{
+    pub fn new(data: &'a [u8]) -> Self{
+        PacketReader{data, pos: 0, version: ProtocolVersion::DEFAULT}
+    }
+
+    // for packets that are laid out differently depending on the version
+    pub fn with_version(data: &'a [u8], version: ProtocolVersion) -> Self{
+        PacketReader{data, pos: 0, version}
+    }
+
+    pub fn version(&self) -> ProtocolVersion{
+        self.version
+    }
+
+    pub fn position(&self) -> usize{
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize{
+        self.data.len() - self.pos
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.remaining() == 0
+    }
+
+    pub fn read<T: Decode>(&mut self) -> Result<T, ProtocolError>{
+        T::decode(self)
+    }
+
+    pub fn read_bytes(&mut self, size: usize) -> Result<&'a [u8], ProtocolError>{
+        if self.remaining() < size{
+            return Err(ProtocolError::UnexpectedEof{needed: size, remaining: self.remaining()});
+        }
+
+        let bytes = &self.data[self.pos..self.pos + size];
+        self.pos += size;
+        Ok(bytes)
+    }
+
+    // everything that is left, used for plugin messages and other length-less byte arrays
+    pub fn read_remaining(&mut self) -> &'a [u8]{
+        let bytes = &self.data[self.pos..];
+        self.pos = self.data.len();
+        bytes
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], ProtocolError>{
+        let mut arr = [0u8; N];
+        arr.copy_from_slice(self.read_bytes(N)?);
+        Ok(arr)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ProtocolError>{
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8, ProtocolError>{
+        Ok(self.read_u8()? as i8)
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, ProtocolError>{
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, ProtocolError>{
+        Ok(u16::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16, ProtocolError>{
+        Ok(i16::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, ProtocolError>{
+        Ok(i32::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, ProtocolError>{
+        Ok(i64::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, ProtocolError>{
+        Ok(f32::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, ProtocolError>{
+        Ok(f64::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_uuid(&mut self) -> Result<u128, ProtocolError>{
+        Ok(u128::from_be_bytes(self.read_array()?))
+    }
+
+    // angles are sent as steps of 1/256 of a full turn, this gives them back in degrees
+    pub fn read_angle(&mut self) -> Result<f32, ProtocolError>{
+        Ok(self.read_i8()? as f32 * 360.0 / 256.0)
+    }
+
+    pub fn read_varint(&mut self) -> Result<i32, ProtocolError>{
+        let mut out: u32 = 0;
+
+        for i in 0..5{
+            let byte = self.read_u8()?;
+            // the 5th byte only has room for the top 4 bits of the number
+            if i == 4 && byte & 0xF0 != 0{
+                return Err(ProtocolError::VarIntTooBig);
+            }
+
+            out |= ((byte & 0x7F) as u32) << (7 * i);
+            if byte & 0x80 == 0{
+                return Ok(out as i32);
+            }
+        }
+
+        Err(ProtocolError::VarIntTooBig)
+    }
+
+    pub fn read_varlong(&mut self) -> Result<i64, ProtocolError>{
+        let mut out: u64 = 0;
+
+        for i in 0..10{
+            let byte = self.read_u8()?;
+            // the 10th byte only has room for the top bit of the number
+            if i == 9 && byte & 0xFE != 0{
+                return Err(ProtocolError::VarLongTooBig);
+            }
+
+            out |= ((byte & 0x7F) as u64) << (7 * i);
+            if byte & 0x80 == 0{
+                return Ok(out as i64);
+            }
+        }
+
+        Err(ProtocolError::VarLongTooBig)
+    }
+
+    // a varint length followed by that many bytes, without copying them
+    pub fn read_byte_array(&mut self) -> Result<&'a [u8], ProtocolError>{
+        let length = self.read_varint()?;
+        if length < 0{
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        self.read_bytes(length as usize)
+    }
+
+    pub fn read_str(&mut self) -> Result<&'a str, ProtocolError>{
+        let bytes = self.read_byte_array()?;
+        std::str::from_utf8(bytes).map_err(|err| ProtocolError::InvalidValue(format!("string is not valid utf-8: {}", err)))
+    }
+
+    pub fn read_string(&mut self) -> Result<String, ProtocolError>{
+        let bytes = self.read_byte_array()?;
+        Ok(String::from_utf8_lossy(bytes).replace('\0', ""))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        crate::packets::{
+            EncryptionRequest,
+            Handshake,
+            LoginSuccess,
+            NextState,
+        },
+    };
+
+    #[test]
+    fn reads_past_the_end_are_errors(){
+        let mut reader = PacketReader::new(&[0x00, 0x01, 0x02]);
+        assert!(matches!(reader.read_i32(), Err(ProtocolError::UnexpectedEof{needed: 4, remaining: 3})));
+        // a failed read doesn't move the cursor
+        assert_eq!(reader.position(), 0);
+        assert_eq!(reader.read_u16().unwrap(), 0x0001);
+        assert!(matches!(reader.read_u16(), Err(ProtocolError::UnexpectedEof{needed: 2, remaining: 1})));
+        assert_eq!(reader.read_u8().unwrap(), 0x02);
+        assert!(reader.is_empty());
+        assert!(reader.read_bool().is_err());
+        assert!(reader.read_remaining().is_empty());
+    }
+
+    #[test]
+    fn byte_arrays_check_their_length(){
+        let mut reader = PacketReader::new(&[0x03, b'a', b'b']);
+        assert!(matches!(reader.read_byte_array(), Err(ProtocolError::UnexpectedEof{needed: 3, remaining: 2})));
+
+        let mut buf = varint_write(-1);
+        buf.push(b'a');
+        assert!(matches!(PacketReader::new(&buf).read_string(), Err(ProtocolError::NegativeLength(-1))));
+
+        assert!(matches!(PacketReader::new(&[0x02, 0xC3, 0x28]).read_str(), Err(ProtocolError::InvalidValue(_))));
+        assert_eq!(PacketReader::new(&[0x02, 0xC3, 0xA9]).read_str().unwrap(), "é");
+    }
+
+    #[test]
+    fn strings_round_trip_and_respect_the_max(){
+        let mut buf = Vec::new();
+        write_string(&mut buf, "héllo", 5).unwrap();
+        assert_eq!(buf[0], 6);
+        assert_eq!(PacketReader::new(&buf).read_string().unwrap(), "héllo");
+
+        assert!(matches!(write_string(&mut Vec::new(), "héllo", 4), Err(ProtocolError::StringTooLong{max: 4, length: 5})));
+    }
+
+    #[test]
+    fn block_pos_packing(){
+        for pos in [BlockPos::new(0, 0, 0), BlockPos::new(-1, -64, -1), BlockPos::new(33554431, 2047, -33554432), BlockPos::new(18357644, 831, -20882616)]{
+            assert_eq!(BlockPos::from_packed(pos.packed()), pos);
+        }
+        // the example from the protocol docs
+        assert_eq!(BlockPos::from_packed(0x4607632C15B4833F), BlockPos::new(18357644, 831, -20882616));
+    }
+
+    #[test]
+    fn handshake_layout(){
+        let handshake = Handshake{protocol_version: 763, server_address: "localhost".to_string(), server_port: 25565, next_state: NextState::Login};
+        let body = packet_body(&handshake).unwrap();
+
+        let mut reader = PacketReader::new(&body);
+        assert_eq!(reader.read_varint().unwrap(), Handshake::ID);
+        assert_eq!(reader.read_varint().unwrap(), 763);
+        assert_eq!(reader.read_string().unwrap(), "localhost");
+        assert_eq!(reader.read_u16().unwrap(), 25565);
+        assert_eq!(reader.read_u8().unwrap(), 2);
+        assert!(reader.is_empty());
+
+        assert_eq!(packer(vec![1, 2, 3]), vec![3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn truncated_packets_fail_to_decode(){
+        let mut body = Vec::new();
+        body.extend_from_slice(&0x1234u128.to_be_bytes());
+        write_string(&mut body, "Steve", 16).unwrap();
+        body.push(1); // one property
+        write_string(&mut body, "textures", 64).unwrap();
+        write_string(&mut body, "e30=", 32767).unwrap();
+        body.push(0); // unsigned
+
+        let success = PacketReader::new(&body).read::<LoginSuccess>().unwrap();
+        assert_eq!(success.uuid, 0x1234);
+        assert_eq!(success.username, "Steve");
+        assert_eq!(success.properties.len(), 1);
+        assert!(success.properties[0].signature.is_none());
+
+        // every prefix of the packet is missing something and has to be an error, not a panic
+        for end in 0..body.len(){
+            assert!(PacketReader::new(&body[..end]).read::<LoginSuccess>().is_err(), "decoded a packet cut at {}", end);
+        }
+    }
+
+    #[test]
+    fn garbage_doesnt_panic(){
+        let inputs: [&[u8]; 5] = [
+            &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+            &[0x00, 0x7F, 0x00],
+            &[0x80, 0x80, 0x80, 0x80, 0x08],
+            &[0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x00],
+            &[],
+        ];
+        for input in inputs{
+            assert!(PacketReader::new(input).read::<EncryptionRequest>().is_err());
+        }
+    }
+
+    #[test]
+    fn varint_known_values(){
+        let cases: [(i32, &[u8]); 11] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (255, &[0xFF, 0x01]),
+            (25565, &[0xDD, 0xC7, 0x01]),
+            (2097151, &[0xFF, 0xFF, 0x7F]),
+            (2147483647, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+            (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            (-2147483648, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+            (-128, &[0x80, 0xFF, 0xFF, 0xFF, 0x0F]),
+        ];
+        for (value, bytes) in cases{
+            assert_eq!(varint_write(value), bytes, "writing {}", value);
+            let mut reader = PacketReader::new(bytes);
+            assert_eq!(reader.read_varint().unwrap(), value);
+            assert!(reader.is_empty());
+        }
+    }
+
+    #[test]
+    fn varlong_known_values(){
+        let cases: [(i64, &[u8]); 7] = [
+            (0, &[0x00]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (2147483647, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+            (9223372036854775807, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]),
+            (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]),
+            (-9223372036854775808, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]),
+        ];
+        for (value, bytes) in cases{
+            assert_eq!(varlong_write(value), bytes, "writing {}", value);
+            let mut reader = PacketReader::new(bytes);
+            assert_eq!(reader.read_varlong().unwrap(), value);
+            assert!(reader.is_empty());
+        }
+    }
+
+    #[test]
+    fn varint_round_trip(){
+        for shift in 0..32{
+            for value in [1i32 << shift, (1i32 << shift).wrapping_sub(1), (1i32 << shift).wrapping_neg(), !(1i32 << shift)]{
+                let bytes = varint_write(value);
+                assert!(bytes.len() <= 5);
+                assert_eq!(PacketReader::new(&bytes).read_varint().unwrap(), value);
+            }
+        }
+        for shift in 0..64{
+            for value in [1i64 << shift, (1i64 << shift).wrapping_sub(1), (1i64 << shift).wrapping_neg(), !(1i64 << shift)]{
+                let bytes = varlong_write(value);
+                assert!(bytes.len() <= 10);
+                assert_eq!(PacketReader::new(&bytes).read_varlong().unwrap(), value);
+            }
+        }
+    }
+
+    #[test]
+    fn varint_too_big(){
+        // a 6th byte
+        assert!(matches!(PacketReader::new(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x01]).read_varint(), Err(ProtocolError::VarIntTooBig)));
+        // 5 bytes but the last one has bits past the 32nd set
+        assert!(matches!(PacketReader::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]).read_varint(), Err(ProtocolError::VarIntTooBig)));
+        assert!(matches!(PacketReader::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x7F]).read_varint(), Err(ProtocolError::VarIntTooBig)));
+
+        assert!(matches!(PacketReader::new(&[0x80; 11]).read_varlong(), Err(ProtocolError::VarLongTooBig)));
+        assert!(matches!(PacketReader::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02]).read_varlong(), Err(ProtocolError::VarLongTooBig)));
+    }
+
+    #[test]
+    fn truncated_varints(){
+        for bytes in [&[][..], &[0x80], &[0xFF, 0xFF], &[0x80, 0x80, 0x80, 0x80]]{
+            assert!(matches!(PacketReader::new(bytes).read_varint(), Err(ProtocolError::UnexpectedEof{..})), "{:?}", bytes);
+            assert!(matches!(PacketReader::new(bytes).read_varlong(), Err(ProtocolError::UnexpectedEof{..})), "{:?}", bytes);
+        }
+        assert!(matches!(PacketReader::new(&[0xFF; 9]).read_varlong(), Err(ProtocolError::UnexpectedEof{..})));
+    }
+}
@@ -0,0 +1,85 @@
+use {
+    std::{
+        io::Write,
+        net::{
+            TcpStream,
+            ToSocketAddrs,
+        },
+    },
+    crate::{
+        codec::{
+            Encode,
+            Packet,
+            ProtocolError,
+            packet_body,
+        },
+        encryption::{
+            CipherStream,
+            StreamCipher,
+        },
+        frame::{
+            FrameReader,
+            PacketCodec,
+        },
+    }
+};
+
+// a blocking connection to a server, every state (status, login, play) goes through the same
+// framing, compression and encryption here so nothing has to care about Set Compression except this
+pub struct Connection{
+    stream: CipherStream<TcpStream>,
+    frames: FrameReader<CipherStream<TcpStream>>,
+    codec: PacketCodec,
+}
+
+impl Connection{
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, ProtocolError>{
+        Connection::from_stream(TcpStream::connect(addr)?)
+    }
+
+    pub fn from_stream(stream: TcpStream) -> Result<Self, ProtocolError>{
+        let frames = FrameReader::new(CipherStream::new(stream.try_clone()?));
+        Ok(Connection{stream: CipherStream::new(stream), frames, codec: PacketCodec::new()})
+    }
+
+    pub fn codec(&self) -> &PacketCodec{
+        &self.codec
+    }
+
+    pub fn set_compression(&mut self, threshold: i32){
+        self.codec.set_compression(threshold);
+    }
+
+    pub fn is_encrypted(&self) -> bool{
+        self.stream.is_encrypted()
+    }
+
+    // everything after Encryption Response is encrypted in both directions
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) -> Result<(), ProtocolError>{
+        if !self.frames.buffered().is_empty(){
+            return Err(ProtocolError::InvalidValue("server sent data before encryption was turned on".to_string()));
+        }
+
+        let (encryptor, decryptor) = StreamCipher::new(shared_secret).split();
+        self.stream.set_encryptor(encryptor);
+        self.frames.get_mut().set_decryptor(decryptor);
+        Ok(())
+    }
+
+    pub fn send<P: Packet + Encode>(&mut self, packet: &P) -> Result<(), ProtocolError>{
+        self.send_raw(&packet_body(packet)?)
+    }
+
+    // packet ID + body that was already encoded somewhere else
+    pub fn send_raw(&mut self, packet: &[u8]) -> Result<(), ProtocolError>{
+        let frame = self.codec.encode_packet(packet)?;
+        self.stream.write_all(frame.as_slice())?;
+        Ok(())
+    }
+
+    // next packet as (packet ID, body)
+    pub fn receive(&mut self) -> Result<(i32, Vec<u8>), ProtocolError>{
+        let frame = self.frames.read_frame()?;
+        self.codec.decode_frame(&frame)
+    }
+}
@@ -0,0 +1,262 @@
+use {
+    aes::{
+        Aes128,
+        cipher::{
+            BlockDecryptMut,
+            BlockEncryptMut,
+            KeyIvInit,
+            generic_array::GenericArray,
+        },
+    },
+    rand::RngCore,
+    rsa::{
+        Pkcs1v15Encrypt,
+        RsaPublicKey,
+        pkcs8::DecodePublicKey,
+    },
+    sha1::{
+        Digest,
+        Sha1,
+    },
+    std::io::{
+        Read,
+        Write,
+    },
+    crate::codec::ProtocolError,
+};
+
+type Aes128Cfb8Enc = cfb8::Encryptor<Aes128>;
+type Aes128Cfb8Dec = cfb8::Decryptor<Aes128>;
+
+pub fn generate_shared_secret() -> [u8; 16]{
+    let mut secret = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+// encrypts with the DER encoded public key the server sent in Encryption Request
+pub fn rsa_encrypt(public_key: &[u8], data: &[u8]) -> Result<Vec<u8>, ProtocolError>{
+    let key = RsaPublicKey::from_public_key_der(public_key)
+        .map_err(|err| ProtocolError::InvalidValue(format!("bad server public key: {}", err)))?;
+    key.encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, data)
+        .map_err(|err| ProtocolError::InvalidValue(format!("rsa encryption failed: {}", err)))
+}
+
+// sha1 of server id + shared secret + public key, printed the way java's BigInteger.toString(16) does it
+// (signed, so a hash with the top bit set comes out negative and without leading zeros)
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String{
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    let mut hash: [u8; 20] = hasher.finalize().into();
+
+    let negative = hash[0] & 0x80 != 0;
+    if negative{
+        // two's complement
+        let mut carry = true;
+        for byte in hash.iter_mut().rev(){
+            *byte = !*byte;
+            if carry{
+                let (value, overflow) = byte.overflowing_add(1);
+                *byte = value;
+                carry = overflow;
+            }
+        }
+    }
+
+    let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let hex = hex.trim_start_matches('0');
+    let hex = if hex.is_empty(){"0"} else {hex};
+
+    if negative{
+        format!("-{}", hex)
+    } else {
+        hex.to_string()
+    }
+}
+
+// AES-128 in CFB8 mode with the shared secret as both key and IV, the cipher state
+// carries over between packets so there is one of these per direction for the whole connection
+pub struct StreamCipher{
+    encryptor: Aes128Cfb8Enc,
+    decryptor: Aes128Cfb8Dec,
+}
+
+impl StreamCipher{
+    pub fn new(shared_secret: &[u8; 16]) -> Self{
+        StreamCipher{
+            encryptor: Aes128Cfb8Enc::new(shared_secret.into(), shared_secret.into()),
+            decryptor: Aes128Cfb8Dec::new(shared_secret.into(), shared_secret.into()),
+        }
+    }
+
+    pub fn split(self) -> (Encryptor, Decryptor){
+        (Encryptor(self.encryptor), Decryptor(self.decryptor))
+    }
+}
+
+pub struct Encryptor(Aes128Cfb8Enc);
+
+impl Encryptor{
+    pub fn encrypt(&mut self, data: &mut [u8]){
+        for byte in data.chunks_mut(1){
+            self.0.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+        }
+    }
+}
+
+pub struct Decryptor(Aes128Cfb8Dec);
+
+impl Decryptor{
+    pub fn decrypt(&mut self, data: &mut [u8]){
+        for byte in data.chunks_mut(1){
+            self.0.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+        }
+    }
+}
+
+// a stream that can be switched over to encryption halfway through, which is what happens
+// right after Encryption Response
+pub struct CipherStream<S>{
+    inner: S,
+    encryptor: Option<Encryptor>,
+    decryptor: Option<Decryptor>,
+}
+
+impl<S> CipherStream<S>{
+    pub fn new(inner: S) -> Self{
+        CipherStream{inner, encryptor: None, decryptor: None}
+    }
+
+    pub fn is_encrypted(&self) -> bool{
+        self.encryptor.is_some() || self.decryptor.is_some()
+    }
+
+    pub fn set_encryptor(&mut self, encryptor: Encryptor){
+        self.encryptor = Some(encryptor);
+    }
+
+    pub fn set_decryptor(&mut self, decryptor: Decryptor){
+        self.decryptor = Some(decryptor);
+    }
+
+    pub fn get_ref(&self) -> &S{
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S{
+        &mut self.inner
+    }
+}
+
+impl<S: Read> Read for CipherStream<S>{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>{
+        let size = self.inner.read(buf)?;
+        if let Some(decryptor) = &mut self.decryptor{
+            decryptor.decrypt(&mut buf[..size]);
+        }
+        Ok(size)
+    }
+}
+
+impl<S: Write> Write for CipherStream<S>{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize>{
+        match &mut self.encryptor{
+            Some(encryptor) => {
+                // the cipher can't be rewound, so everything has to go out once it's encrypted
+                let mut data = buf.to_vec();
+                encryptor.encrypt(&mut data);
+                self.inner.write_all(&data)?;
+                Ok(buf.len())
+            },
+            None => self.inner.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()>{
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn server_hash_matches_java(){
+        // the usual examples, sha1 of just the name
+        assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+        // the parts are just concatenated
+        assert_eq!(server_hash("", b"No", b"tch"), server_hash("Notch", &[], &[]));
+    }
+
+    // NIST SP 800-38A F.3.7, CFB8-AES128
+    const KEY: [u8; 16] = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+    const IV: [u8; 16] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f];
+    const PLAINTEXT: [u8; 18] = [0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a, 0xae, 0x2d];
+    const CIPHERTEXT: [u8; 18] = [0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a, 0x4f, 0x32, 0xb9];
+
+    #[test]
+    fn cfb8_carries_over_between_chunks(){
+        for split in [[0, 18, 18], [1, 8, 18], [7, 16, 17], [16, 16, 18]]{
+            let mut encryptor = Encryptor(Aes128Cfb8Enc::new(&KEY.into(), &IV.into()));
+            let mut decryptor = Decryptor(Aes128Cfb8Dec::new(&KEY.into(), &IV.into()));
+
+            let mut data = PLAINTEXT;
+            let (first, rest) = data.split_at_mut(split[0]);
+            let (second, rest) = rest.split_at_mut(split[1] - split[0]);
+            let (third, fourth) = rest.split_at_mut(split[2] - split[1]);
+            for chunk in [first, second, third, fourth]{
+                encryptor.encrypt(chunk);
+            }
+            assert_eq!(data, CIPHERTEXT, "split at {:?}", split);
+
+            // decrypting in different pieces than it was encrypted in
+            let (first, rest) = data.split_at_mut(18 - split[2]);
+            let (second, third) = rest.split_at_mut(18 - split[1] - first.len());
+            for chunk in [first, second, third]{
+                decryptor.decrypt(chunk);
+            }
+            assert_eq!(data, PLAINTEXT, "split at {:?}", split);
+        }
+    }
+
+    #[test]
+    fn cipher_stream_switches_over(){
+        let secret = IV;
+        let (encryptor, _) = StreamCipher::new(&secret).split();
+        let mut stream = CipherStream::new(Vec::new());
+        stream.write_all(b"plain").unwrap();
+        stream.set_encryptor(encryptor);
+        assert!(stream.is_encrypted());
+        for chunk in PLAINTEXT.chunks(5){
+            stream.write_all(chunk).unwrap();
+        }
+
+        let written = stream.get_ref().clone();
+        assert_eq!(&written[..5], b"plain");
+        let mut expected = PLAINTEXT;
+        StreamCipher::new(&secret).split().0.encrypt(&mut expected);
+        assert_eq!(&written[5..], &expected);
+
+        let (_, decryptor) = StreamCipher::new(&secret).split();
+        let mut stream = CipherStream::new(&written[..]);
+        let mut plain = [0; 5];
+        stream.read_exact(&mut plain).unwrap();
+        assert_eq!(&plain, b"plain");
+        stream.set_decryptor(decryptor);
+        let mut buf = [0; 3];
+        let mut read = Vec::new();
+        loop{
+            let size = stream.read(&mut buf).unwrap();
+            if size == 0{
+                break;
+            }
+            read.extend_from_slice(&buf[..size]);
+        }
+        assert_eq!(read, PLAINTEXT);
+    }
+}
@@ -0,0 +1,294 @@
+use {
+    std::collections::HashMap,
+    crate::{
+        codec::Vec3,
+        metadata::{
+            MetadataValue,
+            Pose,
+            Slot,
+        },
+        packets::{
+            SpawnEntity,
+            SpawnPlayer,
+        },
+        registry::EntityType,
+        version::ProtocolVersion,
+    },
+};
+
+// velocities are sent in 1/8000 of a block per tick
+const VELOCITY_SCALE: f64 = 8000.0;
+// and position deltas in 1/4096 of a block
+const DELTA_SCALE: f64 = 4096.0;
+
+// something the server told us exists, other than ourselves
+#[derive(Debug, Clone)]
+pub struct Entity{
+    pub id: i32,
+    pub uuid: u128,
+    pub entity_type: i32, // registry ID in version, see kind
+    pub version: ProtocolVersion,
+    pub position: Vec3,
+    pub velocity: Vec3, // blocks per tick
+    pub yaw: f32,
+    pub pitch: f32,
+    pub head_yaw: f32,
+    pub on_ground: bool,
+    pub data: i32, // meaning depends on the type, see Spawn Entity on wiki.vg
+    // by index, what each index means is in kind().metadata_fields(version). only what the server has
+    // sent is here, anything else still has the vanilla default
+    pub metadata: HashMap<u8, MetadataValue>,
+    // what the server thinks the position is, the deltas are relative to this and not to position
+    base: Vec3,
+}
+
+impl Entity{
+    pub fn new(id: i32, uuid: u128, entity_type: i32, version: ProtocolVersion, position: Vec3) -> Self{
+        Entity{
+            id,
+            uuid,
+            entity_type,
+            version,
+            position,
+            velocity: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            head_yaw: 0.0,
+            on_ground: false,
+            data: 0,
+            metadata: HashMap::new(),
+            base: position,
+        }
+    }
+
+    // None for an ID the version's registry doesn't have
+    pub fn kind(&self) -> Option<EntityType>{
+        EntityType::from_version_id(self.version, self.entity_type as u32)
+    }
+
+    pub fn type_name(&self) -> String{
+        match self.kind(){
+            Some(kind) => kind.display_name(),
+            None => "Unknown".to_string(),
+        }
+    }
+
+    pub fn is_player(&self) -> bool{
+        self.kind() == Some(EntityType::Player)
+    }
+
+    // a metadata value by the name entity_metadata.json gives it, like "health" or "custom_name"
+    pub fn field(&self, name: &str) -> Option<&MetadataValue>{
+        let field = self.kind()?.metadata_field(self.version, name)?;
+        self.metadata.get(&field.index).filter(|value| value.kind() == field.kind)
+    }
+
+    pub fn set_metadata(&mut self, entries: Vec<(u8, MetadataValue)>){
+        self.metadata.extend(entries);
+    }
+
+    // None for anything that isn't alive, or before the server has sent it
+    pub fn health(&self) -> Option<f32>{
+        self.field("health")?.as_float()
+    }
+
+    // json, like chat
+    pub fn custom_name(&self) -> Option<&str>{
+        self.field("custom_name")?.as_str()
+    }
+
+    pub fn pose(&self) -> Pose{
+        match self.field("pose"){
+            Some(MetadataValue::Pose(pose)) => *pose,
+            _ => Pose::Standing,
+        }
+    }
+
+    // the item of a dropped item, item frame, thrown projectile or item display
+    pub fn item(&self) -> Option<&Slot>{
+        self.field("item")?.as_slot()
+    }
+
+    pub fn is_baby(&self) -> bool{
+        self.field("baby").and_then(MetadataValue::as_bool).unwrap_or(false)
+    }
+
+    // applies a relative move the way the vanilla client does: the delta is added to the last
+    // absolute position in fixed point, so rounding errors don't add up over many moves. an axis
+    // without a delta keeps its value untouched
+    pub fn move_by(&mut self, dx: i16, dy: i16, dz: i16){
+        if dx == 0 && dy == 0 && dz == 0{
+            return;
+        }
+        let apply = |base: f64, delta: i16| if delta == 0{
+            base
+        } else {
+            ((base * DELTA_SCALE).round() as i64 + delta as i64) as f64 / DELTA_SCALE
+        };
+        self.base = Vec3::new(apply(self.base.x, dx), apply(self.base.y, dy), apply(self.base.z, dz));
+        self.position = self.base;
+    }
+
+    pub fn teleport(&mut self, position: Vec3){
+        self.base = position;
+        self.position = position;
+    }
+
+    pub fn set_velocity(&mut self, x: i16, y: i16, z: i16){
+        self.velocity = Vec3::new(x as f64, y as f64, z as f64) * (1.0 / VELOCITY_SCALE);
+    }
+}
+
+// every entity the server has spawned for us and not removed yet, by entity ID
+#[derive(Debug, Clone, Default)]
+pub struct EntityTracker{
+    entities: HashMap<i32, Entity>,
+}
+
+impl EntityTracker{
+    pub fn new() -> Self{
+        EntityTracker::default()
+    }
+
+    pub fn len(&self) -> usize{
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.entities.is_empty()
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Entity>{
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: i32) -> Option<&mut Entity>{
+        self.entities.get_mut(&id)
+    }
+
+    pub fn by_uuid(&self, uuid: u128) -> Option<&Entity>{
+        self.entities.values().find(|entity| entity.uuid == uuid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity>{
+        self.entities.values()
+    }
+
+    // a spawn for an ID we already have replaces that entity, same as vanilla
+    pub fn insert(&mut self, entity: Entity){
+        self.entities.insert(entity.id, entity);
+    }
+
+    pub fn spawn(&mut self, packet: &SpawnEntity){
+        let mut entity = Entity::new(packet.entity_id, packet.uuid, packet.entity_type, packet.version, Vec3::new(packet.x, packet.y, packet.z));
+        entity.yaw = packet.yaw;
+        entity.pitch = packet.pitch;
+        entity.head_yaw = packet.head_yaw;
+        entity.data = packet.data;
+        entity.set_velocity(packet.velocity_x, packet.velocity_y, packet.velocity_z);
+        self.insert(entity);
+    }
+
+    // before 1.20.2 players had their own spawn packet
+    pub fn spawn_player(&mut self, packet: &SpawnPlayer){
+        let entity_type = EntityType::Player.version_id(packet.version).map_or(-1, |id| id as i32);
+        let mut entity = Entity::new(packet.entity_id, packet.uuid, entity_type, packet.version, Vec3::new(packet.x, packet.y, packet.z));
+        entity.yaw = packet.yaw;
+        entity.pitch = packet.pitch;
+        entity.head_yaw = packet.yaw;
+        self.insert(entity);
+    }
+
+    pub fn remove(&mut self, id: i32) -> Option<Entity>{
+        self.entities.remove(&id)
+    }
+
+    pub fn clear(&mut self){
+        self.entities.clear();
+    }
+
+    // every entity within radius blocks of position, in no particular order
+    pub fn within(&self, position: Vec3, radius: f64) -> impl Iterator<Item = &Entity>{
+        self.entities.values().filter(move |entity| entity.position.distance_squared(position) <= radius * radius)
+    }
+
+    // the closest entity within radius blocks that the filter accepts
+    pub fn nearest<F: Fn(&Entity) -> bool>(&self, position: Vec3, radius: f64, filter: F) -> Option<&Entity>{
+        self.within(position, radius)
+            .filter(|entity| filter(entity))
+            .min_by(|a, b| a.position.distance_squared(position).total_cmp(&b.position.distance_squared(position)))
+    }
+
+    pub fn nearest_of_kind(&self, kind: EntityType, position: Vec3, radius: f64) -> Option<&Entity>{
+        self.nearest(position, radius, |entity| entity.kind() == Some(kind))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        crate::codec::{
+            PacketReader,
+            varint_write,
+        },
+    };
+
+    fn spawn_entity(version: ProtocolVersion, entity_type: i32) -> SpawnEntity{
+        let mut data = varint_write(7);
+        data.extend(0x1234_u128.to_be_bytes());
+        data.extend(varint_write(entity_type));
+        for coordinate in [1.5_f64, 64.0, -2.5]{
+            data.extend(coordinate.to_be_bytes());
+        }
+        data.extend([0, 64, 64]);
+        data.extend(varint_write(0));
+        for velocity in [8000_i16, 0, -4000]{
+            data.extend(velocity.to_be_bytes());
+        }
+        let mut reader = PacketReader::with_version(&data, version);
+        let packet = reader.read::<SpawnEntity>().unwrap();
+        assert_eq!(reader.remaining(), 0);
+        packet
+    }
+
+    #[test]
+    fn entity_types_come_from_the_servers_version(){
+        assert_eq!(spawn_entity(ProtocolVersion::V1_20_1, 10).kind(), Some(EntityType::Camel));
+        assert_eq!(spawn_entity(ProtocolVersion::V1_20_4, 10).kind(), Some(EntityType::Breeze));
+        assert_eq!(spawn_entity(ProtocolVersion::V1_20_4, 11).type_name(), "Camel");
+        assert_eq!(spawn_entity(ProtocolVersion::V1_20_1, 500).type_name(), "Unknown");
+
+        let mut entities = EntityTracker::new();
+        entities.spawn(&spawn_entity(ProtocolVersion::V1_20_4, 124));
+        let entity = entities.get(7).unwrap();
+        assert!(entity.is_player());
+        assert_eq!(entity.uuid, 0x1234);
+        assert_eq!(entity.position, Vec3::new(1.5, 64.0, -2.5));
+        assert_eq!(entity.velocity, Vec3::new(1.0, 0.0, -0.5));
+        assert_eq!((entity.yaw, entity.head_yaw), (90.0, 90.0));
+        assert!(entities.nearest_of_kind(EntityType::Player, Vec3::ZERO, 100.0).is_some());
+        assert!(entities.nearest_of_kind(EntityType::Player, Vec3::ZERO, 1.0).is_none());
+
+        entities.spawn_player(&SpawnPlayer{entity_id: 8, uuid: 1, version: ProtocolVersion::V1_19_4, x: 0.0, y: 0.0, z: 0.0, yaw: 0.0, pitch: 0.0});
+        let player = entities.get(8).unwrap();
+        assert_eq!(player.entity_type, 122);
+        assert!(player.is_player());
+    }
+
+    #[test]
+    fn relative_moves_add_up_in_fixed_point(){
+        let mut entity = Entity::new(1, 0, 0, ProtocolVersion::V1_20_1, Vec3::new(0.1, 0.0, 0.0));
+        for _ in 0..4096{
+            entity.move_by(1, 0, 0);
+        }
+        // 0.1 is rounded to 410/4096 once, not on every move
+        assert_eq!(entity.position, Vec3::new((410 + 4096) as f64 / 4096.0, 0.0, 0.0));
+        entity.move_by(0, -4096, 0);
+        assert_eq!(entity.position.y, -1.0);
+
+        entity.teleport(Vec3::new(5.0, 5.0, 5.0));
+        entity.move_by(0, 0, 2048);
+        assert_eq!(entity.position, Vec3::new(5.0, 5.0, 5.5));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    client::ClientHandle,
+    codec::Vec3,
+    packets::{
+        EntityAnimation,
+        SpawnEntity,
+        SpawnPlayer,
+    },
+    text::TextComponent,
+    world::BlockChange,
+};
+
+// things that happen to the client, read them with Client::next_event or hand a Handler to Client::run
+#[derive(Debug, Clone)]
+pub enum Event{
+    // Login (play) arrived, the rest of it is in ClientState::join_game
+    Joined{entity_id: i32, game_mode: u8, dimension: String},
+    // we died and came back, or went to another dimension
+    Respawned{dimension: String},
+    // the server put us somewhere, on joining, respawning or when it didn't like a move of ours
+    Teleported{position: Vec3, yaw: f32, pitch: f32},
+    EntitySpawned(SpawnEntity),
+    PlayerSpawned(SpawnPlayer),
+    // the server stopped telling us about these, they're already gone from ClientState::entities
+    EntitiesRemoved(Vec<i32>),
+    Animation(EntityAnimation),
+    // the navigator got us to its goal (goals that move, like GoalFollow, never finish)
+    GoalReached,
+    // the navigator found no way to its goal and gave up on it
+    NoPath,
+    // a block in a loaded chunk is now something else
+    BlockChanged(BlockChange),
+    // sender is None for system messages and disguised chat. name is the sender's name from the
+    // tab list, or else the name the server sent as plain text, None for system messages
+    Chat{sender: Option<u128>, name: Option<String>, message: TextComponent, overlay: bool},
+    // a custom payload from the server, in the play or configuration state
+    PluginMessage{channel: String, data: Vec<u8>},
+    // a play packet the client doesn't understand yet, as (packet ID, body)
+    Packet{id: i32, data: Vec<u8>},
+    // the connection is gone, this is always the last event. reason is plain text
+    Disconnected{reason: String},
+}
+
+// callback style alternative to polling next_event, the handle can be used to send packets back
+pub trait Handler{
+    fn on_event(&mut self, client: &ClientHandle, event: &Event);
+}
+
+impl<F: FnMut(&ClientHandle, &Event)> Handler for F{
+    fn on_event(&mut self, client: &ClientHandle, event: &Event){
+        self(client, event)
+    }
+}
@@ -0,0 +1,439 @@
+use {
+    flate2::{
+        Compression,
+        read::ZlibDecoder,
+        write::ZlibEncoder,
+    },
+    std::io::{
+        ErrorKind,
+        Read,
+        Write,
+    },
+    crate::codec::{
+        PacketReader,
+        ProtocolError,
+        packer,
+        varint_write,
+    }
+};
+
+// the packet length is at most a 3 byte varint, so no frame can be bigger than this
+pub const MAX_FRAME_SIZE: usize = (1 << 21) - 1;
+
+pub const READ_CHUNK: usize = 8192;
+
+// the part of frame reading that doesn't care where the bytes come from, shared by the
+// blocking FrameReader and the async connection. bytes go in with extend and complete
+// frames come out of next_frame
+pub struct FrameBuffer{
+    buf: Vec<u8>,
+    start: usize,
+    max_frame_size: usize,
+}
+
+impl FrameBuffer{
+    pub fn new(max_frame_size: usize) -> Self{
+        FrameBuffer{buf: Vec::new(), start: 0, max_frame_size}
+    }
+
+    pub fn max_frame_size(&self) -> usize{
+        self.max_frame_size
+    }
+
+    pub fn set_max_frame_size(&mut self, max_frame_size: usize){
+        self.max_frame_size = max_frame_size;
+    }
+
+    // bytes that were read from the stream but are not part of a returned frame yet
+    pub fn buffered(&self) -> &[u8]{
+        &self.buf[self.start..]
+    }
+
+    pub fn extend(&mut self, data: &[u8]){
+        // move the unread tail to the front so the buffer doesn't grow forever
+        if self.start > 0{
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    // a complete frame without its length prefix, or None if more bytes are needed first
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>, ProtocolError>{
+        let mut reader = PacketReader::new(&self.buf[self.start..]);
+        let length = match reader.read_varint(){
+            Ok(length) => length,
+            Err(ProtocolError::UnexpectedEof{..}) => return Ok(None), // the length itself isn't all here yet
+            Err(err) => return Err(err),
+        };
+
+        if length < 0{
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        let length = length as usize;
+        if length > self.max_frame_size{
+            return Err(ProtocolError::FrameTooLarge{size: length, max: self.max_frame_size});
+        }
+
+        let header = reader.position();
+        if reader.remaining() < length{
+            return Ok(None);
+        }
+
+        let frame_start = self.start + header;
+        let frame = self.buf[frame_start..frame_start + length].to_vec();
+        self.start = frame_start + length;
+
+        if self.start == self.buf.len(){
+            self.buf.clear();
+            self.start = 0;
+        }
+        Ok(Some(frame))
+    }
+
+    // what running out of bytes means depends on whether we were halfway through a frame
+    pub fn eof_error(&self) -> ProtocolError{
+        if self.buffered().is_empty(){
+            ProtocolError::ConnectionClosed
+        } else {
+            ProtocolError::UnexpectedEof{needed: 1, remaining: 0}
+        }
+    }
+}
+
+// buffers whatever the stream hands us and only gives back complete length-prefixed frames,
+// so a packet split over several TCP segments (or several packets in one segment) is fine
+pub struct FrameReader<R: Read>{
+    inner: R,
+    frames: FrameBuffer,
+}
+
+impl<R: Read> FrameReader<R>{
+    pub fn new(inner: R) -> Self{
+        FrameReader::with_max_frame_size(inner, MAX_FRAME_SIZE)
+    }
+
+    pub fn with_max_frame_size(inner: R, max_frame_size: usize) -> Self{
+        FrameReader{inner, frames: FrameBuffer::new(max_frame_size)}
+    }
+
+    pub fn max_frame_size(&self) -> usize{
+        self.frames.max_frame_size()
+    }
+
+    pub fn set_max_frame_size(&mut self, max_frame_size: usize){
+        self.frames.set_max_frame_size(max_frame_size);
+    }
+
+    pub fn get_ref(&self) -> &R{
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R{
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R{
+        self.inner
+    }
+
+    pub fn buffered(&self) -> &[u8]{
+        self.frames.buffered()
+    }
+
+    // blocks until a whole frame is available and returns it without the length prefix
+    pub fn read_frame(&mut self) -> Result<Vec<u8>, ProtocolError>{
+        let mut chunk = [0u8; READ_CHUNK];
+        loop{
+            if let Some(frame) = self.frames.next_frame()?{
+                return Ok(frame);
+            }
+
+            match self.inner.read(&mut chunk){
+                Ok(0) => return Err(self.frames.eof_error()),
+                Ok(size) => self.frames.extend(&chunk[..size]),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+// vanilla refuses anything that inflates to more than this
+pub const MAX_UNCOMPRESSED_SIZE: usize = 1 << 23;
+
+// turns frames into (packet ID, body) and back, taking care of the Data Length field and
+// zlib once the server sent Set Compression. the same codec is used in every state
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PacketCodec{
+    compression_threshold: Option<usize>,
+}
+
+impl PacketCodec{
+    pub fn new() -> Self{
+        PacketCodec{compression_threshold: None}
+    }
+
+    pub fn compression_threshold(&self) -> Option<usize>{
+        self.compression_threshold
+    }
+
+    // a negative threshold turns compression back off, same as the Set Compression packet
+    pub fn set_compression(&mut self, threshold: i32){
+        self.compression_threshold = if threshold < 0{
+            None
+        } else {
+            Some(threshold as usize)
+        };
+    }
+
+    pub fn decode_frame(&self, frame: &[u8]) -> Result<(i32, Vec<u8>), ProtocolError>{
+        let mut reader = PacketReader::new(frame);
+
+        let threshold = match self.compression_threshold{
+            Some(threshold) => threshold,
+            None => {
+                let id = reader.read_varint()?;
+                return Ok((id, reader.read_remaining().to_vec()));
+            }
+        };
+
+        let data_length = reader.read_varint()?;
+        if data_length < 0{
+            return Err(ProtocolError::NegativeLength(data_length));
+        }
+        let data_length = data_length as usize;
+
+        if data_length == 0{ // too small to be worth compressing, sent as is
+            let id = reader.read_varint()?;
+            return Ok((id, reader.read_remaining().to_vec()));
+        }
+
+        if data_length < threshold{
+            return Err(ProtocolError::InvalidValue(format!("compressed packet of size {} is under the compression threshold ({})", data_length, threshold)));
+        }
+        if data_length > MAX_UNCOMPRESSED_SIZE{
+            return Err(ProtocolError::FrameTooLarge{size: data_length, max: MAX_UNCOMPRESSED_SIZE});
+        }
+
+        let packet = decompress(reader.read_remaining(), data_length)?;
+        if packet.len() != data_length{
+            return Err(ProtocolError::InvalidValue(format!("uncompressed packet size ({}) is not what it should be ({})", packet.len(), data_length)));
+        }
+
+        let mut reader = PacketReader::new(&packet);
+        let id = reader.read_varint()?;
+        Ok((id, reader.read_remaining().to_vec()))
+    }
+
+    // takes a packet ID + body (see codec::packet_body) and gives back the bytes to write to the socket
+    pub fn encode_packet(&self, packet: &[u8]) -> Result<Vec<u8>, ProtocolError>{
+        let mut inner: Vec<u8> = Vec::new();
+        match self.compression_threshold{
+            Some(threshold) if packet.len() >= threshold => {
+                inner.extend_from_slice(varint_write(packet.len() as i32).as_slice());
+                inner.extend_from_slice(compress(packet)?.as_slice());
+            },
+            Some(_) => {
+                inner.extend_from_slice(varint_write(0).as_slice());
+                inner.extend_from_slice(packet);
+            },
+            None => inner.extend_from_slice(packet),
+        }
+
+        if inner.len() > MAX_FRAME_SIZE{
+            return Err(ProtocolError::FrameTooLarge{size: inner.len(), max: MAX_FRAME_SIZE});
+        }
+        Ok(packer(inner))
+    }
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, ProtocolError>{
+    let mut compresser = ZlibEncoder::new(Vec::new(), Compression::fast());
+    compresser.write_all(data)?;
+    Ok(compresser.finish()?)
+}
+
+// never inflates more than one byte past the expected size, so a zip bomb can't eat all our memory
+fn decompress(data: &[u8], expected_size: usize) -> Result<Vec<u8>, ProtocolError>{
+    let mut buf: Vec<u8> = Vec::with_capacity(expected_size);
+    ZlibDecoder::new(data).take(expected_size as u64 + 1).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        std::io::{
+            self,
+            Cursor,
+        },
+    };
+
+    // hands out at most `step` bytes per read, like a socket that splits everything up
+    struct Trickle{
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+        interrupt: bool,
+    }
+
+    impl Trickle{
+        fn new(data: Vec<u8>, step: usize) -> Self{
+            Trickle{data, pos: 0, step, interrupt: false}
+        }
+    }
+
+    impl Read for Trickle{
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>{
+            // every other read gets interrupted, which has to be retried and not treated as an error
+            self.interrupt = !self.interrupt;
+            if self.interrupt{
+                return Err(io::Error::from(ErrorKind::Interrupted));
+            }
+
+            let size = self.step.min(buf.len()).min(self.data.len() - self.pos);
+            buf[..size].copy_from_slice(&self.data[self.pos..self.pos + size]);
+            self.pos += size;
+            Ok(size)
+        }
+    }
+
+    fn frames(payloads: &[Vec<u8>]) -> Vec<u8>{
+        payloads.iter().flat_map(|payload| packer(payload.clone())).collect()
+    }
+
+    #[test]
+    fn reassembles_byte_by_byte(){
+        let payloads = vec![vec![0x00], vec![0x01, 0x02, 0x03], vec![0xAB; 300], vec![0x42; 20000]];
+        for step in [1, 2, 3, 7, 128, READ_CHUNK]{
+            let mut reader = FrameReader::new(Trickle::new(frames(&payloads), step));
+            for payload in &payloads{
+                assert_eq!(&reader.read_frame().unwrap(), payload, "step {}", step);
+            }
+            assert!(matches!(reader.read_frame(), Err(ProtocolError::ConnectionClosed)));
+        }
+    }
+
+    #[test]
+    fn several_frames_in_one_read(){
+        let payloads = vec![vec![0x01], vec![0x02, 0x03], vec![0x04, 0x05, 0x06]];
+        let mut reader = FrameReader::new(Cursor::new(frames(&payloads)));
+        assert_eq!(reader.read_frame().unwrap(), vec![0x01]);
+        // the rest came in with the first read
+        assert_eq!(reader.buffered(), &[0x02, 0x02, 0x03, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(reader.read_frame().unwrap(), vec![0x02, 0x03]);
+        assert_eq!(reader.read_frame().unwrap(), vec![0x04, 0x05, 0x06]);
+        assert!(reader.buffered().is_empty());
+    }
+
+    #[test]
+    fn empty_frame(){
+        let mut reader = FrameReader::new(Cursor::new(vec![0x00, 0x01, 0x09]));
+        assert!(reader.read_frame().unwrap().is_empty());
+        assert_eq!(reader.read_frame().unwrap(), vec![0x09]);
+    }
+
+    #[test]
+    fn eof_inside_a_frame(){
+        // the length says 5 bytes but only 3 come
+        let mut reader = FrameReader::new(Trickle::new(vec![0x05, 0x01, 0x02, 0x03], 1));
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::UnexpectedEof{..})));
+
+        // the stream ends halfway through the length
+        let mut reader = FrameReader::new(Cursor::new(vec![0x80, 0x80]));
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::UnexpectedEof{..})));
+
+        let mut reader = FrameReader::new(Cursor::new(Vec::new()));
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::ConnectionClosed)));
+    }
+
+    #[test]
+    fn bad_lengths(){
+        let mut reader = FrameReader::new(Cursor::new(varint_write(-1)));
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::NegativeLength(-1))));
+
+        let mut reader = FrameReader::new(Cursor::new(vec![0x80, 0x80, 0x80, 0x80, 0x80, 0x01]));
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::VarIntTooBig)));
+
+        // too large is noticed from the length alone, without waiting for the body
+        let mut reader = FrameReader::new(Cursor::new(varint_write(MAX_FRAME_SIZE as i32 + 1)));
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::FrameTooLarge{..})));
+
+        let mut reader = FrameReader::with_max_frame_size(Cursor::new(frames(&[vec![0; 10]])), 9);
+        assert!(matches!(reader.read_frame(), Err(ProtocolError::FrameTooLarge{size: 10, max: 9})));
+        // nothing was thrown away, so the frame comes out once the limit allows it
+        reader.set_max_frame_size(10);
+        assert_eq!(reader.read_frame().unwrap(), vec![0; 10]);
+    }
+
+    #[test]
+    fn frame_buffer_needs_more(){
+        let mut frames = FrameBuffer::new(MAX_FRAME_SIZE);
+        assert!(frames.next_frame().unwrap().is_none());
+        frames.extend(&[0x82]);
+        assert!(frames.next_frame().unwrap().is_none());
+        frames.extend(&[0x01]); // 130 bytes
+        frames.extend(&[0x07; 129]);
+        assert!(frames.next_frame().unwrap().is_none());
+        frames.extend(&[0x07, 0x01]);
+        assert_eq!(frames.next_frame().unwrap().unwrap(), vec![0x07; 130]);
+        assert!(frames.next_frame().unwrap().is_none());
+        assert_eq!(frames.buffered(), &[0x01]);
+    }
+
+    fn round_trip(codec: &PacketCodec, packet: &[u8]) -> (i32, Vec<u8>){
+        let encoded = codec.encode_packet(packet).unwrap();
+        let frame = FrameReader::new(Cursor::new(encoded)).read_frame().unwrap();
+        codec.decode_frame(&frame).unwrap()
+    }
+
+    #[test]
+    fn codec_round_trip(){
+        let mut packet = varint_write(0x24);
+        packet.extend((0..1000).map(|i| i as u8));
+
+        let mut codec = PacketCodec::new();
+        assert_eq!(round_trip(&codec, &packet), (0x24, packet[1..].to_vec()));
+
+        codec.set_compression(256);
+        // over the threshold, so this one really gets compressed
+        assert!(codec.encode_packet(&packet).unwrap().len() < packet.len());
+        assert_eq!(round_trip(&codec, &packet), (0x24, packet[1..].to_vec()));
+        // under it, sent with a data length of 0
+        assert_eq!(codec.encode_packet(&[0x05, 0x01]).unwrap(), vec![0x03, 0x00, 0x05, 0x01]);
+        assert_eq!(round_trip(&codec, &[0x05, 0x01]), (0x05, vec![0x01]));
+
+        codec.set_compression(-1);
+        assert_eq!(codec.compression_threshold(), None);
+    }
+
+    #[test]
+    fn codec_rejects_bad_frames(){
+        let mut codec = PacketCodec::new();
+        assert!(codec.decode_frame(&[]).is_err());
+
+        codec.set_compression(256);
+        // the data length lies about the inflated size
+        let mut frame = varint_write(600);
+        frame.extend(compress(&[0x01; 500]).unwrap());
+        assert!(matches!(codec.decode_frame(&frame), Err(ProtocolError::InvalidValue(_))));
+
+        // claims to be compressed but is under the threshold
+        let mut frame = varint_write(10);
+        frame.extend(compress(&[0x01; 10]).unwrap());
+        assert!(matches!(codec.decode_frame(&frame), Err(ProtocolError::InvalidValue(_))));
+
+        let mut frame = varint_write(MAX_UNCOMPRESSED_SIZE as i32 + 1);
+        frame.extend(compress(&[0x01; 10]).unwrap());
+        assert!(matches!(codec.decode_frame(&frame), Err(ProtocolError::FrameTooLarge{..})));
+
+        // not zlib at all
+        let mut frame = varint_write(300);
+        frame.extend([0xDE, 0xAD, 0xBE, 0xEF]);
+        assert!(codec.decode_frame(&frame).is_err());
+
+        assert!(matches!(codec.decode_frame(&varint_write(-5)), Err(ProtocolError::NegativeLength(-5))));
+    }
+}
@@ -0,0 +1,38 @@
+pub mod async_connection;
+pub mod chat;
+pub mod client;
+pub mod codec;
+pub mod connection;
+pub mod encryption;
+pub mod entity;
+pub mod event;
+pub mod frame;
+pub mod metadata;
+pub mod microsoft;
+pub mod nbt;
+pub mod packets;
+pub mod pathfinder;
+pub mod physics;
+pub mod player;
+pub mod registry;
+pub mod session;
+pub mod state;
+pub mod text;
+pub mod version;
+pub mod world;
+
+pub use {
+    client::{
+        AuthMode,
+        Client,
+        ClientBuilder,
+        ClientHandle,
+        ServerStatus,
+    },
+    codec::ProtocolError,
+    version::ProtocolVersion,
+    event::{
+        Event,
+        Handler,
+    },
+};
@@ -1,243 +1,122 @@
-mod packets;
-
 use {
-    flate2,
     std::{
-        net::{
-            TcpStream
-        },
-        io::{
-            Write,
-            Read,
-        },
+        env,
         process::exit,
     },
-    packets::{
-        varint_read,
-        varint_write
+    rust_bot::{
+        Client,
+        ClientHandle,
+        Event,
+        ProtocolVersion,
     }
 };
 
-
-fn compress(data: Vec<u8>) -> Vec<u8>{
-    let mut buf: Vec<u8> = Vec::new();
-    let mut compresser = flate2::Compress::new(flate2::Compression::fast(), true);
-    compresser.compress_vec(data.as_slice(), &mut buf, flate2::FlushCompress::Finish).unwrap();
-    buf
-}
-
-fn decompress(data: Vec<u8>) -> Vec<u8>{
-    let mut buf: Vec<u8> = Vec::new();
-    let mut decompresser = flate2::Decompress::new(true);
-    decompresser.decompress_vec(data.as_slice(), &mut buf, flate2::FlushDecompress::Finish).unwrap();
-    buf
-}
-
-fn send(sock: &mut TcpStream, databuff: &[u8]){
-    let ogsize: usize = databuff.len();
-    match sock.write(databuff){
-        Ok(size) => {
-            if size != ogsize{
-                println!("failed sending, og size: {}, actual sent size: {}", ogsize, size);
-                exit(1);
-            }
-        }
-        Err(err) => {
-            println!("failed sending, err: {}", err);
+// usage: rust_bot [host[:port]] [username] [version]
+#[tokio::main]
+async fn main() {
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:25565".to_string());
+    let username = args.next().unwrap_or_else(|| "rust_bot".to_string());
+    // without one the server's status decides
+    let version = args.next().map(|name| match ProtocolVersion::from_name(&name){
+        Some(version) => version,
+        None => {
+            println!("unsupported version: {}", name);
             exit(1);
         }
-    }
-}
-
-fn varint_from_stream(stream: &mut TcpStream) -> i32{
-    let mut data: i32 = 0;
-    for i in 0..5{
-        let mut buff  = vec![0u8; 1];
-
-        stream.read(buff.as_mut_slice()).unwrap();
-
-        if buff.len() == 0{
-            break
-        }
+    });
 
-        let byte = buff[0] as i64;
-        data = data | ((byte & 0x7F) << (7 * i)) as i32;
+    let (host, port) = match address.rsplit_once(':'){
+        Some((host, port)) => match port.parse::<u16>(){
+            Ok(port) => (host.to_string(), port),
+            Err(_) => {
+                println!("invalid port: {}", port);
+                exit(1);
+            }
+        },
+        None => (address.clone(), 25565),
+    };
 
-        if buff[0] & 0x80 == 0{
-            break
-        }
+    let mut builder = Client::builder(&host, port).username(&username);
+    if let Some(version) = version{
+        builder = builder.version(version);
     }
 
-    return data
-}
-
-fn read_packet(sock: &mut TcpStream) -> (Vec<u8>, i32){
-    let length: usize = varint_from_stream(sock) as usize;
-    let mut buff: Vec<u8> = vec![0u8; length];
-
-    let mut received_size = match sock.read(buff.as_mut_slice()){
-        Ok(t) => {t}
-        Err(err) => {
-            panic!("failed reading, err: {}", err);
-        }
-    };
-    while received_size != length{
-        received_size += match sock.read(buff.as_mut_slice()){
-            Ok(t) => {t}
-            Err(err) => {
-                panic!("failed reading, err: {}", err);
+    match builder.status().await{
+        Ok(status) => {
+            println!("{}", status.description.to_ansi(version.or(status.version()).unwrap_or(ProtocolVersion::DEFAULT)));
+            if let Some(name) = &status.version_name{
+                println!("version: {}", name);
+            }
+            if let Some((online, max)) = status.players{
+                println!("players: {}/{}", online, max);
             }
-        };
+            println!("ping: {}ms\n", status.latency.as_millis());
+        },
+        Err(err) => println!("failed getting status, err: {}", err),
     }
 
-    let (id, size) = varint_read(&buff);
-    buff = buff[size..buff.len()].to_vec();
-    (buff, id)
-}
-
-fn connect(ip: &String, port: &u32) -> TcpStream{
-    let mut sock = match TcpStream::connect(format!("{}:{}", ip, port)){
-        Ok(k) => {k}
+    let client = match builder.connect().await{
+        Ok(client) => client,
         Err(err) => {
-            println!("could not connect to server, error: {}", err);
+            println!("login failed, err: {}", err);
             exit(1);
         }
     };
-    return sock
-}
-
-// todo: make it so that the ping is added to the json
-pub fn get_status(ip: String, port: u16){
-    let mut sock = connect(&ip, &(port as u32));
+    println!("username: {}, version: {}", client.username(), client.version());
 
-    send(&mut sock, packets::handshake(763, ip, port, "status".to_string()).as_slice());
-    send(&mut sock, packets::status_request().as_slice());
-
-    let (packet, _) = read_packet(&mut sock);
-    let json = packets::status_response(&packet);
-
-    send(&mut sock, packets::ping_request().as_slice());
-
-    let(packet, _) = read_packet(&mut sock);
-    let ping = packets::ping_response(&packet);
-    println!("{}\n{}\n", json, ping);
-}
-
-pub fn offline_login(ip: String, port: u16){
-    let mut compression = false;
-    let mut compression_size = 0;
-    let mut sock = connect(&ip, &(port as u32));
-
-    send(&mut sock, packets::handshake(763, ip, port, "login".to_string()).as_slice());
-    send(&mut sock, packets::login_start("rust_bot".to_string()).as_slice());
-
-    loop{
-        let (mut packet, id) = read_packet(&mut sock);
-        println!("id: {}", id);
-
-        if compression{
-            packet = decompress(packet);
-        }
-        if id == 0x0{
-            panic!("disconnected, reason: {}", packets::login_disconnect(&packet));
-        } else if id == 0x1{ // encryption request
-            panic!("server must be using online mode or has chat reporting enabled due to requesting for encryption");
-        } else if id == 0x2 { // login success
-            println!("username: {}", packets::login_success(&packet));
-            break;
-        } else if id == 0x3 { // set compression
-            compression = true;
-            compression_size = packets::compression_request(&packet);
-        } else if id == 0x4 { // login plugin request (custom login flow)
-            println!("Warning: server tried sending a custom login request");
-            send(&mut sock, packets::status_request().as_slice()); // re using the empty packet from status_request, if you want you can add support for different custom login flows
-        }
-    }
-
-    let play_send = |sock: &mut TcpStream, packet: Vec<u8>| { // a simple little function to reduce the amount of code needed to send a packet
-        let mut pack: Vec<u8>;
-        if packet.len() >= compression_size as usize && compression {
-            let mut buf: Vec<u8> = Vec::new();
-
-            let uncompressed_size = varint_write(packet.len() as i32);
-            let compressed = compress(packet);
-            let size = varint_write((uncompressed_size.len() + compressed.len()) as i32);
-
-            buf.extend_from_slice(size.as_slice());
-            buf.extend_from_slice(uncompressed_size.as_slice());
-            buf.extend_from_slice(compressed.as_slice());
-
-            pack = buf
-        } else {
-            let mut buf: Vec<u8> = Vec::new();
-            let data_length = varint_write(0);
-            let size = varint_write((packet.len() + data_length.len()) as i32);
-
-            buf.extend_from_slice(size.as_slice());
-            buf.extend_from_slice(data_length.as_slice());
-            buf.extend_from_slice(packet.as_slice());
-
-            pack = buf;
-        }
-
-        send(sock, pack.as_slice());
-    };
-
-    let play_receive = |sock: &mut TcpStream| -> (Vec<u8>, i32) { // same perpous as play_send, to reduce amount of code needed to be writen
-        let (mut packet, id) = read_packet(sock);
-        if compression {
-            let (uncompressed_size, size) = varint_read(&packet);
-            packet = packet[size..packet.len()].to_vec();
-            packet = decompress(packet);
-
-            if packet.len() != uncompressed_size as usize{
-                panic!("uncompressed packet size ({}) is not what it should be ({}) [play_receive]", packet.len(), uncompressed_size)
-            }
-        }
-        (packet, id)
-    };
-
-    // data we will want to track
     // todo:
-    //  add uuid support and turn uuids into player names
-    //  figure out how to handle blocks and block states
-    let mut entities: Vec<(i32, String, (f64, f64, f64, i8, i8, i8), i32, (i16, i16, i16))> = Vec::new(); // entity ID, {uuid will be here}, Type, (position x/y/x, pitch, yaw, head yaw), object id, (velocity x/y/z)
-    let mut players: Vec<(i32, (f64, f64, f64), (i8, i8))> = Vec::new();
-
-    // todo: work on play mode packets
-    loop{ // main play mode loop
-        let (packet, id) = play_receive(&mut sock);
-
-        // todo:
-        //  add pack bundles 0x0
-        //  add spawn exp orb 0x2
-        //  Award Statistics 0x5
-        //  Acknowledge block state 0x6
-        //  Set Block destroy stage 0x7
-        match id{
-            0x1 => {
-                // so messy but dammit it works
-                let (e_id, e_name, (x, y, z, pitch, yaw, h_yaw), data, (vel_x, vel_y, vel_z)) = packets::spawn_entity(&packet);
-                println!("new entity, name: {}\nid: {}\nposition: {}, {}, {}", e_name, e_id, x, y, z);
-                entities.append(&mut vec!((e_id, e_name, (x, y, z, pitch, yaw, h_yaw), data, (vel_x, vel_y, vel_z))));
+    //  add pack bundles 0x0
+    //  add spawn exp orb 0x2
+    //  Award Statistics 0x5
+    //  Set Block destroy stage 0x7
+    client.run(|handle: &ClientHandle, event: &Event| {
+        match event{
+            Event::Joined{entity_id, game_mode, dimension} => {
+                println!("joined {} as entity {}, game mode: {}", dimension, entity_id, game_mode);
+            },
+            Event::Respawned{dimension} => {
+                println!("respawned in {}", dimension);
+            },
+            Event::Teleported{position, yaw, pitch} => {
+                println!("teleported to {:.2}, {:.2}, {:.2} facing {:.1} / {:.1}", position.x, position.y, position.z, yaw, pitch);
+            },
+            Event::EntitySpawned(entity) => {
+                println!("new entity, name: {}\nid: {}\nposition: {}, {}, {}", entity.type_name(), entity.entity_id, entity.x, entity.y, entity.z);
+            },
+            Event::PlayerSpawned(player) => {
+                println!("new player, ID: {}\nposition: {}, {}, {}", player.entity_id, player.x, player.y, player.z);
+            },
+            Event::EntitiesRemoved(entity_ids) => {
+                println!("entities removed: {:?}", entity_ids);
             },
-            0x3 => {
-                let (e_id, (x, y, z), (yaw, pitch)) = packets::spawn_player(&packet);
-                println!("new player, ID: {}\nposition: {}, {}, {}", e_id, x, y, z);
-                players.append(&mut vec!((e_id, (x, y, z), (yaw, pitch))));
+            Event::Animation(animation) => {
+                println!("play with id {} played the {} animation", animation.entity_id, animation.name());
             },
-            0x4 => {
-                let (e_id, animation) = packets::entity_animation(&packet);
-                println!("play with id {} played the {} animation", e_id, animation);
+            Event::GoalReached => {
+                println!("got there");
             },
-            _ => {
+            Event::NoPath => {
+                println!("no way to get there");
+            },
+            Event::BlockChanged(change) => {
+                println!("block at {}, {}, {} changed from {} to {}", change.position.x, change.position.y, change.position.z, change.old, change.new);
+            },
+            Event::Chat{name: Some(name), message, ..} => {
+                println!("chat from {}: {}", name, message.to_ansi(handle.version()));
+            },
+            Event::Chat{message, ..} => {
+                println!("chat: {}", message.to_ansi(handle.version()));
+            },
+            Event::PluginMessage{channel, data} => {
+                println!("plugin message on {}, {} bytes", channel, data.len());
+            },
+            Event::Packet{id, ..} => {
                 println!("unknown packet, id: {}", id);
+            },
+            Event::Disconnected{reason} => {
+                println!("disconnected, reason: {}", reason);
             }
         }
-    }
-}
-
-fn main() {
-    get_status("127.0.0.1".to_string(), 25565);
-    offline_login("127.0.0.1".to_string(), 25565);
+    }).await;
 }
@@ -0,0 +1,657 @@
+use {
+    serde_json::{
+        Value,
+        json,
+    },
+    std::{
+        fmt,
+        fs::{
+            self,
+            OpenOptions,
+        },
+        io::Write,
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::Arc,
+        thread,
+        time::{
+            Duration,
+            SystemTime,
+        },
+    },
+    crate::{
+        codec::ProtocolError,
+        session::Account,
+    }
+};
+
+// the Nintendo Switch client id, it is already allowed to log into Minecraft so nobody
+// has to register their own Azure app. swap it out (and the scope) for your own app if you have one
+pub const DEFAULT_CLIENT_ID: &str = "00000000441cc96b";
+pub const DEFAULT_SCOPE: &str = "service::user.auth.xboxlive.com::MBI_SSL";
+
+pub const DEFAULT_OAUTH_URL: &str = "https://login.live.com";
+pub const DEFAULT_XBOX_USER_URL: &str = "https://user.auth.xboxlive.com";
+pub const DEFAULT_XSTS_URL: &str = "https://xsts.auth.xboxlive.com";
+pub const DEFAULT_MINECRAFT_URL: &str = "https://api.minecraftservices.com";
+
+// tokens that expire within this many seconds are treated as already expired
+const EXPIRY_MARGIN: u64 = 60;
+
+// what the user has to do to finish the device code login
+#[derive(Debug, Clone)]
+pub struct DeviceCode{
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub message: String,
+}
+
+// what to do with the device code when the user has to log in, for AuthMode::Microsoft where
+// the login runs inside ClientBuilder::connect. show the message to the user somehow
+#[derive(Clone)]
+pub struct DeviceCodeCallback(Arc<dyn Fn(&DeviceCode) + Send + Sync>);
+
+impl DeviceCodeCallback{
+    pub fn new<F: Fn(&DeviceCode) + Send + Sync + 'static>(callback: F) -> Self{
+        DeviceCodeCallback(Arc::new(callback))
+    }
+
+    pub fn call(&self, code: &DeviceCode){
+        (self.0)(code)
+    }
+}
+
+impl fmt::Debug for DeviceCodeCallback{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        f.write_str("DeviceCodeCallback")
+    }
+}
+
+// microsoft account -> xbox live -> xsts -> minecraft services -> profile.
+// every step is cached on disk (if a cache path is set) so we only bother the user once
+#[derive(Debug, Clone)]
+pub struct MicrosoftAuth{
+    client_id: String,
+    scope: String,
+    oauth_url: String,
+    xbox_user_url: String,
+    xsts_url: String,
+    minecraft_url: String,
+    cache_path: Option<PathBuf>,
+    timeout: Duration,
+}
+
+impl Default for MicrosoftAuth{
+    fn default() -> Self{
+        MicrosoftAuth{
+            client_id: DEFAULT_CLIENT_ID.to_string(),
+            scope: DEFAULT_SCOPE.to_string(),
+            oauth_url: DEFAULT_OAUTH_URL.to_string(),
+            xbox_user_url: DEFAULT_XBOX_USER_URL.to_string(),
+            xsts_url: DEFAULT_XSTS_URL.to_string(),
+            minecraft_url: DEFAULT_MINECRAFT_URL.to_string(),
+            cache_path: None,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl MicrosoftAuth{
+    pub fn new() -> Self{
+        MicrosoftAuth::default()
+    }
+
+    pub fn with_client_id(mut self, client_id: &str, scope: &str) -> Self{
+        self.client_id = client_id.to_string();
+        self.scope = scope.to_string();
+        self
+    }
+
+    pub fn with_oauth_url(mut self, url: &str) -> Self{
+        self.oauth_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_xbox_user_url(mut self, url: &str) -> Self{
+        self.xbox_user_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_xsts_url(mut self, url: &str) -> Self{
+        self.xsts_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_minecraft_url(mut self, url: &str) -> Self{
+        self.minecraft_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_cache<P: AsRef<Path>>(mut self, path: P) -> Self{
+        self.cache_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self{
+        self.timeout = timeout;
+        self
+    }
+
+    // gives back a usable account, only going as far back in the chain as the cache forces us to.
+    // on_code is only called when the user actually has to log in through the browser.
+    // fails if the cache is set but can't be written, the tokens would be lost otherwise
+    pub fn login<F: FnMut(&DeviceCode)>(&self, on_code: F) -> Result<Account, ProtocolError>{
+        let mut cache = self.load_cache();
+        let now = now_secs();
+
+        if let (Some(token), Some(profile)) = (fresh(&cache["minecraft"], now), cache.get("profile")){
+            if let (Some(uuid), Some(name)) = (profile["uuid"].as_str(), profile["name"].as_str()){
+                if let Ok(uuid) = u128::from_str_radix(uuid, 16){
+                    return Ok(Account{username: name.to_string(), uuid, access_token: token.to_string()});
+                }
+            }
+        }
+
+        let msa_token = match fresh(&cache["msa"], now){
+            Some(token) => token.to_string(),
+            None => {
+                let refreshed = match cache["msa"]["refresh_token"].as_str(){
+                    Some(refresh_token) => self.refresh_msa(refresh_token).ok().map(|mut msa| {
+                        if msa["refresh_token"].is_null(){ // not every refresh hands out a new refresh token
+                            msa["refresh_token"] = json!(refresh_token);
+                        }
+                        msa
+                    }), // a dead refresh token just means logging in again
+                    None => None,
+                };
+                let msa = match refreshed{
+                    Some(msa) => msa,
+                    None => self.device_code_login(on_code)?,
+                };
+                cache["msa"] = msa;
+                self.save_cache(&cache)?;
+                cache["msa"]["access_token"].as_str().unwrap_or_default().to_string()
+            }
+        };
+
+        let (xbl_token, user_hash) = self.xbox_live(&msa_token)?;
+        let xsts_token = self.xsts(&xbl_token)?;
+        let (minecraft_token, expires_in) = self.minecraft_login(&user_hash, &xsts_token)?;
+        let (uuid, username) = self.profile(&minecraft_token)?;
+
+        cache["minecraft"] = json!({"access_token": minecraft_token, "expires_at": now_secs() + expires_in});
+        cache["profile"] = json!({"uuid": format!("{:032x}", uuid), "name": username});
+        self.save_cache(&cache)?;
+
+        Ok(Account{username, uuid, access_token: minecraft_token})
+    }
+
+    fn device_code_login<F: FnMut(&DeviceCode)>(&self, mut on_code: F) -> Result<Value, ProtocolError>{
+        let response = self.post_form(&format!("{}/oauth20_connect.srf", self.oauth_url), &[
+            ("client_id", &self.client_id),
+            ("scope", &self.scope),
+            ("response_type", "device_code"),
+        ])?;
+
+        let device_code = string_field(&response, "device_code")?;
+        let code = DeviceCode{
+            user_code: string_field(&response, "user_code")?,
+            verification_uri: string_field(&response, "verification_uri")?,
+            expires_in: response["expires_in"].as_u64().unwrap_or(900),
+            message: response["message"].as_str().map(|msg| msg.to_string()).unwrap_or_else(|| {
+                format!("go to {} and enter the code {}", response["verification_uri"].as_str().unwrap_or_default(), response["user_code"].as_str().unwrap_or_default())
+            }),
+        };
+        on_code(&code);
+
+        let mut interval = response["interval"].as_u64().unwrap_or(5);
+        let deadline = now_secs() + code.expires_in;
+        while now_secs() < deadline{
+            thread::sleep(Duration::from_secs(interval));
+
+            let result = ureq::post(&format!("{}/oauth20_token.srf", self.oauth_url))
+                .timeout(self.timeout)
+                .send_form(&[
+                    ("client_id", &self.client_id),
+                    ("device_code", &device_code),
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ]);
+
+            match result{
+                Ok(response) => return msa_tokens(&read_json(response)?),
+                Err(ureq::Error::Status(_, response)) => {
+                    let body = read_json(response).unwrap_or(Value::Null);
+                    match body["error"].as_str(){
+                        Some("authorization_pending") => continue,
+                        Some("slow_down") => interval += 5,
+                        Some(err) => return Err(ProtocolError::Auth(format!("microsoft login failed: {}", err))),
+                        None => return Err(ProtocolError::Auth("microsoft login failed".to_string())),
+                    }
+                },
+                Err(err) => return Err(ProtocolError::Auth(format!("could not reach {}: {}", self.oauth_url, err))),
+            }
+        }
+        Err(ProtocolError::Auth("device code expired before the login was finished".to_string()))
+    }
+
+    fn refresh_msa(&self, refresh_token: &str) -> Result<Value, ProtocolError>{
+        let response = self.post_form(&format!("{}/oauth20_token.srf", self.oauth_url), &[
+            ("client_id", &self.client_id),
+            ("scope", &self.scope),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ])?;
+        msa_tokens(&response)
+    }
+
+    fn xbox_live(&self, msa_token: &str) -> Result<(String, String), ProtocolError>{
+        // custom azure apps need the d= prefix, the built in client ids don't
+        let ticket = if self.scope.contains("XboxLive.signin"){
+            format!("d={}", msa_token)
+        } else {
+            msa_token.to_string()
+        };
+
+        let response = self.post_json(&format!("{}/user/authenticate", self.xbox_user_url), json!({
+            "Properties": {
+                "AuthMethod": "RPS",
+                "SiteName": "user.auth.xboxlive.com",
+                "RpsTicket": ticket,
+            },
+            "RelyingParty": "http://auth.xboxlive.com",
+            "TokenType": "JWT",
+        }))?;
+
+        let token = string_field(&response, "Token")?;
+        let user_hash = response["DisplayClaims"]["xui"][0]["uhs"].as_str()
+            .ok_or_else(|| ProtocolError::Auth("xbox live response is missing the user hash".to_string()))?;
+        Ok((token, user_hash.to_string()))
+    }
+
+    fn xsts(&self, xbl_token: &str) -> Result<String, ProtocolError>{
+        let result = ureq::post(&format!("{}/xsts/authorize", self.xsts_url))
+            .timeout(self.timeout)
+            .set("Accept", "application/json")
+            .send_json(json!({
+                "Properties": {
+                    "SandboxId": "RETAIL",
+                    "UserTokens": [xbl_token],
+                },
+                "RelyingParty": "rp://api.minecraftservices.com/",
+                "TokenType": "JWT",
+            }));
+
+        match result{
+            Ok(response) => string_field(&read_json(response)?, "Token"),
+            Err(ureq::Error::Status(401, response)) => {
+                let body = read_json(response).unwrap_or(Value::Null);
+                let reason = match body["XErr"].as_u64(){
+                    Some(2148916233) => "this microsoft account doesn't have an xbox account".to_string(),
+                    Some(2148916235) => "xbox live isn't available in this account's country".to_string(),
+                    Some(2148916236) | Some(2148916237) => "this account needs adult verification (south korea)".to_string(),
+                    Some(2148916238) => "this is a child account and has to be added to a family by an adult".to_string(),
+                    Some(code) => format!("xsts refused the token, XErr: {}", code),
+                    None => "xsts refused the token".to_string(),
+                };
+                Err(ProtocolError::Auth(reason))
+            },
+            Err(err) => Err(http_error(&self.xsts_url, err)),
+        }
+    }
+
+    fn minecraft_login(&self, user_hash: &str, xsts_token: &str) -> Result<(String, u64), ProtocolError>{
+        let response = self.post_json(&format!("{}/authentication/login_with_xbox", self.minecraft_url), json!({
+            "identityToken": format!("XBL3.0 x={};{}", user_hash, xsts_token),
+        }))?;
+
+        let token = string_field(&response, "access_token")?;
+        Ok((token, response["expires_in"].as_u64().unwrap_or(86400)))
+    }
+
+    fn profile(&self, minecraft_token: &str) -> Result<(u128, String), ProtocolError>{
+        let result = ureq::get(&format!("{}/minecraft/profile", self.minecraft_url))
+            .timeout(self.timeout)
+            .set("Authorization", &format!("Bearer {}", minecraft_token))
+            .call();
+
+        let response = match result{
+            Ok(response) => read_json(response)?,
+            Err(ureq::Error::Status(404, _)) => return Err(ProtocolError::Auth("this account doesn't own minecraft".to_string())),
+            Err(err) => return Err(http_error(&self.minecraft_url, err)),
+        };
+
+        let id = string_field(&response, "id")?;
+        let uuid = u128::from_str_radix(&id.replace('-', ""), 16)
+            .map_err(|_| ProtocolError::Auth(format!("profile has an invalid uuid: {}", id)))?;
+        Ok((uuid, string_field(&response, "name")?))
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<Value, ProtocolError>{
+        match ureq::post(url).timeout(self.timeout).send_form(form){
+            Ok(response) => read_json(response),
+            Err(err) => Err(http_error(url, err)),
+        }
+    }
+
+    fn post_json(&self, url: &str, body: Value) -> Result<Value, ProtocolError>{
+        let request = ureq::post(url)
+            .timeout(self.timeout)
+            .set("Accept", "application/json");
+
+        match request.send_json(body){
+            Ok(response) => read_json(response),
+            Err(err) => Err(http_error(url, err)),
+        }
+    }
+
+    fn load_cache(&self) -> Value{
+        let cache = self.cache_path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok());
+
+        match cache{
+            Some(cache) if cache.is_object() => cache,
+            _ => json!({}),
+        }
+    }
+
+    // the cache holds the refresh token, which is as good as the password, so on unix only
+    // the owner may read it. elsewhere the file gets whatever the directory hands down
+    fn save_cache(&self, cache: &Value) -> Result<(), ProtocolError>{
+        let path = match &self.cache_path{
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let cache_error = |err: std::io::Error| ProtocolError::Auth(format!("could not write the token cache to {}: {}", path.display(), err));
+
+        if let Some(parent) = path.parent(){
+            fs::create_dir_all(parent).map_err(cache_error)?;
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path).map_err(cache_error)?;
+
+        // mode only counts when the file is created, an older cache may still be readable by others
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600)).map_err(cache_error)?;
+
+        file.write_all(cache.to_string().as_bytes()).map_err(cache_error)
+    }
+}
+
+fn now_secs() -> u64{
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// the access token of a cached {access_token, expires_at} entry, if it's still good
+fn fresh(entry: &Value, now: u64) -> Option<&str>{
+    let expires_at = entry["expires_at"].as_u64()?;
+    if expires_at < now + EXPIRY_MARGIN{
+        return None;
+    }
+    entry["access_token"].as_str()
+}
+
+fn msa_tokens(response: &Value) -> Result<Value, ProtocolError>{
+    let access_token = string_field(response, "access_token")?;
+    let expires_in = response["expires_in"].as_u64().unwrap_or(3600);
+    Ok(json!({
+        "access_token": access_token,
+        "refresh_token": response["refresh_token"],
+        "expires_at": now_secs() + expires_in,
+    }))
+}
+
+fn string_field(value: &Value, field: &str) -> Result<String, ProtocolError>{
+    match value[field].as_str(){
+        Some(text) => Ok(text.to_string()),
+        None => Err(ProtocolError::Auth(format!("response is missing the {} field", field))),
+    }
+}
+
+fn read_json(response: ureq::Response) -> Result<Value, ProtocolError>{
+    serde_json::from_reader(response.into_reader())
+        .map_err(|err| ProtocolError::Auth(format!("response is not valid json: {}", err)))
+}
+
+fn http_error(url: &str, err: ureq::Error) -> ProtocolError{
+    match err{
+        ureq::Error::Status(code, response) => {
+            let text = response.into_string().unwrap_or_default();
+            ProtocolError::Auth(format!("{} answered with {}: {}", url, code, text))
+        },
+        err => ProtocolError::Auth(format!("could not reach {}: {}", url, err)),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        std::sync::{
+            Mutex,
+            atomic::{
+                AtomicUsize,
+                Ordering,
+            },
+        },
+        crate::session::stub::{
+            HttpStub,
+            Request,
+        },
+    };
+
+    const UUID: u128 = 0x069a79f444e94726a5befca90e38aaf5;
+
+    // stands in for login.live.com, xbox live, xsts and minecraft services all at once.
+    // the device code login is pending for the first few polls
+    fn services(pending: usize, xsts: Result<&'static str, u64>) -> HttpStub{
+        let polls = AtomicUsize::new(0);
+        HttpStub::serve(move |request: &Request| {
+            let (status, body) = match request.path.as_str(){
+                "/oauth20_connect.srf" => (200, json!({
+                    "device_code": "device",
+                    "user_code": "ABCD1234",
+                    "verification_uri": "https://www.microsoft.com/link",
+                    "expires_in": 900,
+                    "interval": 0,
+                })),
+                "/oauth20_token.srf" => match request.form("grant_type").as_deref(){
+                    Some("refresh_token") if request.form("refresh_token").as_deref() == Some("refresh") => {
+                        (200, json!({"access_token": "refreshed msa", "expires_in": 3600}))
+                    },
+                    Some("refresh_token") => (400, json!({"error": "invalid_grant"})),
+                    _ if polls.fetch_add(1, Ordering::SeqCst) < pending => (400, json!({"error": "authorization_pending"})),
+                    _ => (200, json!({"access_token": "msa", "refresh_token": "refresh", "expires_in": 3600})),
+                },
+                "/user/authenticate" => (200, json!({
+                    "Token": "xbl",
+                    "DisplayClaims": {"xui": [{"uhs": "userhash"}]},
+                })),
+                "/xsts/authorize" => match xsts{
+                    Ok(token) => (200, json!({"Token": token, "DisplayClaims": {"xui": [{"uhs": "userhash"}]}})),
+                    Err(code) => (401, json!({"Identity": "0", "XErr": code, "Message": "", "Redirect": "https://start.ui.xboxlive.com/AddChildToFamily"})),
+                },
+                "/authentication/login_with_xbox" => (200, json!({"access_token": "minecraft", "expires_in": 86400})),
+                "/minecraft/profile" => (200, json!({"id": format!("{:032x}", UUID), "name": "Notch"})),
+                _ => (404, json!({})),
+            };
+            (status, body.to_string())
+        })
+    }
+
+    fn auth(server: &HttpStub) -> MicrosoftAuth{
+        MicrosoftAuth::new()
+            .with_oauth_url(&server.url)
+            .with_xbox_user_url(&server.url)
+            .with_xsts_url(&server.url)
+            .with_minecraft_url(&server.url)
+    }
+
+    fn cache_path(name: &str) -> PathBuf{
+        std::env::temp_dir().join(format!("rust_bot_{}_{}", name, std::process::id())).join("tokens.json")
+    }
+
+    fn request<'a>(requests: &'a [Request], path: &str) -> &'a Request{
+        requests.iter().find(|request| request.path == path).unwrap_or_else(|| panic!("nothing was sent to {}", path))
+    }
+
+    #[test]
+    fn device_code_chain(){
+        let server = services(2, Ok("xsts"));
+        let path = cache_path("chain");
+        let codes = Mutex::new(Vec::new());
+        let account = auth(&server).with_cache(&path).login(|code| codes.lock().unwrap().push(code.user_code.clone())).unwrap();
+
+        assert_eq!(account.username, "Notch");
+        assert_eq!(account.uuid, UUID);
+        assert_eq!(account.access_token, "minecraft");
+        assert_eq!(*codes.lock().unwrap(), ["ABCD1234"]);
+        assert_eq!(server.paths(), [
+            "/oauth20_connect.srf",
+            "/oauth20_token.srf", // pending
+            "/oauth20_token.srf", // pending
+            "/oauth20_token.srf",
+            "/user/authenticate",
+            "/xsts/authorize",
+            "/authentication/login_with_xbox",
+            "/minecraft/profile",
+        ]);
+
+        let requests = server.requests();
+        let connect = request(&requests, "/oauth20_connect.srf");
+        assert_eq!(connect.form("client_id").as_deref(), Some(DEFAULT_CLIENT_ID));
+        assert_eq!(connect.form("response_type").as_deref(), Some("device_code"));
+        let poll = request(&requests, "/oauth20_token.srf");
+        assert_eq!(poll.form("device_code").as_deref(), Some("device"));
+        assert_eq!(poll.form("grant_type").as_deref(), Some("urn:ietf:params:oauth:grant-type:device_code"));
+        // the built in client id doesn't take the d= prefix
+        assert_eq!(request(&requests, "/user/authenticate").json()["Properties"]["RpsTicket"], "msa");
+        assert_eq!(request(&requests, "/xsts/authorize").json()["Properties"]["UserTokens"], json!(["xbl"]));
+        assert_eq!(request(&requests, "/authentication/login_with_xbox").json()["identityToken"], "XBL3.0 x=userhash;xsts");
+        assert_eq!(request(&requests, "/minecraft/profile").header("authorization"), Some("Bearer minecraft"));
+
+        // a second login is served from the cache
+        let cached = auth(&server).with_cache(&path).login(|_| panic!("asked for a device code again")).unwrap();
+        assert_eq!(cached.uuid, UUID);
+        assert_eq!(cached.access_token, "minecraft");
+        assert_eq!(server.requests().len(), 8);
+
+        let cache: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(cache["msa"]["refresh_token"], "refresh");
+        assert_eq!(cache["profile"], json!({"uuid": format!("{:032x}", UUID), "name": "Notch"}));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn refresh_token(){
+        let server = services(0, Ok("xsts"));
+        let path = cache_path("refresh");
+        let auth = auth(&server).with_cache(&path);
+        auth.save_cache(&json!({"msa": {"access_token": "old msa", "refresh_token": "refresh", "expires_at": 0}})).unwrap();
+
+        let account = auth.login(|_| panic!("asked for a device code with a refresh token")).unwrap();
+        assert_eq!(account.access_token, "minecraft");
+        assert_eq!(server.paths()[..2], ["/oauth20_token.srf", "/user/authenticate"]);
+        let requests = server.requests();
+        assert_eq!(requests[0].form("grant_type").as_deref(), Some("refresh_token"));
+        assert_eq!(requests[0].form("refresh_token").as_deref(), Some("refresh"));
+        assert_eq!(requests[1].json()["Properties"]["RpsTicket"], "refreshed msa");
+
+        // the refresh didn't hand out a new refresh token, the old one is kept
+        let cache = auth.load_cache();
+        assert_eq!(cache["msa"]["access_token"], "refreshed msa");
+        assert_eq!(cache["msa"]["refresh_token"], "refresh");
+
+        // a dead refresh token falls back to the device code
+        auth.save_cache(&json!({"msa": {"access_token": "old msa", "refresh_token": "revoked", "expires_at": 0}})).unwrap();
+        let mut asked = false;
+        auth.login(|_| asked = true).unwrap();
+        assert!(asked);
+        assert_eq!(auth.load_cache()["msa"]["refresh_token"], "refresh");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn no_xbox_account(){
+        let server = services(0, Err(2148916233));
+        match auth(&server).login(|_| ()){
+            Err(ProtocolError::Auth(message)) => assert_eq!(message, "this microsoft account doesn't have an xbox account"),
+            other => panic!("expected an auth error, got {:?}", other),
+        }
+        // never got as far as minecraft services
+        assert_eq!(server.paths().last().map(String::as_str), Some("/xsts/authorize"));
+    }
+
+    #[test]
+    fn expired_device_code(){
+        let server = HttpStub::serve(|request| match request.path.as_str(){
+            "/oauth20_connect.srf" => (200, json!({
+                "device_code": "device",
+                "user_code": "ABCD1234",
+                "verification_uri": "https://www.microsoft.com/link",
+                "expires_in": 0,
+            }).to_string()),
+            _ => (400, json!({"error": "expired_token"}).to_string()),
+        });
+        match auth(&server).login(|_| ()){
+            Err(ProtocolError::Auth(message)) => assert_eq!(message, "device code expired before the login was finished"),
+            other => panic!("expected an auth error, got {:?}", other),
+        }
+        assert_eq!(server.paths(), ["/oauth20_connect.srf"]);
+
+        // or microsoft says so itself
+        let server = HttpStub::serve(|request| match request.path.as_str(){
+            "/oauth20_connect.srf" => (200, json!({
+                "device_code": "device",
+                "user_code": "ABCD1234",
+                "verification_uri": "https://www.microsoft.com/link",
+                "interval": 0,
+            }).to_string()),
+            _ => (400, json!({"error": "expired_token"}).to_string()),
+        });
+        match auth(&server).login(|_| ()){
+            Err(ProtocolError::Auth(message)) => assert_eq!(message, "microsoft login failed: expired_token"),
+            other => panic!("expected an auth error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cache_round_trip(){
+        let path = std::env::temp_dir().join(format!("rust_bot_cache_{}", std::process::id())).join("tokens.json");
+        let auth = MicrosoftAuth::new().with_cache(&path);
+        let cache = json!({"msa": {"access_token": "token", "refresh_token": "refresh", "expires_at": 100}});
+        auth.save_cache(&cache).unwrap();
+        assert_eq!(auth.load_cache(), cache);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            auth.save_cache(&cache).unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+
+        // nowhere to write it is an error, not a warning on stdout
+        let file = std::env::temp_dir().join(format!("rust_bot_not_a_dir_{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        let auth = MicrosoftAuth::new().with_cache(file.join("tokens.json"));
+        assert!(matches!(auth.save_cache(&cache), Err(ProtocolError::Auth(_))));
+        let _ = fs::remove_file(file);
+    }
+
+    #[test]
+    fn expired_tokens_are_not_fresh(){
+        let entry = json!({"access_token": "token", "expires_at": 1000});
+        assert_eq!(fresh(&entry, 900), Some("token"));
+        assert_eq!(fresh(&entry, 1000 - EXPIRY_MARGIN + 1), None);
+        assert_eq!(fresh(&json!({"access_token": "token"}), 0), None);
+    }
+}
@@ -0,0 +1,740 @@
+use {
+    flate2::{
+        Compression,
+        read::{
+            GzDecoder,
+            ZlibDecoder,
+        },
+        write::{
+            GzEncoder,
+            ZlibEncoder,
+        },
+    },
+    std::{
+        fmt,
+        io::{
+            Read,
+            Write,
+        },
+    },
+    crate::codec::{
+        PacketReader,
+        ProtocolError,
+    },
+};
+
+mod de;
+mod ser;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+pub use {
+    de::from_tag,
+    ser::to_tag,
+};
+
+// serde has no array types, so a Vec<i32> field turns into a list of ints. wrap it in one of these
+// to get the array tag instead, to_tag spots them by their newtype names
+pub const BYTE_ARRAY: &str = "__nbt_byte_array";
+pub const INT_ARRAY: &str = "__nbt_int_array";
+pub const LONG_ARRAY: &str = "__nbt_long_array";
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename = "__nbt_byte_array")]
+pub struct ByteArray(pub Vec<i8>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename = "__nbt_int_array")]
+pub struct IntArray(pub Vec<i32>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename = "__nbt_long_array")]
+pub struct LongArray(pub Vec<i64>);
+
+// compounds and lists can nest, but not forever
+const MAX_DEPTH: usize = 512;
+
+// a file can't claim to inflate to more than this
+const MAX_FILE_SIZE: u64 = 1 << 26;
+
+pub const TAG_END: u8 = 0;
+pub const TAG_BYTE: u8 = 1;
+pub const TAG_SHORT: u8 = 2;
+pub const TAG_INT: u8 = 3;
+pub const TAG_LONG: u8 = 4;
+pub const TAG_FLOAT: u8 = 5;
+pub const TAG_DOUBLE: u8 = 6;
+pub const TAG_BYTE_ARRAY: u8 = 7;
+pub const TAG_STRING: u8 = 8;
+pub const TAG_LIST: u8 = 9;
+pub const TAG_COMPOUND: u8 = 10;
+pub const TAG_INT_ARRAY: u8 = 11;
+pub const TAG_LONG_ARRAY: u8 = 12;
+
+// any NBT value. lists are supposed to hold one tag type only, that is checked when writing them
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag{
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag{
+    pub fn id(&self) -> u8{
+        match self{
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(_) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    // any of the whole number tags, widened
+    pub fn as_i64(&self) -> Option<i64>{
+        match self{
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64>{
+        match self{
+            Tag::Float(value) => Some(*value as f64),
+            Tag::Double(value) => Some(*value),
+            _ => self.as_i64().map(|value| value as f64),
+        }
+    }
+
+    // booleans are bytes in NBT
+    pub fn as_bool(&self) -> Option<bool>{
+        self.as_i64().map(|value| value != 0)
+    }
+
+    pub fn as_str(&self) -> Option<&str>{
+        match self{
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]>{
+        match self{
+            Tag::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound>{
+        match self{
+            Tag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+
+    // shorthand for as_compound + get
+    pub fn get(&self, name: &str) -> Option<&Tag>{
+        self.as_compound()?.get(name)
+    }
+
+    fn read_payload(reader: &mut PacketReader<'_>, id: u8, depth: usize) -> Result<Tag, ProtocolError>{
+        if depth > MAX_DEPTH{
+            return Err(ProtocolError::InvalidValue("NBT nested too deep".to_string()));
+        }
+
+        Ok(match id{
+            TAG_BYTE => Tag::Byte(reader.read_i8()?),
+            TAG_SHORT => Tag::Short(reader.read_i16()?),
+            TAG_INT => Tag::Int(reader.read_i32()?),
+            TAG_LONG => Tag::Long(reader.read_i64()?),
+            TAG_FLOAT => Tag::Float(reader.read_f32()?),
+            TAG_DOUBLE => Tag::Double(reader.read_f64()?),
+            TAG_BYTE_ARRAY => {
+                let length = read_length(reader, 1)?;
+                Tag::ByteArray(reader.read_bytes(length)?.iter().map(|byte| *byte as i8).collect())
+            },
+            TAG_STRING => Tag::String(read_string(reader)?),
+            TAG_LIST => {
+                let element = reader.read_u8()?;
+                let length = reader.read_i32()?;
+                // an empty list may say its elements are TAG_End, a non empty one can't
+                if element == TAG_END && length > 0{
+                    return Err(ProtocolError::InvalidValue("NBT list of TAG_End with elements in it".to_string()));
+                }
+
+                let mut list = Vec::with_capacity((length.max(0) as usize).min(reader.remaining()));
+                for _ in 0..length{
+                    list.push(Tag::read_payload(reader, element, depth + 1)?);
+                }
+                Tag::List(list)
+            },
+            TAG_COMPOUND => {
+                let mut compound = Compound::new();
+                loop{
+                    let element = reader.read_u8()?;
+                    if element == TAG_END{
+                        break;
+                    }
+                    let name = read_string(reader)?;
+                    let value = Tag::read_payload(reader, element, depth + 1)?;
+                    compound.insert(name, value);
+                }
+                Tag::Compound(compound)
+            },
+            TAG_INT_ARRAY => {
+                let length = read_length(reader, 4)?;
+                let mut array = Vec::with_capacity(length);
+                for _ in 0..length{
+                    array.push(reader.read_i32()?);
+                }
+                Tag::IntArray(array)
+            },
+            TAG_LONG_ARRAY => {
+                let length = read_length(reader, 8)?;
+                let mut array = Vec::with_capacity(length);
+                for _ in 0..length{
+                    array.push(reader.read_i64()?);
+                }
+                Tag::LongArray(array)
+            },
+            _ => return Err(ProtocolError::InvalidValue(format!("unknown NBT tag type {}", id))),
+        })
+    }
+
+    fn write_payload(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        match self{
+            Tag::Byte(value) => buf.push(*value as u8),
+            Tag::Short(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Tag::Int(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Tag::Long(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Tag::Float(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Tag::Double(value) => buf.extend_from_slice(&value.to_be_bytes()),
+            Tag::ByteArray(array) => {
+                write_length(buf, array.len())?;
+                buf.extend(array.iter().map(|byte| *byte as u8));
+            },
+            Tag::String(value) => write_string(buf, value)?,
+            Tag::List(list) => {
+                let element = list.first().map_or(TAG_END, Tag::id);
+                if let Some(other) = list.iter().find(|tag| tag.id() != element){
+                    return Err(ProtocolError::InvalidValue(format!("NBT list mixes tag types {} and {}", element, other.id())));
+                }
+
+                buf.push(element);
+                write_length(buf, list.len())?;
+                for tag in list{
+                    tag.write_payload(buf)?;
+                }
+            },
+            Tag::Compound(compound) => {
+                for (name, tag) in compound.iter(){
+                    buf.push(tag.id());
+                    write_string(buf, name)?;
+                    tag.write_payload(buf)?;
+                }
+                buf.push(TAG_END);
+            },
+            Tag::IntArray(array) => {
+                write_length(buf, array.len())?;
+                for value in array{
+                    buf.extend_from_slice(&value.to_be_bytes());
+                }
+            },
+            Tag::LongArray(array) => {
+                write_length(buf, array.len())?;
+                for value in array{
+                    buf.extend_from_slice(&value.to_be_bytes());
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl From<Compound> for Tag{
+    fn from(compound: Compound) -> Self{
+        Tag::Compound(compound)
+    }
+}
+
+// SNBT-ish, good enough for printing
+impl fmt::Display for Tag{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        fn join<T: fmt::Display>(f: &mut fmt::Formatter<'_>, prefix: &str, values: &[T], suffix: &str) -> fmt::Result{
+            write!(f, "[{}", prefix)?;
+            for (i, value) in values.iter().enumerate(){
+                if i > 0{
+                    write!(f, ",")?;
+                }
+                write!(f, "{}{}", value, suffix)?;
+            }
+            write!(f, "]")
+        }
+
+        match self{
+            Tag::Byte(value) => write!(f, "{}b", value),
+            Tag::Short(value) => write!(f, "{}s", value),
+            Tag::Int(value) => write!(f, "{}", value),
+            Tag::Long(value) => write!(f, "{}L", value),
+            Tag::Float(value) => write!(f, "{}f", value),
+            Tag::Double(value) => write!(f, "{}d", value),
+            Tag::ByteArray(array) => join(f, "B;", array, "b"),
+            Tag::String(value) => write!(f, "{:?}", value),
+            Tag::List(list) => join(f, "", list, ""),
+            Tag::Compound(compound) => {
+                write!(f, "{{")?;
+                for (i, (name, tag)) in compound.iter().enumerate(){
+                    if i > 0{
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{:?}:{}", name, tag)?;
+                }
+                write!(f, "}}")
+            },
+            Tag::IntArray(array) => join(f, "I;", array, ""),
+            Tag::LongArray(array) => join(f, "L;", array, "L"),
+        }
+    }
+}
+
+// named tags in the order they were read, so writing a compound back gives the same bytes
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Compound{
+    entries: Vec<(String, Tag)>,
+}
+
+impl Compound{
+    pub fn new() -> Self{
+        Compound{entries: Vec::new()}
+    }
+
+    pub fn len(&self) -> usize{
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag>{
+        self.entries.iter().find(|(key, _)| key == name).map(|(_, tag)| tag)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag>{
+        self.entries.iter_mut().find(|(key, _)| key == name).map(|(_, tag)| tag)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool{
+        self.get(name).is_some()
+    }
+
+    // replaces the tag in place if the name is already there, like a map would
+    pub fn insert(&mut self, name: impl Into<String>, tag: Tag) -> Option<Tag>{
+        let name = name.into();
+        match self.get_mut(&name){
+            Some(old) => Some(std::mem::replace(old, tag)),
+            None => {
+                self.entries.push((name, tag));
+                None
+            },
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tag>{
+        let index = self.entries.iter().position(|(key, _)| key == name)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Tag)>{
+        self.entries.iter().map(|(name, tag)| (name, tag))
+    }
+}
+
+impl IntoIterator for Compound{
+    type Item = (String, Tag);
+    type IntoIter = std::vec::IntoIter<(String, Tag)>;
+
+    fn into_iter(self) -> Self::IntoIter{
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<(String, Tag)> for Compound{
+    fn from_iter<I: IntoIterator<Item = (String, Tag)>>(iter: I) -> Self{
+        let mut compound = Compound::new();
+        for (name, tag) in iter{
+            compound.insert(name, tag);
+        }
+        compound
+    }
+}
+
+// the root tag with its name, which is how files and the protocol up to 1.20.1 send it.
+// None for a lone TAG_End, the protocol's way of saying there is no NBT
+pub fn read_named(reader: &mut PacketReader<'_>) -> Result<Option<(String, Tag)>, ProtocolError>{
+    let id = reader.read_u8()?;
+    if id == TAG_END{
+        return Ok(None);
+    }
+    let name = read_string(reader)?;
+    Ok(Some((name, Tag::read_payload(reader, id, 0)?)))
+}
+
+// the root tag without a name, what the protocol sends since 1.20.2
+pub fn read_nameless(reader: &mut PacketReader<'_>) -> Result<Option<Tag>, ProtocolError>{
+    let id = reader.read_u8()?;
+    if id == TAG_END{
+        return Ok(None);
+    }
+    Ok(Some(Tag::read_payload(reader, id, 0)?))
+}
+
+pub fn write_named(buf: &mut Vec<u8>, name: &str, tag: &Tag) -> Result<(), ProtocolError>{
+    buf.push(tag.id());
+    write_string(buf, name)?;
+    tag.write_payload(buf)
+}
+
+pub fn write_nameless(buf: &mut Vec<u8>, tag: &Tag) -> Result<(), ProtocolError>{
+    buf.push(tag.id());
+    tag.write_payload(buf)
+}
+
+// how an NBT file is packed, level.dat and player data are gzip, region file chunks are zlib
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCompression{
+    None,
+    Gzip,
+    Zlib,
+}
+
+// reads a whole NBT file, the compression is guessed from the first bytes
+pub fn read_file(data: &[u8]) -> Result<(String, Tag), ProtocolError>{
+    let compression = match data{
+        [0x1F, 0x8B, ..] => FileCompression::Gzip,
+        [0x78, 0x01 | 0x5E | 0x9C | 0xDA, ..] => FileCompression::Zlib,
+        _ => FileCompression::None,
+    };
+
+    let mut inflated = Vec::new();
+    let data = match compression{
+        FileCompression::Gzip => {
+            GzDecoder::new(data).take(MAX_FILE_SIZE).read_to_end(&mut inflated)?;
+            inflated.as_slice()
+        },
+        FileCompression::Zlib => {
+            ZlibDecoder::new(data).take(MAX_FILE_SIZE).read_to_end(&mut inflated)?;
+            inflated.as_slice()
+        },
+        FileCompression::None => data,
+    };
+
+    let mut reader = PacketReader::new(data);
+    read_named(&mut reader)?.ok_or_else(|| ProtocolError::InvalidValue("NBT file is empty".to_string()))
+}
+
+pub fn write_file(name: &str, tag: &Tag, compression: FileCompression) -> Result<Vec<u8>, ProtocolError>{
+    let mut buf = Vec::new();
+    write_named(&mut buf, name, tag)?;
+
+    Ok(match compression{
+        FileCompression::None => buf,
+        FileCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&buf)?;
+            encoder.finish()?
+        },
+        FileCompression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&buf)?;
+            encoder.finish()?
+        },
+    })
+}
+
+fn read_length(reader: &mut PacketReader<'_>, element_size: usize) -> Result<usize, ProtocolError>{
+    let length = reader.read_i32()?;
+    if length < 0{
+        return Err(ProtocolError::NegativeLength(length));
+    }
+    let length = length as usize;
+    // checked up front so a bogus length can't make us allocate gigabytes
+    if length * element_size > reader.remaining(){
+        return Err(ProtocolError::UnexpectedEof{needed: length * element_size, remaining: reader.remaining()});
+    }
+    Ok(length)
+}
+
+fn write_length(buf: &mut Vec<u8>, length: usize) -> Result<(), ProtocolError>{
+    let length = i32::try_from(length).map_err(|_| ProtocolError::InvalidValue(format!("NBT array too long: {}", length)))?;
+    buf.extend_from_slice(&length.to_be_bytes());
+    Ok(())
+}
+
+// NBT strings are java's "modified UTF-8": a u16 length, \0 as two bytes and characters outside
+// the BMP as two 3 byte surrogates. anything that is also valid UTF-8 means the same thing in both
+fn read_string(reader: &mut PacketReader<'_>) -> Result<String, ProtocolError>{
+    let length = reader.read_u16()?;
+    let bytes = reader.read_bytes(length as usize)?;
+    if let Ok(string) = std::str::from_utf8(bytes){
+        return Ok(string.to_string());
+    }
+
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len(){
+        let byte = bytes[i] as u16;
+        let (unit, size) = match byte{
+            0x00..=0x7F => (byte, 1),
+            0xC0..=0xDF if i + 1 < bytes.len() => ((byte & 0x1F) << 6 | (bytes[i + 1] as u16 & 0x3F), 2),
+            0xE0..=0xEF if i + 2 < bytes.len() => ((byte & 0x0F) << 12 | (bytes[i + 1] as u16 & 0x3F) << 6 | (bytes[i + 2] as u16 & 0x3F), 3),
+            _ => return Err(ProtocolError::InvalidValue("NBT string is not valid modified UTF-8".to_string())),
+        };
+        units.push(unit);
+        i += size;
+    }
+    Ok(String::from_utf16_lossy(&units))
+}
+
+fn write_string(buf: &mut Vec<u8>, string: &str) -> Result<(), ProtocolError>{
+    let mut encoded;
+    let bytes = if string.chars().any(|char| char == '\0' || char as u32 > 0xFFFF){
+        encoded = Vec::with_capacity(string.len() + 4);
+        for unit in string.encode_utf16(){
+            match unit{
+                0x01..=0x7F => encoded.push(unit as u8),
+                0x00..=0x7FF => encoded.extend_from_slice(&[0xC0 | (unit >> 6) as u8, 0x80 | (unit & 0x3F) as u8]),
+                _ => encoded.extend_from_slice(&[0xE0 | (unit >> 12) as u8, 0x80 | (unit >> 6 & 0x3F) as u8, 0x80 | (unit & 0x3F) as u8]),
+            }
+        }
+        encoded.as_slice()
+    } else {
+        string.as_bytes()
+    };
+
+    let length = u16::try_from(bytes.len()).map_err(|_| ProtocolError::StringTooLong{max: u16::MAX as usize, length: bytes.len()})?;
+    buf.extend_from_slice(&length.to_be_bytes());
+    buf.extend_from_slice(bytes);
+    Ok(())
+}
+
+impl serde::ser::Error for ProtocolError{
+    fn custom<T: fmt::Display>(msg: T) -> Self{
+        ProtocolError::InvalidValue(msg.to_string())
+    }
+}
+
+impl serde::de::Error for ProtocolError{
+    fn custom<T: fmt::Display>(msg: T) -> Self{
+        ProtocolError::InvalidValue(msg.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        crate::world::World,
+    };
+
+    // where each fixture came from is in tests/fixtures/nbt/sources.txt
+    const BIGTEST: &[u8] = include_bytes!("../tests/fixtures/nbt/bigtest.nbt");
+    const LEVEL: &[u8] = include_bytes!("../tests/fixtures/nbt/level.dat");
+    const COMPLEX_PLAYER: &[u8] = include_bytes!("../tests/fixtures/nbt/complex_player.dat");
+    const PLAYER_NAN: &[u8] = include_bytes!("../tests/fixtures/nbt/Player-nan-value.dat");
+    const ARRAYS: &[u8] = include_bytes!("../tests/fixtures/nbt/arrays.nbt");
+    const REGISTRY_CODEC: &[u8] = include_bytes!("../tests/fixtures/nbt/registry_codec_1.20.1.nbt");
+
+    fn gunzip(data: &[u8]) -> Vec<u8>{
+        let mut inflated = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut inflated).unwrap();
+        inflated
+    }
+
+    #[test]
+    fn bigtest_values(){
+        let (name, tag) = read_file(BIGTEST).unwrap();
+        assert_eq!(name, "Level");
+        assert_eq!(tag.get("longTest"), Some(&Tag::Long(i64::MAX)));
+        assert_eq!(tag.get("shortTest"), Some(&Tag::Short(i16::MAX)));
+        assert_eq!(tag.get("intTest"), Some(&Tag::Int(i32::MAX)));
+        assert_eq!(tag.get("byteTest"), Some(&Tag::Byte(i8::MAX)));
+        assert_eq!(tag.get("floatTest"), Some(&Tag::Float(0.49823147)));
+        assert_eq!(tag.get("doubleTest"), Some(&Tag::Double(0.4931287132182315)));
+        assert_eq!(tag.get("stringTest").and_then(Tag::as_str), Some("HELLO WORLD THIS IS A TEST STRING ÅÄÖ!"));
+
+        let nested = tag.get("nested compound test").unwrap();
+        assert_eq!(nested.get("egg").and_then(|egg| egg.get("name")).and_then(Tag::as_str), Some("Eggbert"));
+        assert_eq!(nested.get("ham").and_then(|ham| ham.get("value")).and_then(Tag::as_f64), Some(0.75));
+
+        let longs = tag.get("listTest (long)").and_then(Tag::as_list).unwrap();
+        assert_eq!(longs.iter().filter_map(Tag::as_i64).collect::<Vec<_>>(), vec![11, 12, 13, 14, 15]);
+        let compounds = tag.get("listTest (compound)").and_then(Tag::as_list).unwrap();
+        assert_eq!(compounds[1].get("name").and_then(Tag::as_str), Some("Compound tag #1"));
+        assert_eq!(compounds[0].get("created-on").and_then(Tag::as_i64), Some(1264099775885));
+
+        let bytes = match tag.get("byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))"){
+            Some(Tag::ByteArray(bytes)) => bytes,
+            other => panic!("not a byte array: {:?}", other),
+        };
+        assert_eq!(bytes.len(), 1000);
+        for (n, byte) in bytes.iter().enumerate(){
+            assert_eq!(*byte as usize, (n * n * 255 + n * 7) % 100);
+        }
+    }
+
+    #[test]
+    fn files_write_back_the_same_bytes(){
+        for file in [BIGTEST, LEVEL, COMPLEX_PLAYER]{
+            let inflated = gunzip(file);
+            let (name, tag) = read_file(file).unwrap();
+            assert_eq!(write_file(&name, &tag, FileCompression::None).unwrap(), inflated);
+            // the uncompressed bytes read the same
+            assert_eq!(write_file(&name, &read_file(&inflated).unwrap().1, FileCompression::None).unwrap(), inflated);
+        }
+
+        // an old game wrote the empty Inventory list as a list of bytes, vanilla writes empty
+        // lists as TAG_End lists nowadays and so do we. everything else stays the same
+        let mut expected = gunzip(PLAYER_NAN);
+        let inventory = expected.windows(12).position(|window| window == b"\x09\x00\x09Inventory").unwrap();
+        assert_eq!(expected[inventory + 12], TAG_BYTE);
+        expected[inventory + 12] = TAG_END;
+        let (name, tag) = read_file(PLAYER_NAN).unwrap();
+        assert_eq!(write_file(&name, &tag, FileCompression::None).unwrap(), expected);
+
+        let (name, tag) = read_file(ARRAYS).unwrap();
+        assert_eq!(write_file(&name, &tag, FileCompression::None).unwrap(), ARRAYS);
+        assert_eq!(tag.get("ia"), Some(&Tag::IntArray(vec![-2, -1, 0, 1, 2])));
+        assert_eq!(tag.get("la"), Some(&Tag::LongArray(vec![-2, -1, 0, 1, 2])));
+        assert_eq!(tag.get("ba"), Some(&Tag::ByteArray(vec![-2, -1, 0, 1, 2])));
+    }
+
+    #[test]
+    fn compressed_round_trip(){
+        for file in [BIGTEST, LEVEL, COMPLEX_PLAYER, PLAYER_NAN]{
+            let (name, tag) = read_file(file).unwrap();
+            let uncompressed = write_file(&name, &tag, FileCompression::None).unwrap();
+
+            for compression in [FileCompression::Gzip, FileCompression::Zlib]{
+                let packed = write_file(&name, &tag, compression).unwrap();
+                let (read_name, read_tag) = read_file(&packed).unwrap();
+                assert_eq!(read_name, name);
+                // compared as bytes, the NaN in Player-nan-value.dat isn't equal to itself
+                assert_eq!(write_file(&read_name, &read_tag, FileCompression::None).unwrap(), uncompressed);
+            }
+        }
+    }
+
+    #[test]
+    fn level_dat(){
+        let (_, tag) = read_file(LEVEL).unwrap();
+        let data = tag.get("Data").unwrap();
+        assert_eq!(data.get("DataVersion").and_then(Tag::as_i64), Some(2584));
+        assert_eq!(data.get("LevelName").and_then(Tag::as_str), Some("world"));
+        assert_eq!(data.get("GameRules").and_then(|rules| rules.get("keepInventory")).and_then(Tag::as_str), Some("false"));
+    }
+
+    #[test]
+    fn network_registry_codec(){
+        // 1.20.1 and older send the root compound with an (empty) name
+        let mut reader = PacketReader::new(REGISTRY_CODEC);
+        let (name, codec) = read_named(&mut reader).unwrap().unwrap();
+        assert!(name.is_empty());
+        assert!(reader.is_empty());
+
+        let mut buf = Vec::new();
+        write_named(&mut buf, &name, &codec).unwrap();
+        assert_eq!(buf, REGISTRY_CODEC);
+
+        // 1.20.2 dropped the name, which is the 2 byte length right after the tag type
+        let mut nameless = vec![REGISTRY_CODEC[0]];
+        nameless.extend_from_slice(&REGISTRY_CODEC[3..]);
+        let mut reader = PacketReader::new(&nameless);
+        assert_eq!(read_nameless(&mut reader).unwrap().as_ref(), Some(&codec));
+        assert!(reader.is_empty());
+        let mut buf = Vec::new();
+        write_nameless(&mut buf, &codec).unwrap();
+        assert_eq!(buf, nameless);
+
+        let overworld = World::for_dimension(&codec, "minecraft:overworld");
+        assert_eq!((overworld.min_y(), overworld.height()), (-64, 384));
+        let nether = World::for_dimension(&codec, "minecraft:the_nether");
+        assert_eq!((nether.min_y(), nether.height()), (0, 256));
+
+        // a lone TAG_End is how the protocol says "no NBT"
+        assert!(read_named(&mut PacketReader::new(&[TAG_END])).unwrap().is_none());
+        assert!(read_nameless(&mut PacketReader::new(&[TAG_END])).unwrap().is_none());
+    }
+
+    #[test]
+    fn truncated_and_malformed(){
+        let inflated = gunzip(BIGTEST);
+        for end in 0..inflated.len(){
+            assert!(read_named(&mut PacketReader::new(&inflated[..end])).is_err(), "read bigtest cut at {}", end);
+        }
+
+        let cases: [&[u8]; 6] = [
+            &[TAG_COMPOUND, 0, 0, 42, 0, 0, 0], // unknown tag type
+            &[TAG_BYTE_ARRAY, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF], // negative length
+            &[TAG_INT_ARRAY, 0, 0, 0x7F, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0], // length past the end
+            &[TAG_LIST, 0, 0, TAG_END, 0, 0, 0, 1], // TAG_End elements
+            &[TAG_STRING, 0, 0, 0, 2, 0xFF, 0xFE], // not modified UTF-8
+            &[TAG_LIST, 0, 0, TAG_LONG, 0x7F, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 1],
+        ];
+        for case in cases{
+            assert!(read_named(&mut PacketReader::new(case)).is_err(), "{:?}", case);
+        }
+
+        // lists of lists nested past the limit
+        let mut deep = vec![TAG_LIST, 0, 0];
+        for _ in 0..MAX_DEPTH + 1{
+            deep.extend_from_slice(&[TAG_LIST, 0, 0, 0, 1]);
+        }
+        deep.extend_from_slice(&[TAG_END, 0, 0, 0, 0]);
+        assert!(matches!(read_named(&mut PacketReader::new(&deep)), Err(ProtocolError::InvalidValue(_))));
+
+        assert!(read_file(&[]).is_err());
+        assert!(read_file(&[0x1F, 0x8B, 0x08, 0x00]).is_err());
+    }
+
+    #[test]
+    fn modified_utf8(){
+        let string = "nul \0 and outside the BMP 🦀";
+        let mut buf = Vec::new();
+        write_string(&mut buf, string).unwrap();
+        // \0 is two bytes and the crab is two 3 byte surrogates, 29 ascii/nul chars + 6
+        assert_eq!(&buf[..2], &[0, 33]);
+        assert!(!buf[2..].contains(&0));
+        assert_eq!(read_string(&mut PacketReader::new(&buf)).unwrap(), string);
+
+        let mut buf = Vec::new();
+        write_string(&mut buf, "ÅÄÖ").unwrap();
+        assert_eq!(buf, [0, 6, 0xC3, 0x85, 0xC3, 0x84, 0xC3, 0x96]);
+
+        let mut list = Compound::new();
+        list.insert("mixed", Tag::List(vec![Tag::Int(1), Tag::Short(2)]));
+        assert!(write_named(&mut Vec::new(), "", &Tag::Compound(list)).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use {
+    serde::de::{
+        self,
+        DeserializeOwned,
+        IntoDeserializer,
+        Visitor,
+    },
+    crate::{
+        codec::ProtocolError,
+        nbt::{
+            Compound,
+            Tag,
+        },
+    },
+};
+
+// the other way around from to_tag. whole number tags convert to any integer type (negative
+// bytes included, so what to_tag wrote as a u8 comes back as the same u8), bytes work as bools,
+// the array tags read as sequences and a compound with one entry (or a string) reads as an enum
+pub fn from_tag<T: DeserializeOwned>(tag: Tag) -> Result<T, ProtocolError>{
+    T::deserialize(TagDeserializer(tag))
+}
+
+pub struct TagDeserializer(Tag);
+
+impl TagDeserializer{
+    fn integer(&self) -> Result<i64, ProtocolError>{
+        self.0.as_i64().ok_or_else(|| self.unexpected("a whole number"))
+    }
+
+    fn unexpected(&self, expected: &str) -> ProtocolError{
+        ProtocolError::InvalidValue(format!("expected {}, found NBT tag type {}", expected, self.0.id()))
+    }
+}
+
+macro_rules! deserialize_integer{
+    ($method:ident, $visit:ident, $type:ty) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+            let value = self.integer()?;
+            visitor.$visit(value as $type)
+        }
+    };
+}
+
+impl<'de> de::Deserializer<'de> for TagDeserializer{
+    type Error = ProtocolError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        match self.0{
+            Tag::Byte(value) => visitor.visit_i8(value),
+            Tag::Short(value) => visitor.visit_i16(value),
+            Tag::Int(value) => visitor.visit_i32(value),
+            Tag::Long(value) => visitor.visit_i64(value),
+            Tag::Float(value) => visitor.visit_f32(value),
+            Tag::Double(value) => visitor.visit_f64(value),
+            Tag::ByteArray(array) => visitor.visit_seq(ListAccess(array.into_iter().map(Tag::Byte).collect::<Vec<_>>().into_iter())),
+            Tag::String(value) => visitor.visit_string(value),
+            Tag::List(list) => visitor.visit_seq(ListAccess(list.into_iter())),
+            Tag::Compound(compound) => visitor.visit_map(CompoundAccess{entries: compound.into_iter(), value: None}),
+            Tag::IntArray(array) => visitor.visit_seq(ListAccess(array.into_iter().map(Tag::Int).collect::<Vec<_>>().into_iter())),
+            Tag::LongArray(array) => visitor.visit_seq(ListAccess(array.into_iter().map(Tag::Long).collect::<Vec<_>>().into_iter())),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        let value = self.integer()?;
+        visitor.visit_bool(value != 0)
+    }
+
+    deserialize_integer!(deserialize_i8, visit_i8, i8);
+    deserialize_integer!(deserialize_i16, visit_i16, i16);
+    deserialize_integer!(deserialize_i32, visit_i32, i32);
+    deserialize_integer!(deserialize_i64, visit_i64, i64);
+    deserialize_integer!(deserialize_u8, visit_u8, u8);
+    deserialize_integer!(deserialize_u16, visit_u16, u16);
+    deserialize_integer!(deserialize_u32, visit_u32, u32);
+    deserialize_integer!(deserialize_u64, visit_u64, u64);
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        let value = self.0.as_f64().ok_or_else(|| self.unexpected("a number"))?;
+        visitor.visit_f32(value as f32)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        let value = self.0.as_f64().ok_or_else(|| self.unexpected("a number"))?;
+        visitor.visit_f64(value)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        match self.0{
+            Tag::ByteArray(array) => visitor.visit_byte_buf(array.into_iter().map(|byte| byte as u8).collect()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        self.deserialize_bytes(visitor)
+    }
+
+    // a missing field never gets here, serde turns that into None on its own
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProtocolError>{
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, ProtocolError>{
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, ProtocolError>{
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, ProtocolError>{
+        match self.0{
+            Tag::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Tag::Compound(compound) if compound.len() == 1 => {
+                let (variant, value) = compound.into_iter().next().unwrap_or_else(|| unreachable!());
+                visitor.visit_enum(EnumAccess{variant, value})
+            },
+            _ => Err(self.unexpected("a string or a compound with one entry")),
+        }
+    }
+
+    serde::forward_to_deserialize_any!{
+        char str string seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ProtocolError> for Tag{
+    type Deserializer = TagDeserializer;
+
+    fn into_deserializer(self) -> TagDeserializer{
+        TagDeserializer(self)
+    }
+}
+
+struct ListAccess(std::vec::IntoIter<Tag>);
+
+impl<'de> de::SeqAccess<'de> for ListAccess{
+    type Error = ProtocolError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, ProtocolError>{
+        match self.0.next(){
+            Some(tag) => seed.deserialize(TagDeserializer(tag)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize>{
+        Some(self.0.len())
+    }
+}
+
+struct CompoundAccess{
+    entries: <Compound as IntoIterator>::IntoIter,
+    value: Option<Tag>,
+}
+
+impl<'de> de::MapAccess<'de> for CompoundAccess{
+    type Error = ProtocolError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, ProtocolError>{
+        match self.entries.next(){
+            Some((name, tag)) => {
+                self.value = Some(tag);
+                seed.deserialize(name.into_deserializer()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ProtocolError>{
+        let tag = self.value.take().ok_or_else(|| ProtocolError::InvalidValue("compound value asked for twice".to_string()))?;
+        seed.deserialize(TagDeserializer(tag))
+    }
+
+    fn size_hint(&self) -> Option<usize>{
+        Some(self.entries.len())
+    }
+}
+
+struct EnumAccess{
+    variant: String,
+    value: Tag,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess{
+    type Error = ProtocolError;
+    type Variant = TagDeserializer;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, TagDeserializer), ProtocolError>{
+        let variant = seed.deserialize(IntoDeserializer::<ProtocolError>::into_deserializer(self.variant))?;
+        Ok((variant, TagDeserializer(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for TagDeserializer{
+    type Error = ProtocolError;
+
+    fn unit_variant(self) -> Result<(), ProtocolError>{
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, ProtocolError>{
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _length: usize, visitor: V) -> Result<V::Value, ProtocolError>{
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, ProtocolError>{
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        serde::{
+            Deserialize,
+            Serialize,
+        },
+        crate::nbt::{
+            ByteArray,
+            FileCompression,
+            IntArray,
+            LongArray,
+            Tag,
+            from_tag,
+            read_file,
+            to_tag,
+            write_file,
+        },
+    };
+
+    const BIGTEST: &[u8] = include_bytes!("../../tests/fixtures/nbt/bigtest.nbt");
+    const LEVEL: &[u8] = include_bytes!("../../tests/fixtures/nbt/level.dat");
+    const ARRAYS: &[u8] = include_bytes!("../../tests/fixtures/nbt/arrays.nbt");
+
+    // every field of bigtest.nbt, in the order the file has them
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Bigtest{
+        #[serde(rename = "longTest")]
+        long: i64,
+        #[serde(rename = "shortTest")]
+        short: i16,
+        #[serde(rename = "stringTest")]
+        string: String,
+        #[serde(rename = "floatTest")]
+        float: f32,
+        #[serde(rename = "intTest")]
+        int: i32,
+        #[serde(rename = "nested compound test")]
+        nested: Nested,
+        #[serde(rename = "listTest (long)")]
+        longs: Vec<i64>,
+        #[serde(rename = "listTest (compound)")]
+        compounds: Vec<Created>,
+        #[serde(rename = "byteTest")]
+        byte: i8,
+        #[serde(rename = "byteArrayTest (the first 1000 values of (n*n*255+n*7)%100, starting with n=0 (0, 62, 34, 16, 8, ...))")]
+        bytes: ByteArray,
+        #[serde(rename = "doubleTest")]
+        double: f64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Nested{
+        ham: Food,
+        egg: Food,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Food{
+        name: String,
+        value: f32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Created{
+        name: String,
+        #[serde(rename = "created-on")]
+        created_on: i64,
+    }
+
+    #[test]
+    fn bigtest_through_serde(){
+        let (name, tag) = read_file(BIGTEST).unwrap();
+        let bigtest: Bigtest = from_tag(tag.clone()).unwrap();
+        assert_eq!(bigtest.long, i64::MAX);
+        assert_eq!(bigtest.nested.egg.name, "Eggbert");
+        assert_eq!(bigtest.nested.ham.value, 0.75);
+        assert_eq!(bigtest.longs, vec![11, 12, 13, 14, 15]);
+        assert_eq!(bigtest.compounds[0].created_on, 1264099775885);
+        assert_eq!(bigtest.bytes.0[1], 62);
+        assert_eq!(bigtest.string, "HELLO WORLD THIS IS A TEST STRING ÅÄÖ!");
+
+        // and back, to the very same bytes
+        let written = to_tag(&bigtest).unwrap();
+        assert_eq!(written, tag);
+        assert_eq!(write_file(&name, &written, FileCompression::Gzip).map(|file| read_file(&file).unwrap().1).unwrap(), tag);
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Level{
+        data: LevelData,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct LevelData{
+        data_version: u32,
+        level_name: String,
+        difficulty_locked: bool, // a byte in the file
+        game_type: GameType, // an int
+        data_packs: DataPacks,
+        game_rules: std::collections::HashMap<String, String>,
+        #[serde(rename = "WanderingTraderId")]
+        wandering_trader: Option<IntArray>,
+        not_in_the_file: Option<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(from = "i32")]
+    enum GameType{
+        Survival,
+        Other(i32),
+    }
+
+    impl From<i32> for GameType{
+        fn from(id: i32) -> Self{
+            match id{
+                0 => GameType::Survival,
+                id => GameType::Other(id),
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct DataPacks{
+        enabled: Vec<String>,
+        disabled: Vec<String>,
+    }
+
+    #[test]
+    fn level_dat_through_serde(){
+        let level: Level = from_tag(read_file(LEVEL).unwrap().1).unwrap();
+        let data = level.data;
+        assert_eq!(data.data_version, 2584);
+        assert_eq!(data.level_name, "world");
+        assert!(!data.difficulty_locked);
+        assert_eq!(data.game_type, GameType::Survival);
+        assert_eq!(data.data_packs.enabled[0], "vanilla");
+        assert!(data.data_packs.disabled.is_empty());
+        assert_eq!(data.game_rules.get("randomTickSpeed").map(String::as_str), Some("3"));
+        assert!(data.not_in_the_file.is_none());
+        assert!(data.wandering_trader.is_none_or(|uuid| uuid.0.len() == 4));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Arrays{
+        la: LongArray,
+        ia: IntArray,
+        ba: ByteArray,
+    }
+
+    #[test]
+    fn array_tags(){
+        let (_, tag) = read_file(ARRAYS).unwrap();
+        let arrays: Arrays = from_tag(tag.clone()).unwrap();
+        assert_eq!(arrays, Arrays{la: LongArray(vec![-2, -1, 0, 1, 2]), ia: IntArray(vec![-2, -1, 0, 1, 2]), ba: ByteArray(vec![-2, -1, 0, 1, 2])});
+        assert_eq!(to_tag(&arrays).unwrap(), tag);
+
+        // without the newtypes they are plain lists, which read fine too
+        let lists: std::collections::HashMap<String, Vec<i64>> = from_tag(tag).unwrap();
+        assert_eq!(lists["ba"], vec![-2, -1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn wrong_types_are_errors(){
+        let (_, tag) = read_file(BIGTEST).unwrap();
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Wrong{
+            #[serde(rename = "stringTest")]
+            string: i32,
+        }
+        assert!(from_tag::<Wrong>(tag.clone()).is_err());
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Missing{
+            missing: String,
+        }
+        assert!(from_tag::<Missing>(tag).is_err());
+        assert!(from_tag::<String>(Tag::Int(1)).is_err());
+        assert!(from_tag::<Vec<i32>>(Tag::String("a".to_string())).is_err());
+    }
+}
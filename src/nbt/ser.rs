@@ -0,0 +1,604 @@
+use {
+    serde::ser::{
+        self,
+        Impossible,
+        Serialize,
+    },
+    crate::{
+        codec::ProtocolError,
+        nbt::{
+            BYTE_ARRAY,
+            Compound,
+            INT_ARRAY,
+            LONG_ARRAY,
+            Tag,
+        },
+    },
+};
+
+// turns anything serde can serialize into a Tag. structs and maps become compounds, sequences
+// become lists, bytes become a byte array and None fields are left out. unsigned numbers are
+// stored in the signed tag of the same size, so a u8 of 200 is written as the byte -56
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> Result<Tag, ProtocolError>{
+    value.serialize(TagSerializer)?.ok_or_else(|| ProtocolError::InvalidValue("NBT can't hold a None on its own".to_string()))
+}
+
+// None is what an Option::None or a unit serializes to, which only makes sense inside a compound
+type Output = Option<Tag>;
+
+fn required<T: Serialize + ?Sized>(value: &T) -> Result<Tag, ProtocolError>{
+    value.serialize(TagSerializer)?.ok_or_else(|| ProtocolError::InvalidValue("NBT lists can't hold a None".to_string()))
+}
+
+fn array<F: FnOnce(&[Tag]) -> Option<Tag>>(tag: Tag, name: &str, convert: F) -> Result<Output, ProtocolError>{
+    let converted = match &tag{
+        Tag::ByteArray(array) if name == BYTE_ARRAY => Some(Tag::ByteArray(array.clone())),
+        Tag::List(list) => convert(list),
+        _ => None,
+    };
+    converted.map(Some).ok_or_else(|| ProtocolError::InvalidValue(format!("{} has to be a sequence of numbers that fit, found NBT tag type {}", name, tag.id())))
+}
+
+// the elements of a list as the element type of an array tag, None if one of them doesn't fit
+fn numbers<T: TryFrom<i64>>(list: &[Tag]) -> Option<Vec<T>>{
+    list.iter().map(|tag| tag.as_i64().and_then(|value| T::try_from(value).ok())).collect()
+}
+
+fn variant(name: &str, tag: Tag) -> Tag{
+    let mut compound = Compound::new();
+    compound.insert(name, tag);
+    Tag::Compound(compound)
+}
+
+struct TagSerializer;
+
+impl ser::Serializer for TagSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = CompoundSerializer;
+    type SerializeStruct = CompoundSerializer;
+    type SerializeStructVariant = CompoundSerializer;
+
+    fn serialize_bool(self, value: bool) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Byte(value as i8)))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Byte(value)))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Short(value)))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Int(value)))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Long(value)))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Byte(value as i8)))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Short(value as i16)))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Int(value as i32)))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Long(value as i64)))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Float(value)))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Double(value)))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::String(value.to_string())))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::String(value.to_string())))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::ByteArray(value.iter().map(|byte| *byte as i8).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Output, ProtocolError>{
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Output, ProtocolError>{
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Output, ProtocolError>{
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::Compound(Compound::new())))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Output, ProtocolError>{
+        Ok(Some(Tag::String(variant.to_string())))
+    }
+
+    // the array newtypes (see nbt::IntArray) come through here under their marker names
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, name: &'static str, value: &T) -> Result<Output, ProtocolError>{
+        match name{
+            BYTE_ARRAY => array(required(value)?, name, |tag| Some(Tag::ByteArray(numbers(tag)?))),
+            INT_ARRAY => array(required(value)?, name, |tag| Some(Tag::IntArray(numbers(tag)?))),
+            LONG_ARRAY => array(required(value)?, name, |tag| Some(Tag::LongArray(numbers(tag)?))),
+            _ => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant_name: &'static str, value: &T) -> Result<Output, ProtocolError>{
+        Ok(Some(variant(variant_name, required(value)?)))
+    }
+
+    fn serialize_seq(self, length: Option<usize>) -> Result<ListSerializer, ProtocolError>{
+        Ok(ListSerializer{variant: None, list: Vec::with_capacity(length.unwrap_or(0))})
+    }
+
+    fn serialize_tuple(self, length: usize) -> Result<ListSerializer, ProtocolError>{
+        self.serialize_seq(Some(length))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, length: usize) -> Result<ListSerializer, ProtocolError>{
+        self.serialize_seq(Some(length))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, length: usize) -> Result<ListSerializer, ProtocolError>{
+        Ok(ListSerializer{variant: Some(variant), list: Vec::with_capacity(length)})
+    }
+
+    fn serialize_map(self, _length: Option<usize>) -> Result<CompoundSerializer, ProtocolError>{
+        Ok(CompoundSerializer{variant: None, compound: Compound::new(), key: None})
+    }
+
+    fn serialize_struct(self, _name: &'static str, length: usize) -> Result<CompoundSerializer, ProtocolError>{
+        self.serialize_map(Some(length))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _length: usize) -> Result<CompoundSerializer, ProtocolError>{
+        Ok(CompoundSerializer{variant: Some(variant), compound: Compound::new(), key: None})
+    }
+}
+
+struct ListSerializer{
+    variant: Option<&'static str>,
+    list: Vec<Tag>,
+}
+
+impl ListSerializer{
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ProtocolError>{
+        let tag = required(value)?;
+        if let Some(first) = self.list.first(){
+            if first.id() != tag.id(){
+                return Err(ProtocolError::InvalidValue(format!("NBT lists can't mix tag types {} and {}", first.id(), tag.id())));
+            }
+        }
+        self.list.push(tag);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Output, ProtocolError>{
+        let list = Tag::List(self.list);
+        Ok(Some(match self.variant{
+            Some(name) => variant(name, list),
+            None => list,
+        }))
+    }
+}
+
+impl ser::SerializeSeq for ListSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ProtocolError>{
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ProtocolError>{
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ProtocolError>{
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ListSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ProtocolError>{
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+struct CompoundSerializer{
+    variant: Option<&'static str>,
+    compound: Compound,
+    key: Option<String>,
+}
+
+impl CompoundSerializer{
+    fn insert<T: Serialize + ?Sized>(&mut self, name: String, value: &T) -> Result<(), ProtocolError>{
+        if let Some(tag) = value.serialize(TagSerializer)?{
+            self.compound.insert(name, tag);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Output, ProtocolError>{
+        let compound = Tag::Compound(self.compound);
+        Ok(Some(match self.variant{
+            Some(name) => variant(name, compound),
+            None => compound,
+        }))
+    }
+}
+
+impl ser::SerializeMap for CompoundSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ProtocolError>{
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ProtocolError>{
+        let name = self.key.take().ok_or_else(|| ProtocolError::InvalidValue("map value without a key".to_string()))?;
+        self.insert(name, value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for CompoundSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, name: &'static str, value: &T) -> Result<(), ProtocolError>{
+        self.insert(name.to_string(), value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for CompoundSerializer{
+    type Ok = Output;
+    type Error = ProtocolError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, name: &'static str, value: &T) -> Result<(), ProtocolError>{
+        self.insert(name.to_string(), value)
+    }
+
+    fn end(self) -> Result<Output, ProtocolError>{
+        self.finish()
+    }
+}
+
+// compound names are strings, numbers and chars are turned into one
+struct KeySerializer;
+
+fn key_error() -> ProtocolError{
+    ProtocolError::InvalidValue("NBT compound keys have to be strings".to_string())
+}
+
+impl ser::Serializer for KeySerializer{
+    type Ok = String;
+    type Error = ProtocolError;
+    type SerializeSeq = Impossible<String, ProtocolError>;
+    type SerializeTuple = Impossible<String, ProtocolError>;
+    type SerializeTupleStruct = Impossible<String, ProtocolError>;
+    type SerializeTupleVariant = Impossible<String, ProtocolError>;
+    type SerializeMap = Impossible<String, ProtocolError>;
+    type SerializeStruct = Impossible<String, ProtocolError>;
+    type SerializeStructVariant = Impossible<String, ProtocolError>;
+
+    fn serialize_bool(self, value: bool) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_char(self, value: char) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<String, ProtocolError>{
+        Ok(value.to_string())
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, ProtocolError>{
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<String, ProtocolError>{
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<String, ProtocolError>{
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<String, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _length: Option<usize>) -> Result<Self::SerializeSeq, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _length: usize) -> Result<Self::SerializeTuple, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _length: usize) -> Result<Self::SerializeTupleStruct, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _length: usize) -> Result<Self::SerializeTupleVariant, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _length: Option<usize>) -> Result<Self::SerializeMap, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _length: usize) -> Result<Self::SerializeStruct, ProtocolError>{
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _length: usize) -> Result<Self::SerializeStructVariant, ProtocolError>{
+        Err(key_error())
+    }
+}
+
+// a Tag serializes to whatever it holds, which makes it easy to dump as json. the arrays go
+// through their marker newtypes, so to_tag gives back the same Tag
+impl Serialize for Tag{
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        match self{
+            Tag::Byte(value) => serializer.serialize_i8(*value),
+            Tag::Short(value) => serializer.serialize_i16(*value),
+            Tag::Int(value) => serializer.serialize_i32(*value),
+            Tag::Long(value) => serializer.serialize_i64(*value),
+            Tag::Float(value) => serializer.serialize_f32(*value),
+            Tag::Double(value) => serializer.serialize_f64(*value),
+            Tag::ByteArray(array) => serializer.serialize_newtype_struct(BYTE_ARRAY, array),
+            Tag::String(value) => serializer.serialize_str(value),
+            Tag::List(list) => {
+                let mut seq = serializer.serialize_seq(Some(list.len()))?;
+                for tag in list{
+                    seq.serialize_element(tag)?;
+                }
+                seq.end()
+            },
+            Tag::Compound(compound) => {
+                let mut map = serializer.serialize_map(Some(compound.len()))?;
+                for (name, tag) in compound.iter(){
+                    map.serialize_entry(name, tag)?;
+                }
+                map.end()
+            },
+            Tag::IntArray(array) => serializer.serialize_newtype_struct(INT_ARRAY, array),
+            Tag::LongArray(array) => serializer.serialize_newtype_struct(LONG_ARRAY, array),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        crate::nbt::{
+            IntArray,
+            read_file,
+        },
+    };
+
+    const FIXTURES: [&[u8]; 5] = [
+        include_bytes!("../../tests/fixtures/nbt/bigtest.nbt"),
+        include_bytes!("../../tests/fixtures/nbt/level.dat"),
+        include_bytes!("../../tests/fixtures/nbt/complex_player.dat"),
+        include_bytes!("../../tests/fixtures/nbt/arrays.nbt"),
+        include_bytes!("../../tests/fixtures/nbt/registry_codec_1.20.1.nbt"),
+    ];
+
+    #[test]
+    fn tags_serialize_to_themselves(){
+        for fixture in FIXTURES{
+            let (_, tag) = read_file(fixture).unwrap();
+            assert_eq!(to_tag(&tag).unwrap(), tag);
+        }
+    }
+
+    #[test]
+    fn tags_as_json(){
+        let (_, tag) = read_file(FIXTURES[3]).unwrap();
+        // the array markers don't show up outside of NBT
+        assert_eq!(serde_json::to_string(&tag).unwrap(), r#"{"la":[-2,-1,0,1,2],"ia":[-2,-1,0,1,2],"ba":[-2,-1,0,1,2]}"#);
+    }
+
+    #[derive(serde::Serialize)]
+    enum Shape{
+        Empty,
+        Cube(f64),
+        Box{x: f64, y: f64},
+    }
+
+    #[derive(serde::Serialize)]
+    struct Everything{
+        flag: bool,
+        small: u8,
+        unsigned: u32,
+        missing: Option<i32>,
+        present: Option<i32>,
+        shapes: Vec<Shape>,
+        uuid: IntArray,
+        bytes: RawBytes,
+    }
+
+    // serde only calls serialize_bytes for types that ask for it
+    struct RawBytes([u8; 3]);
+
+    impl Serialize for RawBytes{
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+
+    #[test]
+    fn serde_data_model(){
+        let tag = to_tag(&Everything{
+            flag: true,
+            small: 200,
+            unsigned: u32::MAX,
+            missing: None,
+            present: Some(7),
+            shapes: vec![Shape::Cube(1.0), Shape::Box{x: 1.0, y: 2.0}],
+            uuid: IntArray(vec![1, 2, 3, 4]),
+            bytes: RawBytes([0, 128, 255]),
+        }).unwrap();
+
+        assert_eq!(tag.get("flag"), Some(&Tag::Byte(1)));
+        assert_eq!(tag.get("small"), Some(&Tag::Byte(-56)));
+        assert_eq!(tag.get("unsigned"), Some(&Tag::Int(-1)));
+        assert!(tag.get("missing").is_none());
+        assert_eq!(tag.get("present"), Some(&Tag::Int(7)));
+        assert_eq!(tag.get("uuid"), Some(&Tag::IntArray(vec![1, 2, 3, 4])));
+        assert_eq!(tag.get("bytes"), Some(&Tag::ByteArray(vec![0, -128, -1])));
+
+        let shapes = tag.get("shapes").and_then(Tag::as_list).unwrap();
+        assert_eq!(shapes[0].get("Cube"), Some(&Tag::Double(1.0)));
+        assert_eq!(shapes[1].get("Box").and_then(|shape| shape.get("y")), Some(&Tag::Double(2.0)));
+        assert_eq!(to_tag(&Shape::Empty).unwrap(), Tag::String("Empty".to_string()));
+    }
+
+    #[test]
+    fn unrepresentable_values(){
+        // a list can only hold one tag type
+        assert!(to_tag(&vec![Shape::Empty, Shape::Cube(1.0)]).is_err());
+        assert!(to_tag(&vec![Some(1), None]).is_err());
+        assert!(to_tag(&None::<i32>).is_err());
+        // compound names have to be strings, numbers are turned into one
+        assert_eq!(to_tag(&std::collections::BTreeMap::from([(5u32, 1)])).unwrap().get("5"), Some(&Tag::Int(1)));
+        assert!(to_tag(&std::collections::BTreeMap::from([((1, 2), 1)])).is_err());
+
+        #[derive(serde::Serialize)]
+        #[serde(rename = "__nbt_int_array")]
+        struct NotInts(Vec<String>);
+        assert!(to_tag(&NotInts(vec!["a".to_string()])).is_err());
+        #[derive(serde::Serialize)]
+        #[serde(rename = "__nbt_byte_array")]
+        struct TooBig(Vec<i32>);
+        assert!(to_tag(&TooBig(vec![1000])).is_err());
+    }
+}
@@ -1,416 +1,2025 @@
 use {
-    std::{
-        process::exit,
-        time::SystemTime,
+    std::collections::HashMap,
+    crate::{
+        codec::{
+            BlockPos,
+            Decode,
+            Encode,
+            Packet,
+            PacketReader,
+            ProtocolError,
+            Vec3,
+            varint_write,
+            write_string,
+        },
+        nbt::{
+            self,
+            Tag,
+        },
+        metadata::{
+            MetadataKind,
+            MetadataValue,
+        },
+        registry::EntityType,
+        text::TextComponent,
+        version::{
+            PlayPacket,
+            ProtocolVersion,
+            Serverbound,
+        },
     }
 };
 
-pub fn varint_write(number: i32) -> Vec<u8>{
-    let mut data: Vec<u8> = Vec::new();
-    let mut num = number;
-
-    if num < 0{
-        num = (1 << 31) + num
-    }
-
-    while num >= 0x80{
-        let byte = num & 0x7F;
-        num = num >> 7;
-
-        let tmp = (0x80 | byte) as u8;
-        data.extend_from_slice(&[tmp]);
-    }
-    let byte = (num & 0x7F) as u8;
-    data.extend_from_slice(&[byte]);
-    data
-}
-
-pub fn varint_read(data: &Vec<u8>) -> (i32, usize){
-    if data.len() == 0{
-        println!("error, data can't be empty [pub varint_read]");
-        exit(1)
-    }
-
-    let mut out: i32 = 0;
-    let mut shift = 0;
-    let mut val: i64 = 0x80;
-    let mut n = 0;
-
-    while val & 0x80 != 0{
-        val = data[n..n+1][0] as i64;
-        out = out | ((val & 0x7F) << shift) as i32;
-        shift += 7;
-        n += 1
-    }
-
-    if out & (1 << 31) == 1{
-        out = out - (1 << 31)
-    }
-
-    (out, n)
-}
-
-fn packer(packet: Vec<u8>) -> Vec<u8>{
-    let mut buff = varint_write(packet.len() as i32);
-    buff.extend_from_slice(packet.as_slice());
-    buff
-}
-
-pub fn read_string(data: &Vec<u8>) -> (String, i32){
-    if data.len() == 0{
-        panic!("error, data can't be empty [pub read_string]");
-    }
-
-    let (mut size, byte_size) = varint_read(data);
-    let string = String::from_utf8_lossy(&data[byte_size..(size as usize + byte_size)]).replace("\0", "");
-    size = byte_size as i32 + size;
-    (string, size)
-}
-
-fn write_string(data: String) -> Vec<u8>{
-    let mut buff: Vec<u8> = Vec::new();
-    let size = data.as_bytes().len() as i32;
-    buff.extend_from_slice(varint_write(size).as_slice());
-    buff.extend_from_slice(data.as_bytes());
-    buff
-}
-
-// todo: find a way to automatically get the ideas for each version
-// yes i did all this by hand, took an hour
-fn entity_id_to_string(id: i32) -> String{
-    match id {
-        0 => "allay",
-        1 => "Area effect cloud",
-        2 => "Armor stand",
-        3 => "Arrow",
-        4 => "Axolotl",
-        5 => "Bat",
-        6 => "Bee",
-        7 => "Blaze",
-        8 => "Block display",
-        9 => "Boat",
-        10 => "Camel",
-        11 => "Cat",
-        12 => "Cave Spider",
-        13 => "Chest Boat",
-        14 => "Chest Minecart",
-        15 => "Chicken",
-        16 => "Cod",
-        17 => "Command Block Minecart",
-        18 => "Cow",
-        19 => "Creeper",
-        20 => "Dolphin",
-        21 => "Donkey",
-        22 => "Dragon Fireball",
-        23 => "Drowned",
-        24 => "Egg",
-        25 => "Elder Guardian",
-        26 => "End Crystal",
-        27 => "Ender Dragon",
-        28 => "Ender Pearl",
-        29 => "Enderman",
-        30 => "Endermite",
-        31 => "Evoker",
-        32 => "Evoker Fangs",
-        33 => "Experience Bottle",
-        34 => "Experience Orb",
-        35 => "Eye of Ender",
-        36 => "Falling block",
-        37 => "Firework Rocket",
-        38 => "Fox",
-        39 => "Frog",
-        40 => "Furnace Minecart",
-        41 => "Ghast",
-        42 => "Giant",
-        43 => "Glowing Item Frame", // is actually "Glow item frame" but "Glowing item frame" sounds better
-        44 => "Glow Squid",
-        45 => "Goat",
-        46 => "Guardian",
-        47 => "Hoglin",
-        48 => "Hopper Minecart",
-        49 => "Horse",
-        50 => "Husk",
-        51 => "Illusioner",
-        52 => "Interaction", // why is an interaction an entity?
-        53 => "Iron Golem",
-        54 => "Item",
-        55 => "Item Display",
-        56 => "Item Frame",
-        57 => "Fireball",
-        58 => "Leash Knot",
-        59 => "Lightning Bolt",
-        60 => "Llama",
-        61 => "Llama Spit",
-        62 => "Magma Cube",
-        63 => "Marker",
-        64 => "Minecraft",
-        65 => "Mooshroom",
-        66 => "Mule",
-        67 => "Ocelot",
-        68 => "Painting",
-        69 => "Panda", // Nice
-        70 => "Parrot",
-        71 => "Phantom",
-        72 => "Pig",
-        73 => "Piglin",
-        74 => "Piglin Brute",
-        75 => "Pillager",
-        76 => "Polar Bear",
-        77 => "Potion", // thrown potion?
-        78 => "Pufferfish",
-        79 => "Rabbit",
-        80 => "Ravager",
-        81 => "Salmon",
-        82 => "Sheep",
-        83 => "Shulker",
-        84 => "Shulker Bullet",
-        85 => "Silverfish bullet",
-        86 => "Skeleton",
-        87 => "Skeleton Horse",
-        88 => "Slime",
-        89 => "Small Fireball",
-        90 => "Sniffer",
-        91 => "Snow Golem",
-        92 => "Snowball",
-        93 => "Spawner Minecart",
-        94 => "Spectral Arrow",
-        95 => "Spider",
-        96 => "Squid",
-        97 => "Stray",
-        98 => "Strider",
-        99 => "Tadpole",
-        100 => "Text Display",
-        101 => "Tnt",
-        102 => "Tnt Minecart",
-        103 => "Trader Llama",
-        104 => "Trident",
-        105 => "Tropical Fish",
-        106 => "Turtle",
-        107 => "Vex",
-        108 => "Villager",
-        109 => "Vindicator",
-        110 => "Wandering Trader",
-        111 => "Warden",
-        112 => "Witch",
-        113 => "Wither",
-        114 => "Wither Skeleton",
-        115 => "Wither Skull",
-        116 => "Wolf",
-        117 => "Zoglin",
-        118 => "Zombie",
-        119 => "Zombie Horse",
-        120 => "Zombie Villager",
-        121 => "Zombified Piglin",
-        122 => "Player",
-        123 => "Fishing Bobber",
-        _ => "Unknown"
-    }.to_string()
+// packets we send
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextState{
+    Status,
+    Login,
 }
 
-// packets we send
+#[derive(Debug, Clone)]
+pub struct Handshake{
+    pub protocol_version: i32,
+    pub server_address: String,
+    pub server_port: u16,
+    pub next_state: NextState,
+}
+
+impl Packet for Handshake{
+    const ID: i32 = 0x0;
+}
+
+impl Encode for Handshake{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.protocol_version).as_slice()); // protocol version
+        write_string(buf, &self.server_address, 255)?; // server ip used to connect
+        buf.extend_from_slice(self.server_port.to_be_bytes().as_slice()); // server port used to connect
+
+        match self.next_state{
+            NextState::Status => buf.push(1u8),
+            NextState::Login => buf.push(2u8),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatusRequest;
+
+impl Packet for StatusRequest{
+    const ID: i32 = 0x0;
+}
+
+impl Encode for StatusRequest{
+    fn encode(&self, _buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PingRequest{
+    pub payload: i64,
+}
+
+impl Packet for PingRequest{
+    const ID: i32 = 0x1;
+}
+
+impl Encode for PingRequest{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(self.payload.to_be_bytes().as_slice());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginStart{
+    pub username: String,
+    pub uuid: Option<u128>,
+}
+
+impl Packet for LoginStart{
+    const ID: i32 = 0x0;
+}
+
+impl Encode for LoginStart{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        self.encode_for(buf, ProtocolVersion::DEFAULT)
+    }
 
-pub fn handshake(protocol: i32, server_addr: String, server_port: u16, next_state: String) -> Vec<u8>{
-    if next_state.to_lowercase() != "status" && next_state.to_lowercase() != "login"{
-        panic!("error: next_state on handshake packet must be 'status' or 'login', not: {}", next_state.to_lowercase());
+    fn encode_for(&self, buf: &mut Vec<u8>, version: ProtocolVersion) -> Result<(), ProtocolError>{
+        write_string(buf, &self.username, 16)?;
+        if version.has_configuration(){
+            // not optional anymore since 1.20.2, offline servers work out the uuid from the name anyway
+            buf.extend_from_slice(self.uuid.unwrap_or(0).to_be_bytes().as_slice());
+            return Ok(());
+        }
+        match self.uuid{
+            Some(uuid) => {
+                buf.push(1u8);
+                buf.extend_from_slice(uuid.to_be_bytes().as_slice());
+            },
+            None => buf.push(0u8),
+        }
+        Ok(())
     }
+}
 
-    let protocol_ver: Vec<u8> = varint_write(protocol);
-    let server = server_addr.as_bytes();
-    let server_size: Vec<u8> = varint_write(server.len() as i32);
-    let port = server_port.to_be_bytes();
+#[derive(Debug, Clone)]
+pub struct EncryptionResponse{
+    pub shared_secret: Vec<u8>, // both of these are RSA encrypted with the server's public key
+    pub verify_token: Vec<u8>,
+}
 
-    let mut buf: Vec<u8> = Vec::new();
-    buf.extend_from_slice(varint_write(0x0).as_slice()); // packet ID
-    buf.extend_from_slice(protocol_ver.as_slice()); // protocol version
-    buf.extend_from_slice(server_size.as_slice()); // server ip length
-    buf.extend_from_slice(server); // server ip used to connect
-    buf.extend_from_slice(port.as_slice()); // server port used to connect
+impl Packet for EncryptionResponse{
+    const ID: i32 = 0x1;
+}
 
-    if next_state.to_lowercase() == "status"{
-        buf.extend_from_slice(&[1u8]);
-    } else if next_state.to_lowercase() == "login"{
-        buf.extend_from_slice(&[2u8]);
+impl Encode for EncryptionResponse{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.shared_secret.len() as i32).as_slice());
+        buf.extend_from_slice(self.shared_secret.as_slice());
+        buf.extend_from_slice(varint_write(self.verify_token.len() as i32).as_slice());
+        buf.extend_from_slice(self.verify_token.as_slice());
+        Ok(())
     }
-    packer(buf)
 }
 
-pub fn status_request() -> Vec<u8>{
-    let mut buf: Vec<u8> = Vec::new();
-    buf.extend_from_slice(varint_write(0x0).as_slice());
-    packer(buf)
+// answer to a login plugin request, we don't understand any custom login flows so we always say no
+#[derive(Debug, Clone)]
+pub struct LoginPluginResponse{
+    pub message_id: i32,
+    pub data: Option<Vec<u8>>,
 }
 
-pub fn ping_request() -> Vec<u8>{
-    let mut buff: Vec<u8> = Vec::new();
-    let epoch_new: u64 = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-    buff.extend_from_slice(varint_write(0x1).as_slice());
-    buff.extend_from_slice(epoch_new.to_be_bytes().as_slice());
-    packer(buff)
+impl Packet for LoginPluginResponse{
+    const ID: i32 = 0x2;
 }
 
-//Todo: add uuid support by asking for a bool and Optional uuid (?) and then encode them properly
+impl Encode for LoginPluginResponse{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.message_id).as_slice());
+        match &self.data{
+            Some(data) => {
+                buf.push(1u8);
+                buf.extend_from_slice(data.as_slice());
+            },
+            None => buf.push(0u8),
+        }
+        Ok(())
+    }
+}
+
+// 1.20.2+, tells the server we got Login Success and moves us to the configuration state
+#[derive(Debug, Clone, Copy)]
+pub struct LoginAcknowledged;
+
+impl Packet for LoginAcknowledged{
+    const ID: i32 = 0x3;
+}
 
-pub fn login_start(username: String) -> Vec<u8>{
-    if username.len() > 16{
-        panic!("error: username must be less then 16 characters, size: {}", username.len());
+impl Encode for LoginAcknowledged{
+    fn encode(&self, _buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        Ok(())
     }
+}
+
+// answer to Finish Configuration, we're in the play state after sending it
+#[derive(Debug, Clone, Copy)]
+pub struct AcknowledgeFinishConfiguration;
+
+impl Packet for AcknowledgeFinishConfiguration{
+    const ID: i32 = 0x2;
+}
+
+impl Encode for AcknowledgeFinishConfiguration{
+    fn encode(&self, _buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        Ok(())
+    }
+}
+
+// answer to Start Configuration, sent in the play state but we're configuring again after it
+#[derive(Debug, Clone, Copy)]
+pub struct AcknowledgeConfiguration;
+
+impl PlayPacket for AcknowledgeConfiguration{
+    const PACKET: Serverbound = Serverbound::AcknowledgeConfiguration;
+}
+
+impl Encode for AcknowledgeConfiguration{
+    fn encode(&self, _buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourcePackResult{
+    Loaded,
+    Declined,
+    Failed,
+    Accepted,
+}
+
+// configuration state only, the uuid is the pack's (1.20.3+)
+#[derive(Debug, Clone, Copy)]
+pub struct ResourcePackResponse{
+    pub uuid: Option<u128>,
+    pub result: ResourcePackResult,
+}
 
-    let mut buff: Vec<u8> = Vec::new();
-    let user = write_string(username);
-    let has_uuid = [0u8];
+impl Packet for ResourcePackResponse{
+    const ID: i32 = 0x5;
+}
+
+impl Encode for ResourcePackResponse{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        self.encode_for(buf, ProtocolVersion::DEFAULT)
+    }
 
-    buff.extend_from_slice(varint_write(0x0).as_slice());
-    buff.extend_from_slice(user.as_slice());
-    buff.extend_from_slice(&has_uuid);
-    packer(buff)
+    fn encode_for(&self, buf: &mut Vec<u8>, version: ProtocolVersion) -> Result<(), ProtocolError>{
+        if version >= ProtocolVersion::V1_20_4{
+            buf.extend_from_slice(self.uuid.unwrap_or(0).to_be_bytes().as_slice());
+        }
+        let result = match self.result{
+            ResourcePackResult::Loaded => 0,
+            ResourcePackResult::Declined => 1,
+            ResourcePackResult::Failed => 2,
+            ResourcePackResult::Accepted => 3,
+        };
+        buf.extend_from_slice(varint_write(result).as_slice());
+        Ok(())
+    }
 }
 
 // stuff received from a server
 
-// Todo: add uuid support
-pub fn login_success(packet: &Vec<u8>) -> String{
-    let mut data = packet[16..packet.len()].to_vec(); // skips uuid
+#[derive(Debug, Clone)]
+pub struct StatusResponse{
+    pub json: String,
+}
 
-    let (username, size) = read_string(&data);
-    data = data[size as usize..data.len()].to_vec();
+impl Packet for StatusResponse{
+    const ID: i32 = 0x0;
+}
 
-    let (props, size) = varint_read(&data);
-    data = data[size..data.len() as usize].to_vec();
+impl Decode for StatusResponse{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(StatusResponse{json: reader.read_string()?})
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PingResponse{
+    pub payload: i64,
+}
 
-    for _ in 0..props{
-        let (name, size) = read_string(&data);
-        data = data[size as usize..data.len()].to_vec();
+impl Packet for PingResponse{
+    const ID: i32 = 0x1;
+}
+
+impl Decode for PingResponse{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(PingResponse{payload: reader.read_i64()?})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginDisconnect{
+    pub reason: TextComponent,
+}
+
+impl Packet for LoginDisconnect{
+    const ID: i32 = 0x0;
+}
+
+impl Decode for LoginDisconnect{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(LoginDisconnect{reason: text_from_json(reader.read_string()?)})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EncryptionRequest{
+    pub server_id: String, // always empty on vanilla servers
+    pub public_key: Vec<u8>, // DER encoded
+    pub verify_token: Vec<u8>,
+}
+
+impl Packet for EncryptionRequest{
+    const ID: i32 = 0x1;
+}
+
+impl Decode for EncryptionRequest{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(EncryptionRequest{
+            server_id: reader.read_string()?,
+            public_key: reader.read_byte_array()?.to_vec(),
+            verify_token: reader.read_byte_array()?.to_vec(),
+        })
+    }
+}
 
-        let (value, size) = read_string(&data);
-        data = data[size as usize..data.len()].to_vec();
+#[derive(Debug, Clone)]
+pub struct Property{
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
 
-        if data[0] == 0u8{
-            data = data[1..data.len()].to_vec();
-            println!("name: {}\nvalue: {}\n", name, value);
+impl Decode for Property{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let name = reader.read_string()?;
+        let value = reader.read_string()?;
+        let signature = if reader.read_bool()?{
+            Some(reader.read_string()?)
         } else {
-            data = data[1..data.len()].to_vec();
+            None
+        };
+        Ok(Property{name, value, signature})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginSuccess{
+    pub uuid: u128,
+    pub username: String,
+    pub properties: Vec<Property>,
+}
+
+impl Packet for LoginSuccess{
+    const ID: i32 = 0x2;
+}
+
+impl Decode for LoginSuccess{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let uuid = reader.read_uuid()?;
+        let username = reader.read_string()?;
+
+        let props = reader.read_varint()?;
+        let mut properties = Vec::new();
+        for _ in 0..props{
+            properties.push(reader.read()?);
+        }
+        Ok(LoginSuccess{uuid, username, properties})
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SetCompression{
+    pub threshold: i32,
+}
+
+impl Packet for SetCompression{
+    const ID: i32 = 0x3;
+}
+
+impl Decode for SetCompression{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SetCompression{threshold: reader.read_varint()?})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginPluginRequest{
+    pub message_id: i32,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+
+impl Packet for LoginPluginRequest{
+    const ID: i32 = 0x4;
+}
+
+impl Decode for LoginPluginRequest{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(LoginPluginRequest{
+            message_id: reader.read_varint()?,
+            channel: reader.read_string()?,
+            data: reader.read_remaining().to_vec(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpawnEntity{
+    pub entity_id: i32,
+    pub uuid: u128,
+    pub entity_type: i32, // registry ID in version, see kind
+    pub version: ProtocolVersion,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub head_yaw: f32,
+    pub data: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+impl SpawnEntity{
+    pub fn kind(&self) -> Option<EntityType>{
+        EntityType::from_version_id(self.version, self.entity_type as u32)
+    }
+
+    pub fn type_name(&self) -> String{
+        match self.kind(){
+            Some(kind) => kind.display_name(),
+            None => "Unknown".to_string(),
+        }
+    }
+}
+
+impl Decode for SpawnEntity{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SpawnEntity{
+            entity_id: reader.read_varint()?,
+            uuid: reader.read_uuid()?,
+            entity_type: reader.read_varint()?,
+            version: reader.version(),
+            x: reader.read_f64()?,
+            y: reader.read_f64()?,
+            z: reader.read_f64()?,
+            pitch: reader.read_angle()?,
+            yaw: reader.read_angle()?,
+            head_yaw: reader.read_angle()?,
+            data: reader.read_varint()?,
+            velocity_x: reader.read_i16()?,
+            velocity_y: reader.read_i16()?,
+            velocity_z: reader.read_i16()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpawnPlayer{
+    pub entity_id: i32,
+    pub uuid: u128,
+    pub version: ProtocolVersion,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Decode for SpawnPlayer{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SpawnPlayer{
+            entity_id: reader.read_varint()?,
+            uuid: reader.read_uuid()?,
+            version: reader.version(),
+            x: reader.read_f64()?,
+            y: reader.read_f64()?,
+            z: reader.read_f64()?,
+            yaw: reader.read_angle()?,
+            pitch: reader.read_angle()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EntityAnimation{
+    pub entity_id: i32,
+    pub animation: u8,
+}
+
+impl EntityAnimation{
+    pub fn name(&self) -> String{
+        match self.animation{
+            0 => "Swing main arm",
+            1 => "Take Damage",
+            2 => "Leave Bed",
+            3 => "Swing offhand",
+            4 => "Critical effect",
+            5 => "Magic Critical effect (?)",
+            _ => "Unknown"
+        }.to_string()
+    }
+}
+
+impl Decode for EntityAnimation{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(EntityAnimation{
+            entity_id: reader.read_varint()?,
+            animation: reader.read_u8()?,
+        })
+    }
+}
+
+// the deltas are in 1/4096 of a block, see entity::Entity::move_by
+#[derive(Debug, Clone, Copy)]
+pub struct UpdateEntityPosition{
+    pub entity_id: i32,
+    pub dx: i16,
+    pub dy: i16,
+    pub dz: i16,
+    pub on_ground: bool,
+}
+
+impl Decode for UpdateEntityPosition{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(UpdateEntityPosition{
+            entity_id: reader.read_varint()?,
+            dx: reader.read_i16()?,
+            dy: reader.read_i16()?,
+            dz: reader.read_i16()?,
+            on_ground: reader.read_bool()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UpdateEntityPositionRotation{
+    pub entity_id: i32,
+    pub dx: i16,
+    pub dy: i16,
+    pub dz: i16,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl Decode for UpdateEntityPositionRotation{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(UpdateEntityPositionRotation{
+            entity_id: reader.read_varint()?,
+            dx: reader.read_i16()?,
+            dy: reader.read_i16()?,
+            dz: reader.read_i16()?,
+            yaw: reader.read_angle()?,
+            pitch: reader.read_angle()?,
+            on_ground: reader.read_bool()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EntityRotation{
+    pub entity_id: i32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl Decode for EntityRotation{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(EntityRotation{
+            entity_id: reader.read_varint()?,
+            yaw: reader.read_angle()?,
+            pitch: reader.read_angle()?,
+            on_ground: reader.read_bool()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SetHeadRotation{
+    pub entity_id: i32,
+    pub head_yaw: f32,
+}
+
+impl Decode for SetHeadRotation{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SetHeadRotation{
+            entity_id: reader.read_varint()?,
+            head_yaw: reader.read_angle()?,
+        })
+    }
+}
+
+// used instead of Update Entity Position when the entity moved 8 blocks or more
+#[derive(Debug, Clone, Copy)]
+pub struct TeleportEntity{
+    pub entity_id: i32,
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl Decode for TeleportEntity{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(TeleportEntity{
+            entity_id: reader.read_varint()?,
+            position: reader.read()?,
+            yaw: reader.read_angle()?,
+            pitch: reader.read_angle()?,
+            on_ground: reader.read_bool()?,
+        })
+    }
+}
+
+// velocity in 1/8000 of a block per tick
+#[derive(Debug, Clone, Copy)]
+pub struct SetEntityVelocity{
+    pub entity_id: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+impl Decode for SetEntityVelocity{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SetEntityVelocity{
+            entity_id: reader.read_varint()?,
+            velocity_x: reader.read_i16()?,
+            velocity_y: reader.read_i16()?,
+            velocity_z: reader.read_i16()?,
+        })
+    }
+}
+
+// entity_metadata.json says which index is which field for each entity type. decoding stops at
+// the first value we can't read (an unknown serializer, or a particle type the version's registry
+// doesn't have), entries before it are kept and the rest of the packet is skipped
+#[derive(Debug, Clone)]
+pub struct SetEntityMetadata{
+    pub entity_id: i32,
+    pub entries: Vec<(u8, MetadataValue)>,
+}
+
+impl Decode for SetEntityMetadata{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let entity_id = reader.read_varint()?;
+        let mut entries = Vec::new();
+        loop{
+            let index = reader.read_u8()?;
+            if index == 0xFF{
+                break;
+            }
+            let value = match MetadataKind::from_id(reader.read_varint()?){
+                Some(kind) => MetadataValue::read(reader, kind)?,
+                None => None,
+            };
+            match value{
+                Some(value) => entries.push((index, value)),
+                None => {
+                    reader.read_remaining();
+                    break;
+                },
+            }
+        }
+        Ok(SetEntityMetadata{entity_id, entries})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoveEntities{
+    pub entity_ids: Vec<i32>,
+}
+
+impl Decode for RemoveEntities{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let count = reader.read_varint()?;
+        let mut entity_ids = Vec::new();
+        for _ in 0..count{
+            entity_ids.push(reader.read_varint()?);
+        }
+        Ok(RemoveEntities{entity_ids})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayDisconnect{
+    pub reason: TextComponent,
+}
+
+impl Decode for PlayDisconnect{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(PlayDisconnect{reason: read_text(reader)?})
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemChat{
+    pub content: TextComponent,
+    pub overlay: bool, // shown above the hotbar instead of in the chat box
+}
+
+impl Decode for SystemChat{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SystemChat{
+            content: read_text(reader)?,
+            overlay: reader.read_bool()?,
+        })
+    }
+}
+
+// a message signature, or the index of one the server sent before (see Player Chat)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviousMessage{
+    Id(i32),
+    Signature(Box<[u8; 256]>),
+}
+
+// what the server's chat filter did to a player message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterMask{
+    PassThrough,
+    FullyFiltered,
+    PartiallyFiltered(Vec<i64>), // bit set, one bit per character that got hidden
+}
+
+// a message a player sent. signature and previous_messages are only there with secure chat,
+// unsigned_content is the message after the server (a plugin) changed it
+#[derive(Debug, Clone)]
+pub struct PlayerChat{
+    pub sender: u128,
+    pub index: i32, // how many messages this sender sent in this session before
+    pub signature: Option<Box<[u8; 256]>>,
+    pub message: String,
+    pub timestamp: i64, // milliseconds since the epoch
+    pub salt: i64,
+    pub previous_messages: Vec<PreviousMessage>, // the last seen messages the signature covers
+    pub unsigned_content: Option<TextComponent>,
+    pub filter: FilterMask,
+    pub chat_type: i32, // index in the minecraft:chat_type registry
+    pub sender_name: TextComponent,
+    pub target_name: Option<TextComponent>,
+}
+
+impl Decode for PlayerChat{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let sender = reader.read_uuid()?;
+        let index = reader.read_varint()?;
+        let signature = if reader.read_bool()?{ Some(Box::new(reader.read_array()?)) } else { None };
+        let message = reader.read_string()?;
+        let timestamp = reader.read_i64()?;
+        let salt = reader.read_i64()?;
+
+        let count = reader.read_varint()?;
+        if !(0..=20).contains(&count){
+            return Err(ProtocolError::InvalidValue(format!("{} previous messages in player chat, at most 20 are allowed", count)));
+        }
+        let mut previous_messages = Vec::new();
+        for _ in 0..count{
+            // the ID is one higher than the index into the messages the server sent, 0 means the signature follows
+            previous_messages.push(match reader.read_varint()?{
+                0 => PreviousMessage::Signature(Box::new(reader.read_array()?)),
+                id => PreviousMessage::Id(id - 1),
+            });
+        }
+
+        let unsigned_content = if reader.read_bool()?{ Some(read_text(reader)?) } else { None };
+        let filter = match reader.read_varint()?{
+            0 => FilterMask::PassThrough,
+            1 => FilterMask::FullyFiltered,
+            2 => {
+                let length = reader.read_varint()?;
+                if length < 0{
+                    return Err(ProtocolError::NegativeLength(length));
+                }
+                let mut bits = Vec::new();
+                for _ in 0..length{
+                    bits.push(reader.read_i64()?);
+                }
+                FilterMask::PartiallyFiltered(bits)
+            },
+            other => return Err(ProtocolError::InvalidValue(format!("filter type {}", other))),
+        };
+        Ok(PlayerChat{
+            sender,
+            index,
+            signature,
+            message,
+            timestamp,
+            salt,
+            previous_messages,
+            unsigned_content,
+            filter,
+            chat_type: reader.read_varint()?,
+            sender_name: read_text(reader)?,
+            target_name: if reader.read_bool()?{ Some(read_text(reader)?) } else { None },
+        })
+    }
+}
+
+// a message from something that isn't a player, like /say from the console
+#[derive(Debug, Clone)]
+pub struct DisguisedChat{
+    pub message: TextComponent,
+    pub chat_type: i32,
+    pub sender_name: TextComponent,
+    pub target_name: Option<TextComponent>,
+}
+
+impl Decode for DisguisedChat{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(DisguisedChat{
+            message: read_text(reader)?,
+            chat_type: reader.read_varint()?,
+            sender_name: read_text(reader)?,
+            target_name: if reader.read_bool()?{ Some(read_text(reader)?) } else { None },
+        })
+    }
+}
+
+// message_count and acknowledged come from chat::LastSeenMessages::update, signature stays None
+// without a chat session (servers with enforce-secure-profile=false take that)
+#[derive(Debug, Clone)]
+pub struct ChatMessage{
+    pub message: String,
+    pub timestamp: i64,
+    pub salt: i64,
+    pub signature: Option<Box<[u8; 256]>>,
+    pub message_count: i32,
+    pub acknowledged: [u8; 3], // a bit for each of the last 20 messages, see chat::LastSeenMessages
+}
+
+impl PlayPacket for ChatMessage{
+    const PACKET: Serverbound = Serverbound::ChatMessage;
+}
+
+impl Encode for ChatMessage{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        write_string(buf, &self.message, 256)?;
+        buf.extend_from_slice(self.timestamp.to_be_bytes().as_slice());
+        buf.extend_from_slice(self.salt.to_be_bytes().as_slice());
+        buf.push(self.signature.is_some() as u8);
+        if let Some(signature) = &self.signature{
+            buf.extend_from_slice(signature.as_slice());
+        }
+        buf.extend_from_slice(varint_write(self.message_count).as_slice());
+        buf.extend_from_slice(self.acknowledged.as_slice());
+        Ok(())
+    }
+}
+
+// a command without the leading slash
+#[derive(Debug, Clone)]
+pub struct ChatCommand{
+    pub command: String,
+    pub timestamp: i64,
+    pub salt: i64,
+    pub argument_signatures: Vec<(String, Box<[u8; 256]>)>,
+    pub message_count: i32,
+    pub acknowledged: [u8; 3],
+}
+
+impl PlayPacket for ChatCommand{
+    const PACKET: Serverbound = Serverbound::ChatCommand;
+}
+
+impl Encode for ChatCommand{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        write_string(buf, &self.command, 256)?;
+        buf.extend_from_slice(self.timestamp.to_be_bytes().as_slice());
+        buf.extend_from_slice(self.salt.to_be_bytes().as_slice());
+        buf.extend_from_slice(varint_write(self.argument_signatures.len() as i32).as_slice());
+        for (argument, signature) in &self.argument_signatures{
+            write_string(buf, argument, 16)?;
+            buf.extend_from_slice(signature.as_slice());
+        }
+        buf.extend_from_slice(varint_write(self.message_count).as_slice());
+        buf.extend_from_slice(self.acknowledged.as_slice());
+        Ok(())
+    }
+}
+
+// tells the server we saw message_count more messages, so it doesn't think we fell behind
+#[derive(Debug, Clone, Copy)]
+pub struct MessageAcknowledgment{
+    pub message_count: i32,
+}
+
+impl PlayPacket for MessageAcknowledgment{
+    const PACKET: Serverbound = Serverbound::MessageAcknowledgment;
+}
+
+impl Encode for MessageAcknowledgment{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.message_count).as_slice());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeepAlive{
+    pub id: i64,
+}
 
-            let (sig, size) = read_string(&data);
-            data = data[size as usize..data.len()].to_vec();
+impl Decode for KeepAlive{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(KeepAlive{id: reader.read_i64()?})
+    }
+}
+
+// has to echo the id of the last KeepAlive or the server kicks us after about 20 seconds
+#[derive(Debug, Clone, Copy)]
+pub struct KeepAliveResponse{
+    pub id: i64,
+}
+
+impl PlayPacket for KeepAliveResponse{
+    const PACKET: Serverbound = Serverbound::KeepAlive;
+}
+
+// the ID in the configuration state
+impl Packet for KeepAliveResponse{
+    const ID: i32 = 0x3;
+}
+
+impl Encode for KeepAliveResponse{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(self.id.to_be_bytes().as_slice());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlayPing{
+    pub id: i32,
+}
+
+impl Decode for PlayPing{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(PlayPing{id: reader.read_i32()?})
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PlayPong{
+    pub id: i32,
+}
+
+impl PlayPacket for PlayPong{
+    const PACKET: Serverbound = Serverbound::Pong;
+}
+
+// the ID in the configuration state
+impl Packet for PlayPong{
+    const ID: i32 = 0x4;
+}
+
+impl Encode for PlayPong{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(self.id.to_be_bytes().as_slice());
+        Ok(())
+    }
+}
+
+// 1.20.2+, the server sends chunks in batches and waits for this before it sends more than a few
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkBatchFinished{
+    pub batch_size: i32,
+}
+
+impl Decode for ChunkBatchFinished{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(ChunkBatchFinished{batch_size: reader.read_varint()?})
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkBatchReceived{
+    pub chunks_per_tick: f32, // how fast we'd like the next batches
+}
+
+impl PlayPacket for ChunkBatchReceived{
+    const PACKET: Serverbound = Serverbound::ChunkBatchReceived;
+}
+
+impl Encode for ChunkBatchReceived{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(self.chunks_per_tick.to_be_bytes().as_slice());
+        Ok(())
+    }
+}
+
+// which of the fields of Synchronize Player Position are relative to where we are now
+pub const RELATIVE_X: u8 = 0x01;
+pub const RELATIVE_Y: u8 = 0x02;
+pub const RELATIVE_Z: u8 = 0x04;
+pub const RELATIVE_YAW: u8 = 0x08;
+pub const RELATIVE_PITCH: u8 = 0x10;
+
+// the server putting us somewhere, has to be answered with Confirm Teleportation
+#[derive(Debug, Clone, Copy)]
+pub struct SynchronizePlayerPosition{
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub relative: u8, // RELATIVE_* bits
+    pub teleport_id: i32,
+}
+
+impl SynchronizePlayerPosition{
+    // where we end up when we were at position looking at yaw / pitch
+    pub fn apply(&self, position: Vec3, yaw: f32, pitch: f32) -> (Vec3, f32, f32){
+        let pick = |bit: u8, current: f64, new: f64| if self.relative & bit != 0{ current + new } else { new };
+        let position = Vec3::new(
+            pick(RELATIVE_X, position.x, self.position.x),
+            pick(RELATIVE_Y, position.y, self.position.y),
+            pick(RELATIVE_Z, position.z, self.position.z),
+        );
+        let yaw = pick(RELATIVE_YAW, yaw as f64, self.yaw as f64) as f32;
+        let pitch = pick(RELATIVE_PITCH, pitch as f64, self.pitch as f64) as f32;
+        (position, yaw, pitch)
+    }
+}
+
+impl Decode for SynchronizePlayerPosition{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(SynchronizePlayerPosition{
+            position: reader.read()?,
+            yaw: reader.read_f32()?,
+            pitch: reader.read_f32()?,
+            relative: reader.read_u8()?,
+            teleport_id: reader.read_varint()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConfirmTeleportation{
+    pub teleport_id: i32,
+}
+
+impl PlayPacket for ConfirmTeleportation{
+    const PACKET: Serverbound = Serverbound::ConfirmTeleportation;
+}
+
+impl Encode for ConfirmTeleportation{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend(varint_write(self.teleport_id));
+        Ok(())
+    }
+}
+
+// position is where our feet are
+#[derive(Debug, Clone, Copy)]
+pub struct SetPlayerPosition{
+    pub position: Vec3,
+    pub on_ground: bool,
+}
+
+impl PlayPacket for SetPlayerPosition{
+    const PACKET: Serverbound = Serverbound::SetPlayerPosition;
+}
+
+impl Encode for SetPlayerPosition{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        self.position.encode(buf)?;
+        buf.push(self.on_ground as u8);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SetPlayerPositionRotation{
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl PlayPacket for SetPlayerPositionRotation{
+    const PACKET: Serverbound = Serverbound::SetPlayerPositionRotation;
+}
+
+impl Encode for SetPlayerPositionRotation{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        self.position.encode(buf)?;
+        buf.extend_from_slice(self.yaw.to_be_bytes().as_slice());
+        buf.extend_from_slice(self.pitch.to_be_bytes().as_slice());
+        buf.push(self.on_ground as u8);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SetPlayerRotation{
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+impl PlayPacket for SetPlayerRotation{
+    const PACKET: Serverbound = Serverbound::SetPlayerRotation;
+}
+
+impl Encode for SetPlayerRotation{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(self.yaw.to_be_bytes().as_slice());
+        buf.extend_from_slice(self.pitch.to_be_bytes().as_slice());
+        buf.push(self.on_ground as u8);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SetPlayerOnGround{
+    pub on_ground: bool,
+}
+
+impl PlayPacket for SetPlayerOnGround{
+    const PACKET: Serverbound = Serverbound::SetPlayerOnGround;
+}
+
+impl Encode for SetPlayerOnGround{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.push(self.on_ground as u8);
+        Ok(())
+    }
+}
+
+// the side of a block we dig at or place against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFace{
+    Down,
+    Up,
+    North, // -z
+    South, // +z
+    West, // -x
+    East, // +x
+}
+
+impl BlockFace{
+    pub fn id(self) -> u8{
+        self as u8
+    }
+
+    // the block on this side of position
+    pub fn offset(self, position: BlockPos) -> BlockPos{
+        let BlockPos{x, y, z} = position;
+        match self{
+            BlockFace::Down => BlockPos::new(x, y - 1, z),
+            BlockFace::Up => BlockPos::new(x, y + 1, z),
+            BlockFace::North => BlockPos::new(x, y, z - 1),
+            BlockFace::South => BlockPos::new(x, y, z + 1),
+            BlockFace::West => BlockPos::new(x - 1, y, z),
+            BlockFace::East => BlockPos::new(x + 1, y, z),
+        }
+    }
+}
+
+// Player Action statuses
+pub const START_DIGGING: i32 = 0;
+pub const CANCEL_DIGGING: i32 = 1;
+pub const FINISH_DIGGING: i32 = 2;
+
+// sequence comes from World::predict_block, the server acknowledges it with Acknowledge Block Change
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerAction{
+    pub status: i32,
+    pub position: BlockPos,
+    pub face: BlockFace,
+    pub sequence: i32,
+}
+
+impl PlayPacket for PlayerAction{
+    const PACKET: Serverbound = Serverbound::PlayerAction;
+}
+
+impl Encode for PlayerAction{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.status).as_slice());
+        self.position.encode(buf)?;
+        buf.push(self.face.id());
+        buf.extend_from_slice(varint_write(self.sequence).as_slice());
+        Ok(())
+    }
+}
 
-            println!("name: {}\nvalue: {}\nsignature: {}\n", name, value, sig);
+// right click on a block with whatever is in the hand, cursor is where on the face (0 to 1)
+#[derive(Debug, Clone, Copy)]
+pub struct UseItemOn{
+    pub hand: i32, // 0 main hand, 1 off hand
+    pub position: BlockPos,
+    pub face: BlockFace,
+    pub cursor: [f32; 3],
+    pub inside_block: bool,
+    pub sequence: i32,
+}
+
+impl PlayPacket for UseItemOn{
+    const PACKET: Serverbound = Serverbound::UseItemOn;
+}
+
+impl Encode for UseItemOn{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.hand).as_slice());
+        self.position.encode(buf)?;
+        buf.extend_from_slice(varint_write(self.face.id() as i32).as_slice());
+        for value in self.cursor{
+            buf.extend_from_slice(value.to_be_bytes().as_slice());
         }
+        buf.push(self.inside_block as u8);
+        buf.extend_from_slice(varint_write(self.sequence).as_slice());
+        Ok(())
     }
-    username
 }
 
-pub fn compression_request(packet: &Vec<u8>) -> i32{
-    let (i, _) = varint_read(packet);
-    i
+#[derive(Debug, Clone, Copy)]
+pub struct SwingArm{
+    pub hand: i32,
 }
 
-pub fn login_disconnect(packet: &Vec<u8>) -> String{
-    if packet.len() == 0{
-        panic!("error, data can't be empty [pub login_disconnect]");
+impl PlayPacket for SwingArm{
+    const PACKET: Serverbound = Serverbound::SwingArm;
+}
+
+impl Encode for SwingArm{
+    fn encode(&self, buf: &mut Vec<u8>) -> Result<(), ProtocolError>{
+        buf.extend_from_slice(varint_write(self.hand).as_slice());
+        Ok(())
     }
+}
 
-    let (json_data, _) = read_string(packet);
-    json_data
+// the chat signing key a player uses, only there on servers with secure chat
+#[derive(Debug, Clone)]
+pub struct ChatSession{
+    pub session_id: u128,
+    pub expires_at: i64,
+    pub public_key: Vec<u8>,
+    pub key_signature: Vec<u8>,
 }
 
-pub fn status_response(packet: &Vec<u8>) -> String{
-    if packet.len() == 0{
-        panic!("error, data can't be empty [pub status_response]");
+// one player's entry in Player Info Update, only the fields that the packet's actions
+// cover are filled in, the rest stay None
+#[derive(Debug, Clone, Default)]
+pub struct PlayerInfoEntry{
+    pub uuid: u128,
+    pub name: Option<String>,
+    pub properties: Option<Vec<Property>>,
+    pub chat_session: Option<Option<ChatSession>>,
+    pub game_mode: Option<i32>,
+    pub listed: Option<bool>,
+    pub latency: Option<i32>, // milliseconds, measured by the server through keep alives
+    pub display_name: Option<Option<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerInfoUpdate{
+    pub actions: u8,
+    pub entries: Vec<PlayerInfoEntry>,
+}
+
+impl PlayerInfoUpdate{
+    pub const ADD_PLAYER: u8 = 0x01;
+    pub const INITIALIZE_CHAT: u8 = 0x02;
+    pub const UPDATE_GAME_MODE: u8 = 0x04;
+    pub const UPDATE_LISTED: u8 = 0x08;
+    pub const UPDATE_LATENCY: u8 = 0x10;
+    pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
+}
+
+impl Decode for PlayerInfoUpdate{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let actions = reader.read_u8()?;
+        let count = reader.read_varint()?;
+
+        let mut entries = Vec::new();
+        for _ in 0..count{
+            let mut entry = PlayerInfoEntry{uuid: reader.read_uuid()?, ..Default::default()};
+
+            if actions & PlayerInfoUpdate::ADD_PLAYER != 0{
+                entry.name = Some(reader.read_string()?);
+                let props = reader.read_varint()?;
+                let mut properties = Vec::new();
+                for _ in 0..props{
+                    properties.push(reader.read()?);
+                }
+                entry.properties = Some(properties);
+            }
+            if actions & PlayerInfoUpdate::INITIALIZE_CHAT != 0{
+                entry.chat_session = Some(if reader.read_bool()?{
+                    Some(ChatSession{
+                        session_id: reader.read_uuid()?,
+                        expires_at: reader.read_i64()?,
+                        public_key: reader.read_byte_array()?.to_vec(),
+                        key_signature: reader.read_byte_array()?.to_vec(),
+                    })
+                } else {
+                    None
+                });
+            }
+            if actions & PlayerInfoUpdate::UPDATE_GAME_MODE != 0{
+                entry.game_mode = Some(reader.read_varint()?);
+            }
+            if actions & PlayerInfoUpdate::UPDATE_LISTED != 0{
+                entry.listed = Some(reader.read_bool()?);
+            }
+            if actions & PlayerInfoUpdate::UPDATE_LATENCY != 0{
+                entry.latency = Some(reader.read_varint()?);
+            }
+            if actions & PlayerInfoUpdate::UPDATE_DISPLAY_NAME != 0{
+                entry.display_name = Some(if reader.read_bool()?{
+                    Some(read_chat(reader)?)
+                } else {
+                    None
+                });
+            }
+            entries.push(entry);
+        }
+        Ok(PlayerInfoUpdate{actions, entries})
     }
+}
 
-    let (json_data, _) = read_string(packet);
-    json_data
+#[derive(Debug, Clone)]
+pub struct PlayerInfoRemove{
+    pub uuids: Vec<u128>,
 }
 
-pub fn ping_response(packet: &Vec<u8>) -> String{
-    if packet.len() != 8{
-        panic!("error, invalid length packet\nexpected: 8\nreceived: {}", packet.len());
+impl Decode for PlayerInfoRemove{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let count = reader.read_varint()?;
+        let mut uuids = Vec::new();
+        for _ in 0..count{
+            uuids.push(reader.read_uuid()?);
+        }
+        Ok(PlayerInfoRemove{uuids})
     }
+}
 
-    let now: u64 = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-    let epoch = u64::from_be_bytes(packet.as_slice().try_into().unwrap());
+// where the player last died, if they ever did
+#[derive(Debug, Clone)]
+pub struct DeathLocation{
+    pub dimension: String,
+    pub position: BlockPos,
+}
 
-    if now == epoch {
-        format!("ping <1 second")
+// Login (play), the first packet after Login Success
+#[derive(Debug, Clone)]
+pub struct JoinGame{
+    pub entity_id: i32,
+    pub hardcore: bool,
+    pub game_mode: u8,
+    pub previous_game_mode: i8, // -1 if there is none
+    pub dimension_names: Vec<String>,
+    pub registry_codec: Option<Tag>, // None since 1.20.2, it comes in the configuration state there
+    pub dimension_type: String,
+    pub dimension_name: String,
+    pub hashed_seed: i64,
+    pub max_players: i32,
+    pub view_distance: i32,
+    pub simulation_distance: i32,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub do_limited_crafting: bool, // 1.20.2+
+    pub debug: bool,
+    pub flat: bool,
+    pub death_location: Option<DeathLocation>,
+    pub portal_cooldown: i32, // 1.20+
+}
+
+impl Decode for JoinGame{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        if reader.version().has_configuration(){
+            return JoinGame::decode_1_20_2(reader);
+        }
+
+        let entity_id = reader.read_i32()?;
+        let hardcore = reader.read_bool()?;
+        let game_mode = reader.read_u8()?;
+        let previous_game_mode = reader.read_i8()?;
+        let dimension_names = read_identifiers(reader)?;
+
+        Ok(JoinGame{
+            entity_id,
+            hardcore,
+            game_mode,
+            previous_game_mode,
+            dimension_names,
+            registry_codec: match nbt::read_named(reader)?{
+                Some((_, tag)) => Some(tag),
+                None => return Err(ProtocolError::InvalidValue("Login (play) without a registry codec".to_string())),
+            },
+            dimension_type: reader.read_string()?,
+            dimension_name: reader.read_string()?,
+            hashed_seed: reader.read_i64()?,
+            max_players: reader.read_varint()?,
+            view_distance: reader.read_varint()?,
+            simulation_distance: reader.read_varint()?,
+            reduced_debug_info: reader.read_bool()?,
+            enable_respawn_screen: reader.read_bool()?,
+            do_limited_crafting: false,
+            debug: reader.read_bool()?,
+            flat: reader.read_bool()?,
+            death_location: read_death_location(reader)?,
+            portal_cooldown: if reader.version() >= ProtocolVersion::V1_20_1{ reader.read_varint()? } else { 0 },
+        })
+    }
+}
+
+impl JoinGame{
+    // the registry codec moved out and the spawn info (shared with Respawn) moved to the end
+    fn decode_1_20_2(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(JoinGame{
+            entity_id: reader.read_i32()?,
+            hardcore: reader.read_bool()?,
+            dimension_names: read_identifiers(reader)?,
+            registry_codec: None,
+            max_players: reader.read_varint()?,
+            view_distance: reader.read_varint()?,
+            simulation_distance: reader.read_varint()?,
+            reduced_debug_info: reader.read_bool()?,
+            enable_respawn_screen: reader.read_bool()?,
+            do_limited_crafting: reader.read_bool()?,
+            dimension_type: reader.read_string()?,
+            dimension_name: reader.read_string()?,
+            hashed_seed: reader.read_i64()?,
+            game_mode: reader.read_u8()?,
+            previous_game_mode: reader.read_i8()?,
+            debug: reader.read_bool()?,
+            flat: reader.read_bool()?,
+            death_location: read_death_location(reader)?,
+            portal_cooldown: reader.read_varint()?,
+        })
+    }
+}
+
+fn read_identifiers(reader: &mut PacketReader<'_>) -> Result<Vec<String>, ProtocolError>{
+    let count = reader.read_varint()?;
+    let mut identifiers = Vec::new();
+    for _ in 0..count{
+        identifiers.push(reader.read_string()?);
+    }
+    Ok(identifiers)
+}
+
+fn read_death_location(reader: &mut PacketReader<'_>) -> Result<Option<DeathLocation>, ProtocolError>{
+    Ok(if reader.read_bool()?{
+        Some(DeathLocation{dimension: reader.read_string()?, position: reader.read()?})
     } else {
-        format!("ping: {}", now-epoch)
+        None
+    })
+}
+
+// nbt inside a packet, the root has an (empty) name before 1.20.2
+pub(crate) fn read_network_nbt(reader: &mut PacketReader<'_>) -> Result<Option<Tag>, ProtocolError>{
+    if reader.version().nameless_nbt(){
+        nbt::read_nameless(reader)
+    } else {
+        Ok(nbt::read_named(reader)?.map(|(_, tag)| tag))
     }
 }
 
-pub fn spawn_entity(packet: &Vec<u8>) -> (i32, String, (f64, f64, f64, i8, i8, i8), i32, (i16, i16, i16)){
-    let mut data = packet;
+// a chat component, always handed out as json. since 1.20.3 it's sent as nbt, which gets
+// turned into the json it stands for (bools come out as 0 / 1 bytes)
+pub(crate) fn read_chat(reader: &mut PacketReader<'_>) -> Result<String, ProtocolError>{
+    if !reader.version().nbt_chat(){
+        return reader.read_string();
+    }
+    match nbt::read_nameless(reader)?{
+        Some(tag) => serde_json::to_string(&tag).map_err(|err| ProtocolError::InvalidValue(format!("chat component: {}", err))),
+        None => Ok(String::new()),
+    }
+}
+
+// a chat component parsed, for the packets that are mostly read by people. one that doesn't
+// parse is shown as it came in rather than failing the packet
+pub(crate) fn read_text(reader: &mut PacketReader<'_>) -> Result<TextComponent, ProtocolError>{
+    if !reader.version().nbt_chat(){
+        return Ok(text_from_json(reader.read_string()?));
+    }
+    match nbt::read_nameless(reader)?{
+        Some(tag) => Ok(TextComponent::from_nbt(&tag).unwrap_or_else(|_| TextComponent::text(serde_json::to_string(&tag).unwrap_or_default()))),
+        None => Ok(TextComponent::default()),
+    }
+}
+
+fn text_from_json(json: String) -> TextComponent{
+    TextComponent::from_json(&json).unwrap_or_else(|_| TextComponent::text(json))
+}
 
-    let (entity_id, size) = varint_read(data);
-    data = &data[size..data.len()].to_vec();
+// light for the sections of one chunk column. bit i of a mask is section i - 1, counting
+// from the section below the bottom of the world, arrays are 2048 bytes of 4 bit values
+#[derive(Debug, Clone, Default)]
+pub struct LightData{
+    pub sky_light_mask: Vec<i64>,
+    pub block_light_mask: Vec<i64>,
+    pub empty_sky_light_mask: Vec<i64>,
+    pub empty_block_light_mask: Vec<i64>,
+    pub sky_light: Vec<Vec<u8>>,
+    pub block_light: Vec<Vec<u8>>,
+}
+
+impl Decode for LightData{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        if reader.version() < ProtocolVersion::V1_20_1{
+            reader.read_bool()?; // trust edges, removed in 1.20
+        }
+        Ok(LightData{
+            sky_light_mask: read_bit_set(reader)?,
+            block_light_mask: read_bit_set(reader)?,
+            empty_sky_light_mask: read_bit_set(reader)?,
+            empty_block_light_mask: read_bit_set(reader)?,
+            sky_light: read_light_arrays(reader)?,
+            block_light: read_light_arrays(reader)?,
+        })
+    }
+}
+
+fn read_bit_set(reader: &mut PacketReader<'_>) -> Result<Vec<i64>, ProtocolError>{
+    let length = reader.read_varint()?;
+    if length < 0{
+        return Err(ProtocolError::NegativeLength(length));
+    }
+    let mut longs = Vec::with_capacity((length as usize).min(reader.remaining() / 8));
+    for _ in 0..length{
+        longs.push(reader.read_i64()?);
+    }
+    Ok(longs)
+}
 
-    // todo: add uuid support
-    data = &data[16..data.len()].to_vec();
+fn read_light_arrays(reader: &mut PacketReader<'_>) -> Result<Vec<Vec<u8>>, ProtocolError>{
+    let count = reader.read_varint()?;
+    let mut arrays = Vec::new();
+    for _ in 0..count{
+        arrays.push(reader.read_byte_array()?.to_vec());
+    }
+    Ok(arrays)
+}
 
-    let (entity_type_raw, size) = varint_read(data);
-    let entity_type = entity_id_to_string(entity_type_raw);
-    data = &data[size..data.len()].to_vec();
+// a block entity that came with a chunk, x and z are relative to the chunk
+#[derive(Debug, Clone)]
+pub struct ChunkBlockEntity{
+    pub x: u8,
+    pub y: i16,
+    pub z: u8,
+    pub kind: i32,
+    pub data: Option<Tag>,
+}
 
-    let x = f64::from_be_bytes(data[0..8].try_into().unwrap());
-    let y = f64::from_be_bytes(data[8..16].try_into().unwrap());
-    let z = f64::from_be_bytes(data[16..24].try_into().unwrap());
-    data = &data[24..data.len()].to_vec();
+// Chunk Data and Update Light, data holds the chunk sections as sent, see world::Chunk
+#[derive(Debug, Clone)]
+pub struct ChunkData{
+    pub x: i32,
+    pub z: i32,
+    pub heightmaps: Option<Tag>,
+    pub data: Vec<u8>,
+    pub block_entities: Vec<ChunkBlockEntity>,
+    pub light: LightData,
+}
 
+impl Decode for ChunkData{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let x = reader.read_i32()?;
+        let z = reader.read_i32()?;
+        let heightmaps = read_network_nbt(reader)?;
+        let data = reader.read_byte_array()?.to_vec();
+
+        let count = reader.read_varint()?;
+        let mut block_entities = Vec::new();
+        for _ in 0..count{
+            let xz = reader.read_u8()?;
+            block_entities.push(ChunkBlockEntity{
+                x: xz >> 4,
+                z: xz & 0x0F,
+                y: reader.read_i16()?,
+                kind: reader.read_varint()?,
+                data: read_network_nbt(reader)?,
+            });
+        }
 
-    let pitch = data[0..1][0] as i8;
-    data = &data[1..data.len()].to_vec();
+        Ok(ChunkData{x, z, heightmaps, data, block_entities, light: reader.read()?})
+    }
+}
 
-    let yaw = data[0..1][0] as i8;
-    data = &data[1..data.len()].to_vec();
+#[derive(Debug, Clone)]
+pub struct UpdateLight{
+    pub x: i32,
+    pub z: i32,
+    pub light: LightData,
+}
 
-    let head_yaw = data[0..1][0] as i8;
-    data = &data[1..data.len()].to_vec();
+impl Decode for UpdateLight{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(UpdateLight{
+            x: reader.read_varint()?,
+            z: reader.read_varint()?,
+            light: reader.read()?,
+        })
+    }
+}
 
-    let (entity_data, size) = varint_read(&data);
-    data = &data[size..data.len()].to_vec();
+#[derive(Debug, Clone)]
+pub struct UnloadChunk{
+    pub x: i32,
+    pub z: i32,
+}
 
-    let velocity_x = i16::from_be_bytes(data[0..2].try_into().unwrap());
-    let velocity_y = i16::from_be_bytes(data[2..4].try_into().unwrap());
-    let velocity_z = i16::from_be_bytes(data[4..6].try_into().unwrap());
+impl Decode for UnloadChunk{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        if reader.version() >= ProtocolVersion::V1_20_2{
+            // z comes first since 1.20.2
+            let z = reader.read_i32()?;
+            return Ok(UnloadChunk{x: reader.read_i32()?, z});
+        }
+        Ok(UnloadChunk{
+            x: reader.read_i32()?,
+            z: reader.read_i32()?,
+        })
+    }
+}
 
-    (entity_id, entity_type, (x, y, z, pitch, yaw, head_yaw), entity_data, (velocity_x, velocity_y, velocity_z))
+// sent on death and when changing dimension
+#[derive(Debug, Clone)]
+pub struct Respawn{
+    pub dimension_type: String,
+    pub dimension_name: String,
+    pub hashed_seed: i64,
+    pub game_mode: u8,
+    pub previous_game_mode: i8,
+    pub debug: bool,
+    pub flat: bool,
+    pub data_kept: u8,
+    pub death_location: Option<DeathLocation>,
+    pub portal_cooldown: i32,
 }
 
-// todo: add uuid support
-pub fn spawn_player(packet: &Vec<u8>) -> (i32, (f64, f64, f64), (i8, i8)){
-    let mut data = packet;
+impl Decode for Respawn{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let dimension_type = reader.read_string()?;
+        let dimension_name = reader.read_string()?;
+        let hashed_seed = reader.read_i64()?;
+        let game_mode = reader.read_u8()?;
+        let previous_game_mode = reader.read_i8()?;
+        let debug = reader.read_bool()?;
+        let flat = reader.read_bool()?;
+
+        // data kept moved behind the death location and portal cooldown in 1.20.2
+        let (data_kept, death_location, portal_cooldown) = match reader.version(){
+            ProtocolVersion::V1_19_4 => (reader.read_u8()?, read_death_location(reader)?, 0),
+            ProtocolVersion::V1_20_1 => (reader.read_u8()?, read_death_location(reader)?, reader.read_varint()?),
+            _ => {
+                let death_location = read_death_location(reader)?;
+                let portal_cooldown = reader.read_varint()?;
+                (reader.read_u8()?, death_location, portal_cooldown)
+            },
+        };
+
+        Ok(Respawn{dimension_type, dimension_name, hashed_seed, game_mode, previous_game_mode, debug, flat, data_kept, death_location, portal_cooldown})
+    }
+}
 
-    let (entity_id, size) = varint_read(&data);
-    data = &data[size..data.len()].to_vec();
+#[derive(Debug, Clone)]
+pub struct BlockUpdate{
+    pub position: BlockPos,
+    pub state: u32,
+}
 
-    data = &data[16..data.len()].to_vec();
+impl Decode for BlockUpdate{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(BlockUpdate{
+            position: reader.read()?,
+            state: reader.read_varint()? as u32,
+        })
+    }
+}
 
-    let x = f64::from_be_bytes(data[0..8].try_into().unwrap());
-    let y = f64::from_be_bytes(data[8..16].try_into().unwrap());
-    let z = f64::from_be_bytes(data[16..24].try_into().unwrap());
-    data = &data[24..data.len()].to_vec();
+// several block changes in one chunk section, already turned into world coordinates
+#[derive(Debug, Clone)]
+pub struct UpdateSectionBlocks{
+    pub section_x: i32,
+    pub section_y: i32,
+    pub section_z: i32,
+    pub blocks: Vec<(BlockPos, u32)>,
+}
 
-    let yaw = data[0..1][0] as i8;
-    data = &data[1..data.len()].to_vec();
+impl Decode for UpdateSectionBlocks{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        // x (22 bits), z (22 bits), y (20 bits)
+        let section = reader.read_i64()?;
+        let section_x = (section >> 42) as i32;
+        let section_y = (section << 44 >> 44) as i32;
+        let section_z = (section << 22 >> 42) as i32;
+        if reader.version() < ProtocolVersion::V1_20_1{
+            reader.read_bool()?; // suppress light updates, removed in 1.20
+        }
 
-    let pitch = data[0..1][0] as i8;
-    data = &data[1..data.len()].to_vec();
+        let count = reader.read_varint()?;
+        let mut blocks = Vec::new();
+        for _ in 0..count{
+            // state ID << 12 | x << 8 | z << 4 | y, the coordinates relative to the section
+            let block = reader.read_varlong()?;
+            let position = BlockPos::new(
+                section_x * 16 + (block >> 8 & 0x0F) as i32,
+                section_y * 16 + (block & 0x0F) as i32,
+                section_z * 16 + (block >> 4 & 0x0F) as i32,
+            );
+            blocks.push((position, (block >> 12) as u32));
+        }
+        Ok(UpdateSectionBlocks{section_x, section_y, section_z, blocks})
+    }
+}
 
-    (entity_id, (x, y, z), (yaw, pitch))
+// the server is done with every block change we sent up to and including this sequence number
+#[derive(Debug, Clone)]
+pub struct AcknowledgeBlockChange{
+    pub sequence: i32,
 }
 
-pub fn entity_animation(packet: &Vec<u8>) -> (i32, String){
-    let (e_ie, size) = varint_read(packet);
-    let animation_byte = packet[size..size+1][0] as i8;
+impl Decode for AcknowledgeBlockChange{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(AcknowledgeBlockChange{sequence: reader.read_varint()?})
+    }
+}
 
-    let animation = match animation_byte{
-        0 => "Swing main arm",
-        1 => "Take Damage",
-        2 => "Leave Bed",
-        3 => "Swing offhand",
-        4 => "Critical effect",
-        5 => "Magic Critical effect (?)",
-        _ => "Unknown"
-    }.to_string();
+// a custom payload on a namespaced channel, like minecraft:brand or a plugin's own. comes in
+// the play and configuration states
+#[derive(Debug, Clone)]
+pub struct PluginMessage{
+    pub channel: String,
+    pub data: Vec<u8>,
+}
 
-    (e_ie, animation)
+impl Decode for PluginMessage{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(PluginMessage{
+            channel: reader.read_string()?,
+            data: reader.read_remaining().to_vec(),
+        })
+    }
+}
+
+// 1.20.2+, what Login (play) used to carry as its registry codec
+#[derive(Debug, Clone)]
+pub struct RegistryData{
+    pub registry_codec: Tag,
+}
+
+impl Decode for RegistryData{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        match read_network_nbt(reader)?{
+            Some(registry_codec) => Ok(RegistryData{registry_codec}),
+            None => Err(ProtocolError::InvalidValue("Registry Data without a registry codec".to_string())),
+        }
+    }
+}
+
+// the experimental features the server has turned on, minecraft:vanilla is always there
+#[derive(Debug, Clone)]
+pub struct FeatureFlags{
+    pub flags: Vec<String>,
+}
+
+impl Decode for FeatureFlags{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(FeatureFlags{flags: read_identifiers(reader)?})
+    }
+}
+
+// registry -> tag name -> the IDs of the entries in that registry the tag holds, e.g.
+// minecraft:block -> minecraft:logs -> every log block
+#[derive(Debug, Clone, Default)]
+pub struct UpdateTags{
+    pub tags: HashMap<String, HashMap<String, Vec<i32>>>,
+}
+
+impl Decode for UpdateTags{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let mut tags = HashMap::new();
+        let registries = reader.read_varint()?;
+        for _ in 0..registries{
+            let registry = reader.read_string()?;
+            let count = reader.read_varint()?;
+            let mut registry_tags = HashMap::new();
+            for _ in 0..count{
+                let name = reader.read_string()?;
+                let entries = reader.read_varint()?;
+                let mut ids = Vec::new();
+                for _ in 0..entries{
+                    ids.push(reader.read_varint()?);
+                }
+                registry_tags.insert(name, ids);
+            }
+            tags.insert(registry, registry_tags);
+        }
+        Ok(UpdateTags{tags})
+    }
+}
+
+// Resource Pack (1.20.2), Add Resource Pack since 1.20.3 which gives packs a uuid
+#[derive(Debug, Clone)]
+pub struct ResourcePack{
+    pub uuid: Option<u128>,
+    pub url: String,
+    pub hash: String, // sha1 as hex, can be empty
+    pub forced: bool,
+    pub prompt: Option<String>, // chat component as json
+}
+
+impl Decode for ResourcePack{
+    fn decode(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        let uuid = if reader.version() >= ProtocolVersion::V1_20_4{ Some(reader.read_uuid()?) } else { None };
+        Ok(ResourcePack{
+            uuid,
+            url: reader.read_string()?,
+            hash: reader.read_string()?,
+            forced: reader.read_bool()?,
+            prompt: if reader.read_bool()?{ Some(read_chat(reader)?) } else { None },
+        })
+    }
+}
+
+// the body of a Login (play) in the version's layout, for tests that need a server to let them in
+#[cfg(test)]
+pub(crate) fn test_login(version: ProtocolVersion) -> Vec<u8>{
+    match version{
+        ProtocolVersion::V1_19_4 => tests::login_1_20_1(false, false),
+        ProtocolVersion::V1_20_1 => tests::login_1_20_1(false, true),
+        _ => tests::login_1_20_2(false),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // tests/fixtures/nbt/sources.txt says where this comes from
+    const REGISTRY_CODEC: &[u8] = include_bytes!("../tests/fixtures/nbt/registry_codec_1.20.1.nbt");
+
+    // a string as it goes over the wire, varint length (all of these fit in one byte) and utf-8
+    fn string(value: &str) -> Vec<u8>{
+        assert!(value.len() < 0x80);
+        [&[value.len() as u8], value.as_bytes()].concat()
+    }
+
+    fn decode<T: Decode>(data: &[u8], version: ProtocolVersion) -> Result<T, ProtocolError>{
+        let mut reader = PacketReader::with_version(data, version);
+        let packet = reader.read::<T>()?;
+        assert_eq!(reader.remaining(), 0, "{} bytes left over", reader.remaining());
+        Ok(packet)
+    }
+
+    // 0x193fffed4fec is (100, -20, -300) packed
+    const DEATH_LOCATION: [u8; 8] = [0x00, 0x00, 0x19, 0x3f, 0xff, 0xed, 0x4f, 0xec];
+
+    fn death_location() -> Vec<u8>{
+        [&[0x01][..], &string("minecraft:the_nether"), &DEATH_LOCATION].concat()
+    }
+
+    fn assert_death_location(death_location: &Option<DeathLocation>){
+        let death_location = death_location.as_ref().expect("no death location");
+        assert_eq!(death_location.dimension, "minecraft:the_nether");
+        assert_eq!(death_location.position, BlockPos::new(100, -20, -300));
+    }
+
+    // Login (play) up to 1.20.1, with the registry codec in the middle
+    pub(super) fn login_1_20_1(death: bool, portal_cooldown: bool) -> Vec<u8>{
+        [
+            &[0x00, 0x00, 0x01, 0x2c][..], // entity id 300
+            &[0x00], // not hardcore
+            &[0x01], // creative
+            &[0xff], // no previous game mode
+            &[0x03], &string("minecraft:overworld"), &string("minecraft:the_nether"), &string("minecraft:the_end"),
+            REGISTRY_CODEC,
+            &string("minecraft:overworld"), // dimension type
+            &string("minecraft:overworld"), // dimension
+            &[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0], // hashed seed
+            &[0x14], // max players
+            &[0x0a], // view distance
+            &[0x08], // simulation distance
+            &[0x00, 0x01, 0x00, 0x01], // reduced debug info, respawn screen, debug, flat
+            &if death{ death_location() } else { vec![0x00] },
+            if portal_cooldown{ &[0xac, 0x02] } else { &[] }, // 300
+        ].concat()
+    }
+
+    #[test]
+    fn login_with_registry_codec(){
+        let join_game = decode::<JoinGame>(&login_1_20_1(false, true), ProtocolVersion::V1_20_1).unwrap();
+        assert_eq!(join_game.entity_id, 300);
+        assert!(!join_game.hardcore);
+        assert_eq!(join_game.game_mode, 1);
+        assert_eq!(join_game.previous_game_mode, -1);
+        assert_eq!(join_game.dimension_names, ["minecraft:overworld", "minecraft:the_nether", "minecraft:the_end"]);
+        let codec = join_game.registry_codec.as_ref().expect("no registry codec");
+        let overworld = codec.get("minecraft:dimension_type").and_then(|registry| registry.get("value")).and_then(Tag::as_list).unwrap()
+            .iter().find(|entry| entry.get("name").and_then(Tag::as_str) == Some("minecraft:overworld")).unwrap();
+        assert_eq!(overworld.get("element").and_then(|element| element.get("min_y")).and_then(Tag::as_i64), Some(-64));
+        assert_eq!(join_game.dimension_type, "minecraft:overworld");
+        assert_eq!(join_game.dimension_name, "minecraft:overworld");
+        assert_eq!(join_game.hashed_seed, 0x123456789abcdef0);
+        assert_eq!((join_game.max_players, join_game.view_distance, join_game.simulation_distance), (20, 10, 8));
+        assert!(!join_game.reduced_debug_info && join_game.enable_respawn_screen && !join_game.debug && join_game.flat);
+        assert!(join_game.death_location.is_none());
+        assert_eq!(join_game.portal_cooldown, 300);
+
+        let join_game = decode::<JoinGame>(&login_1_20_1(true, true), ProtocolVersion::V1_20_1).unwrap();
+        assert_death_location(&join_game.death_location);
+        assert_eq!(join_game.portal_cooldown, 300);
+
+        // 1.19.4 is the same without the portal cooldown
+        let join_game = decode::<JoinGame>(&login_1_20_1(true, false), ProtocolVersion::V1_19_4).unwrap();
+        assert_death_location(&join_game.death_location);
+        assert_eq!(join_game.portal_cooldown, 0);
+        assert!(join_game.registry_codec.is_some());
+        // and doesn't read a 1.20.1 cooldown
+        let data = login_1_20_1(true, true);
+        let mut reader = PacketReader::with_version(&data, ProtocolVersion::V1_19_4);
+        reader.read::<JoinGame>().unwrap();
+        assert_eq!(reader.remaining(), 2);
+    }
+
+    // Login (play) since 1.20.2, no registry codec and the spawn info at the end
+    pub(super) fn login_1_20_2(death: bool) -> Vec<u8>{
+        [
+            &[0x00, 0x00, 0x01, 0x2c][..], // entity id 300
+            &[0x01], // hardcore
+            &[0x01], &string("minecraft:overworld"),
+            &[0x14], // max players
+            &[0x0c], // view distance
+            &[0x0a], // simulation distance
+            &[0x01, 0x01, 0x01], // reduced debug info, respawn screen, limited crafting
+            &string("minecraft:the_end"), // dimension type
+            &string("minecraft:the_end"), // dimension
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe], // hashed seed
+            &[0x03], // spectator
+            &[0x00], // survival before
+            &[0x01, 0x00], // debug, flat
+            &if death{ death_location() } else { vec![0x00] },
+            &[0x00], // portal cooldown
+        ].concat()
+    }
+
+    #[test]
+    fn login_without_registry_codec(){
+        for version in [ProtocolVersion::V1_20_2, ProtocolVersion::V1_20_4]{
+            let join_game = decode::<JoinGame>(&login_1_20_2(false), version).unwrap();
+            assert_eq!(join_game.entity_id, 300);
+            assert!(join_game.hardcore);
+            assert_eq!(join_game.dimension_names, ["minecraft:overworld"]);
+            assert!(join_game.registry_codec.is_none());
+            assert_eq!((join_game.max_players, join_game.view_distance, join_game.simulation_distance), (20, 12, 10));
+            assert!(join_game.reduced_debug_info && join_game.enable_respawn_screen && join_game.do_limited_crafting);
+            assert_eq!(join_game.dimension_type, "minecraft:the_end");
+            assert_eq!(join_game.dimension_name, "minecraft:the_end");
+            assert_eq!(join_game.hashed_seed, -2);
+            assert_eq!(join_game.game_mode, 3);
+            assert_eq!(join_game.previous_game_mode, 0);
+            assert!(join_game.debug && !join_game.flat);
+            assert!(join_game.death_location.is_none());
+            assert_eq!(join_game.portal_cooldown, 0);
+
+            let join_game = decode::<JoinGame>(&login_1_20_2(true), version).unwrap();
+            assert_death_location(&join_game.death_location);
+        }
+    }
+
+    #[test]
+    fn truncated_login(){
+        for (data, version) in [(login_1_20_1(true, true), ProtocolVersion::V1_20_1), (login_1_20_2(true), ProtocolVersion::V1_20_2)]{
+            for size in 0..data.len(){
+                assert!(PacketReader::with_version(&data[..size], version).read::<JoinGame>().is_err(), "{} of {} bytes", size, data.len());
+            }
+        }
+        // a codec that isn't a compound (here TAG_End) isn't a codec
+        let mut data = login_1_20_1(false, true);
+        let codec = 4 + 1 + 1 + 1 + 1 + string("minecraft:overworld").len() + string("minecraft:the_nether").len() + string("minecraft:the_end").len();
+        data.splice(codec..codec + REGISTRY_CODEC.len(), [0x00]);
+        assert!(PacketReader::with_version(&data, ProtocolVersion::V1_20_1).read::<JoinGame>().is_err());
+    }
+
+    fn respawn_start() -> Vec<u8>{
+        [
+            &string("minecraft:the_nether")[..], // dimension type
+            &string("minecraft:the_nether"), // dimension
+            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2a], // hashed seed
+            &[0x00], // survival
+            &[0x02], // adventure before
+            &[0x00, 0x00], // debug, flat
+        ].concat()
+    }
+
+    #[test]
+    fn respawn(){
+        // data kept comes before the death location up to 1.20.1
+        let old = [&respawn_start()[..], &[0x03], &death_location()].concat();
+        let respawn = decode::<Respawn>(&old, ProtocolVersion::V1_19_4).unwrap();
+        assert_eq!(respawn.dimension_type, "minecraft:the_nether");
+        assert_eq!(respawn.dimension_name, "minecraft:the_nether");
+        assert_eq!(respawn.hashed_seed, 42);
+        assert_eq!((respawn.game_mode, respawn.previous_game_mode), (0, 2));
+        assert_eq!(respawn.data_kept, 3);
+        assert_death_location(&respawn.death_location);
+        assert_eq!(respawn.portal_cooldown, 0);
+
+        let with_cooldown = [&old[..], &[0x28]].concat();
+        let respawn = decode::<Respawn>(&with_cooldown, ProtocolVersion::V1_20_1).unwrap();
+        assert_eq!(respawn.data_kept, 3);
+        assert_death_location(&respawn.death_location);
+        assert_eq!(respawn.portal_cooldown, 40);
+
+        // and at the very end since 1.20.2
+        let new = [&respawn_start()[..], &death_location(), &[0x28], &[0x01]].concat();
+        for version in [ProtocolVersion::V1_20_2, ProtocolVersion::V1_20_4]{
+            let respawn = decode::<Respawn>(&new, version).unwrap();
+            assert_eq!(respawn.data_kept, 1);
+            assert_death_location(&respawn.death_location);
+            assert_eq!(respawn.portal_cooldown, 40);
+        }
+        let respawn = decode::<Respawn>(&[&respawn_start()[..], &[0x00, 0x00, 0x02]].concat(), ProtocolVersion::V1_20_2).unwrap();
+        assert!(respawn.death_location.is_none());
+        assert_eq!(respawn.data_kept, 2);
+
+        for size in 0..new.len(){
+            assert!(PacketReader::with_version(&new[..size], ProtocolVersion::V1_20_2).read::<Respawn>().is_err(), "{} of {} bytes", size, new.len());
+        }
+    }
 }
@@ -0,0 +1,918 @@
+use {
+    std::f32::consts::PI,
+    crate::{
+        codec::{
+            BlockPos,
+            Vec3,
+        },
+        player::LocalPlayer,
+        registry::{
+            BlockKind,
+            Fluid,
+            GenericBlockState,
+        },
+        world::{
+            Blocks,
+            World,
+        },
+    },
+};
+
+// a port of the movement code in vanilla's LivingEntity.travel and Entity.move, down to which
+// numbers are floats, so the server's own simulation agrees with where we say we are. what's
+// left out: elytra, potion effects, fluid currents and other entities
+
+pub const PLAYER_WIDTH: f64 = 0.6;
+pub const PLAYER_HEIGHT: f64 = 1.8;
+pub const SNEAKING_HEIGHT: f64 = 1.5;
+pub const SWIMMING_HEIGHT: f64 = 0.6;
+pub const EYE_HEIGHT: f64 = 1.62;
+pub const SNEAKING_EYE_HEIGHT: f64 = 1.27;
+pub const SWIMMING_EYE_HEIGHT: f64 = 0.4;
+
+// how deep the fluid has to be before jumping swims up instead of jumping off the ground
+const FLUID_JUMP_THRESHOLD: f64 = 0.4;
+// ticks between two jumps while jump is held
+const JUMP_DELAY: u32 = 10;
+const EPSILON: f64 = 1.0e-7;
+
+// the keys that are held down this tick
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Inputs{
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    pub sneak: bool,
+    pub sprint: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis{
+    X,
+    Y,
+    Z,
+}
+
+impl Axis{
+    fn of(self, vec: Vec3) -> f64{
+        match self{
+            Axis::X => vec.x,
+            Axis::Y => vec.y,
+            Axis::Z => vec.z,
+        }
+    }
+
+    fn others(self) -> (Axis, Axis){
+        match self{
+            Axis::X => (Axis::Y, Axis::Z),
+            Axis::Y => (Axis::X, Axis::Z),
+            Axis::Z => (Axis::X, Axis::Y),
+        }
+    }
+}
+
+// an axis aligned box in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb{
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb{
+    pub fn new(min: Vec3, max: Vec3) -> Self{
+        Aabb{min, max}
+    }
+
+    // feet at position, centered on x and z
+    pub fn at_feet(position: Vec3, width: f64, height: f64) -> Self{
+        let half = width / 2.0;
+        Aabb::new(Vec3::new(position.x - half, position.y, position.z - half), Vec3::new(position.x + half, position.y + height, position.z + half))
+    }
+
+    pub fn offset(&self, by: Vec3) -> Self{
+        Aabb::new(self.min + by, self.max + by)
+    }
+
+    // grows the box on the side the movement goes to
+    pub fn expand_towards(&self, movement: Vec3) -> Self{
+        let mut expanded = *self;
+        if movement.x < 0.0{ expanded.min.x += movement.x } else { expanded.max.x += movement.x }
+        if movement.y < 0.0{ expanded.min.y += movement.y } else { expanded.max.y += movement.y }
+        if movement.z < 0.0{ expanded.min.z += movement.z } else { expanded.max.z += movement.z }
+        expanded
+    }
+
+    pub fn deflate(&self, amount: f64) -> Self{
+        let by = Vec3::new(amount, amount, amount);
+        Aabb::new(self.min + by, self.max - by)
+    }
+
+    // touching faces don't count
+    pub fn intersects(&self, other: &Aabb) -> bool{
+        self.min.x < other.max.x && self.max.x > other.min.x
+            && self.min.y < other.max.y && self.max.y > other.min.y
+            && self.min.z < other.max.z && self.max.z > other.min.z
+    }
+
+    // how far the entity box can go along the axis (up to distance) before it runs into this box
+    fn collide(&self, entity: &Aabb, axis: Axis, distance: f64) -> f64{
+        let (a, b) = axis.others();
+        let overlaps = |axis: Axis| axis.of(self.max) > axis.of(entity.min) + EPSILON && axis.of(self.min) < axis.of(entity.max) - EPSILON;
+        if !overlaps(a) || !overlaps(b){
+            return distance;
+        }
+        if distance > 0.0 && axis.of(self.min) >= axis.of(entity.max) - EPSILON{
+            distance.min(axis.of(self.min) - axis.of(entity.max))
+        } else if distance < 0.0 && axis.of(self.max) <= axis.of(entity.min) + EPSILON{
+            distance.max(axis.of(self.max) - axis.of(entity.min))
+        } else {
+            distance
+        }
+    }
+}
+
+// the movement attributes of the player. tick is deterministic: the same world, player and
+// inputs always end up in the same place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Physics{
+    pub movement_speed: f32, // the movement_speed attribute
+    pub step_height: f64,
+    pub gravity: f64,
+}
+
+impl Default for Physics{
+    fn default() -> Self{
+        Physics{
+            movement_speed: 0.1,
+            step_height: 0.6,
+            gravity: 0.08,
+        }
+    }
+}
+
+impl Physics{
+    // one game tick of LocalPlayer.aiStep, velocity and position of the player change. B is the
+    // Block of the server's version, see registry::with_block_kind
+    pub fn tick<B: BlockKind>(&self, world: &World, player: &mut LocalPlayer, inputs: Inputs){
+        let world = &world.blocks::<B>();
+        // Entity.baseTick
+        self.update_fluids(world, player);
+        self.update_swimming(world, player);
+
+        // LocalPlayer.aiStep
+        player.sneaking = inputs.sneak;
+        let mut forward = impulse(inputs.forward, inputs.backward);
+        let mut strafe = impulse(inputs.left, inputs.right);
+        if player.sneaking && !player.swimming{
+            forward *= 0.3;
+            strafe *= 0.3;
+        }
+        // under water any forward key starts sprinting, on the surface it doesn't
+        let enough_impulse = if player.under_water{ forward > 1.0e-5 } else { forward >= 0.8 };
+        if inputs.sprint && !player.sprinting && enough_impulse && (!player.in_water || player.under_water){
+            player.sprinting = true;
+        }
+        if player.sprinting{
+            let no_impulse = !inputs.sprint || forward <= 1.0e-5;
+            let stop = if player.swimming{
+                !player.on_ground && !inputs.sneak && no_impulse || !player.in_water
+            } else {
+                no_impulse || player.horizontal_collision || player.in_water && !player.under_water
+            };
+            if stop{
+                player.sprinting = false;
+            }
+        }
+        if player.in_water && inputs.sneak{
+            player.velocity.y -= 0.04_f32 as f64;
+        }
+
+        // LivingEntity.aiStep
+        if player.jump_delay > 0{
+            player.jump_delay -= 1;
+        }
+        let zero_small = |value: f64| if value.abs() < 0.003{ 0.0 } else { value };
+        player.velocity = Vec3::new(zero_small(player.velocity.x), zero_small(player.velocity.y), zero_small(player.velocity.z));
+
+        if inputs.jump{
+            let height = if player.in_lava{ player.lava_height } else { player.water_height };
+            let in_water = player.in_water && height > 0.0;
+            if (in_water || player.in_lava) && (!player.on_ground || height > FLUID_JUMP_THRESHOLD){
+                player.velocity.y += 0.04_f32 as f64;
+            } else if (player.on_ground || in_water && height <= FLUID_JUMP_THRESHOLD) && player.jump_delay == 0{
+                self.jump_from_ground(world, player);
+                player.jump_delay = JUMP_DELAY;
+            }
+        } else {
+            player.jump_delay = 0;
+        }
+
+        let input = Vec3::new((strafe * 0.98) as f64, 0.0, (forward * 0.98) as f64);
+        self.travel(world, player, input, inputs.jump);
+    }
+
+    fn travel<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &mut LocalPlayer, input: Vec3, jumping: bool){
+        // Player.travel, swimming goes up or down the way we look
+        if player.swimming{
+            let look_y = -sin(player.pitch * (PI / 180.0)) as f64;
+            let factor = if look_y < -0.2{ 0.085 } else { 0.06 };
+            let above = Vec3::new(player.position.x, player.position.y + 1.0 - 0.1, player.position.z).block();
+            if look_y <= 0.0 || jumping || world.state(above).and_then(own_fluid).is_some(){
+                player.velocity.y += (look_y - player.velocity.y) * factor;
+            }
+        }
+
+        let falling = player.velocity.y <= 0.0;
+
+        if player.in_water{
+            let start_y = player.position.y;
+            let slowdown = if player.sprinting{ 0.9_f32 } else { 0.8_f32 } as f64;
+            player.velocity = player.velocity + input_vector(input, 0.02, player.yaw);
+            self.move_by(world, player, player.velocity);
+
+            let mut velocity = player.velocity;
+            if player.horizontal_collision && self.on_climbable(world, player){
+                velocity.y = 0.2;
+            }
+            velocity = Vec3::new(velocity.x * slowdown, velocity.y * 0.8_f32 as f64, velocity.z * slowdown);
+            velocity = self.fluid_falling(player, falling, velocity);
+            if player.horizontal_collision && self.is_free(world, player, Vec3::new(velocity.x, velocity.y + 0.6 - player.position.y + start_y, velocity.z)){
+                velocity.y = 0.3;
+            }
+            player.velocity = velocity;
+        } else if player.in_lava{
+            let start_y = player.position.y;
+            player.velocity = player.velocity + input_vector(input, 0.02, player.yaw);
+            self.move_by(world, player, player.velocity);
+
+            let mut velocity = if player.lava_height <= FLUID_JUMP_THRESHOLD{
+                let velocity = Vec3::new(player.velocity.x * 0.5, player.velocity.y * 0.8_f32 as f64, player.velocity.z * 0.5);
+                self.fluid_falling(player, falling, velocity)
+            } else {
+                player.velocity * 0.5
+            };
+            velocity.y -= self.gravity / 4.0;
+            if player.horizontal_collision && self.is_free(world, player, Vec3::new(velocity.x, velocity.y + 0.6 - player.position.y + start_y, velocity.z)){
+                velocity.y = 0.3;
+            }
+            player.velocity = velocity;
+        } else {
+            let below = below_affecting_movement(player);
+            let friction = world.state(below).map_or(0.6, |state| state.physics().friction);
+            let drag = if player.on_ground{ friction * 0.91 } else { 0.91 } as f64;
+
+            // handleRelativeFrictionAndCalculateMovement
+            let speed = if player.on_ground{
+                self.speed(player) * (0.21600002 / (friction * friction * friction))
+            } else if player.sprinting{
+                0.025999999
+            } else {
+                0.02
+            };
+            player.velocity = player.velocity + input_vector(input, speed, player.yaw);
+            player.velocity = self.handle_on_climbable(world, player, player.velocity);
+            self.move_by(world, player, player.velocity);
+            let mut velocity = player.velocity;
+            if (player.horizontal_collision || jumping) && self.on_climbable(world, player){
+                velocity.y = 0.2;
+            }
+
+            if !world.is_loaded(below.x.div_euclid(16), below.z.div_euclid(16)){
+                velocity.y = if player.position.y > world.min_y() as f64{ -0.1 } else { 0.0 };
+            } else {
+                velocity.y -= self.gravity;
+            }
+            player.velocity = Vec3::new(velocity.x * drag, velocity.y * 0.98_f32 as f64, velocity.z * drag);
+        }
+    }
+
+    // Entity.move, the player moves by movement or as far as the blocks let it
+    fn move_by<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &mut LocalPlayer, movement: Vec3){
+        let mut movement = movement;
+        if let Some(stuck) = player.stuck.take(){
+            movement = Vec3::new(movement.x * stuck.x, movement.y * stuck.y, movement.z * stuck.z);
+            player.velocity = Vec3::ZERO;
+        }
+        movement = self.back_off_from_edge(world, player, movement);
+
+        let collided = self.collide(world, player, movement);
+        if collided.length_squared() > EPSILON{
+            player.position = player.position + collided;
+        }
+
+        let x_collision = (movement.x - collided.x).abs() >= 1.0e-5_f32 as f64;
+        let z_collision = (movement.z - collided.z).abs() >= 1.0e-5_f32 as f64;
+        let vertical_collision = movement.y != collided.y;
+        player.horizontal_collision = x_collision || z_collision;
+        player.on_ground = vertical_collision && movement.y < 0.0;
+
+        if player.on_ground{
+            player.fall_distance = 0.0;
+        } else if collided.y < 0.0{
+            player.fall_distance -= collided.y;
+        }
+
+        if x_collision{
+            player.velocity.x = 0.0;
+        }
+        if z_collision{
+            player.velocity.z = 0.0;
+        }
+
+        let on = world.state(BlockPos::new(
+            player.position.x.floor() as i32,
+            (player.position.y - 0.2_f32 as f64).floor() as i32,
+            player.position.z.floor() as i32,
+        ));
+        let on = on.map(|state| state.physics());
+        if vertical_collision{
+            // Block.updateEntityAfterFallOn, slime and beds bounce unless we sneak
+            match on{
+                Some(physics) if physics.bounce > 0.0 && !player.sneaking => {
+                    if player.velocity.y < 0.0{
+                        player.velocity.y = -player.velocity.y * physics.bounce;
+                    }
+                },
+                _ => player.velocity.y = 0.0,
+            }
+        }
+        if player.on_ground && !player.sneaking && on.is_some_and(|physics| physics.sticky){
+            let vertical = player.velocity.y.abs();
+            if vertical < 0.1{
+                let factor = 0.4 + vertical * 0.2;
+                player.velocity.x *= factor;
+                player.velocity.z *= factor;
+            }
+        }
+
+        // Entity.checkInsideBlocks
+        let inside = player.bounding_box().deflate(EPSILON);
+        for (position, state) in blocks_in(world, &inside){
+            let Some(stuck) = state.physics().stuck else{
+                continue;
+            };
+            let touches = state.collision_boxes().is_empty() || boxes_at(state, position).iter().any(|other| other.intersects(&inside));
+            if touches{
+                player.stuck = Some(Vec3::new(stuck[0], stuck[1], stuck[2]));
+                player.fall_distance = 0.0;
+            }
+        }
+
+        let factor = self.speed_factor(world, player) as f64;
+        player.velocity.x *= factor;
+        player.velocity.z *= factor;
+    }
+
+    // Entity.collide, including walking up steps
+    fn collide<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer, movement: Vec3) -> Vec3{
+        let bounds = player.bounding_box();
+        let collided = if movement.length_squared() == 0.0{
+            movement
+        } else {
+            collide_with_world(world, &bounds, movement)
+        };
+
+        let x_collision = movement.x != collided.x;
+        let y_collision = movement.y != collided.y;
+        let z_collision = movement.z != collided.z;
+        let grounded = player.on_ground || y_collision && movement.y < 0.0;
+        if self.step_height > 0.0 && grounded && (x_collision || z_collision){
+            let horizontal = |vec: Vec3| vec.x * vec.x + vec.z * vec.z;
+            let mut stepped = collide_with_world(world, &bounds, Vec3::new(movement.x, self.step_height, movement.z));
+            let up = collide_with_world(world, &bounds.expand_towards(Vec3::new(movement.x, 0.0, movement.z)), Vec3::new(0.0, self.step_height, 0.0));
+            if up.y < self.step_height{
+                let over = collide_with_world(world, &bounds.offset(up), Vec3::new(movement.x, 0.0, movement.z)) + up;
+                if horizontal(over) > horizontal(stepped){
+                    stepped = over;
+                }
+            }
+            if horizontal(stepped) > horizontal(collided){
+                return stepped + collide_with_world(world, &bounds.offset(stepped), Vec3::new(0.0, -stepped.y + movement.y, 0.0));
+            }
+        }
+        collided
+    }
+
+    // Player.maybeBackOffFromEdge, sneaking doesn't walk off edges
+    fn back_off_from_edge<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer, movement: Vec3) -> Vec3{
+        if movement.y > 0.0 || !player.sneaking || !self.is_above_ground(world, player){
+            return movement;
+        }
+        let bounds = player.bounding_box();
+        let free = |dx: f64, dz: f64| no_collision(world, &bounds.offset(Vec3::new(dx, -self.step_height, dz)));
+        let back_off = |distance: f64| if (-0.05..0.05).contains(&distance){
+            0.0
+        } else if distance > 0.0{
+            distance - 0.05
+        } else {
+            distance + 0.05
+        };
+
+        let mut dx = movement.x;
+        let mut dz = movement.z;
+        while dx != 0.0 && free(dx, 0.0){
+            dx = back_off(dx);
+        }
+        while dz != 0.0 && free(0.0, dz){
+            dz = back_off(dz);
+        }
+        while dx != 0.0 && dz != 0.0 && free(dx, dz){
+            dx = back_off(dx);
+            dz = back_off(dz);
+        }
+        Vec3::new(dx, movement.y, dz)
+    }
+
+    fn is_above_ground<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer) -> bool{
+        player.on_ground || player.fall_distance < self.step_height
+            && !no_collision(world, &player.bounding_box().offset(Vec3::new(0.0, player.fall_distance - self.step_height, 0.0)))
+    }
+
+    // Entity.isFree, nothing solid and no fluid where the player would be after moving by offset
+    fn is_free<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer, offset: Vec3) -> bool{
+        let bounds = player.bounding_box().offset(offset);
+        no_collision(world, &bounds) && !blocks_in(world, &bounds).any(|(position, _)| fluid_at(world, position).is_some())
+    }
+
+    fn jump_from_ground<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &mut LocalPlayer){
+        player.velocity.y = (0.42 * self.jump_factor(world, player)) as f64;
+        if player.sprinting{
+            let yaw = player.yaw * (PI / 180.0);
+            player.velocity.x += (-sin(yaw) * 0.2) as f64;
+            player.velocity.z += (cos(yaw) * 0.2) as f64;
+        }
+    }
+
+    fn speed(&self, player: &LocalPlayer) -> f32{
+        if player.sprinting{
+            (self.movement_speed as f64 * 1.3) as f32
+        } else {
+            self.movement_speed
+        }
+    }
+
+    // LivingEntity.getFluidFallingAdjustedMovement
+    fn fluid_falling(&self, player: &LocalPlayer, falling: bool, velocity: Vec3) -> Vec3{
+        if player.sprinting{
+            return velocity;
+        }
+        let y = if falling && (velocity.y - 0.005).abs() >= 0.003 && (velocity.y - self.gravity / 16.0).abs() < 0.003{
+            -0.003
+        } else {
+            velocity.y - self.gravity / 16.0
+        };
+        Vec3::new(velocity.x, y, velocity.z)
+    }
+
+    fn on_climbable<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer) -> bool{
+        world.state(player.position.block()).is_some_and(|state| state.physics().climbable)
+    }
+
+    fn handle_on_climbable<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &mut LocalPlayer, velocity: Vec3) -> Vec3{
+        if !self.on_climbable(world, player){
+            return velocity;
+        }
+        player.fall_distance = 0.0;
+        let limit = 0.15_f32 as f64;
+        let mut y = velocity.y.max(-limit);
+        let on_scaffolding = world.state(player.position.block()).and_then(|state| state.block()).is_some_and(|block| block.name() == "minecraft:scaffolding");
+        if y < 0.0 && !on_scaffolding && player.sneaking{
+            y = 0.0;
+        }
+        Vec3::new(velocity.x.clamp(-limit, limit), y, velocity.z.clamp(-limit, limit))
+    }
+
+    // the block we are in decides, unless it doesn't do anything, then the one below does
+    fn speed_factor<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer) -> f32{
+        let feet = world.state(player.position.block());
+        let factor = feet.map_or(1.0, |state| state.physics().speed_factor);
+        let in_water = feet.and_then(|state| state.block()).is_some_and(|block| matches!(block.name(), "minecraft:water" | "minecraft:bubble_column"));
+        if factor != 1.0 || in_water{
+            return factor;
+        }
+        world.state(below_affecting_movement(player)).map_or(1.0, |state| state.physics().speed_factor)
+    }
+
+    fn jump_factor<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &LocalPlayer) -> f32{
+        let factor = world.state(player.position.block()).map_or(1.0, |state| state.physics().jump_factor);
+        if factor != 1.0{
+            return factor;
+        }
+        world.state(below_affecting_movement(player)).map_or(1.0, |state| state.physics().jump_factor)
+    }
+
+    // Entity.updateInWaterStateAndDoFluidPushing and the lava half of baseTick
+    fn update_fluids<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &mut LocalPlayer){
+        let bounds = player.bounding_box();
+        let water = fluid_height(world, &bounds, Fluid::Water);
+        player.in_water = water.is_some();
+        player.water_height = water.unwrap_or(0.0);
+        if player.in_water{
+            player.fall_distance = 0.0;
+        }
+        player.lava_height = fluid_height(world, &bounds, Fluid::Lava).unwrap_or(0.0);
+        player.in_lava = player.lava_height > 0.0;
+
+        // Entity.updateFluidOnEyes, a bit below the eyes has to be under the surface
+        let eyes = player.position.y + player.eye_height() - 0.11111111;
+        let block = Vec3::new(player.position.x, eyes, player.position.z).block();
+        let eyes_in_water = fluid_at(world, block).is_some_and(|(fluid, height)| fluid == Fluid::Water && eyes < (block.y as f32 + height) as f64);
+        player.under_water = player.in_water && eyes_in_water;
+    }
+
+    // Entity.updateSwimming, sprinting under water with our feet in water starts swimming and
+    // it goes on for as long as we sprint in water
+    fn update_swimming<B: BlockKind>(&self, world: &Blocks<'_, B>, player: &mut LocalPlayer){
+        player.swimming = if player.swimming{
+            player.sprinting && player.in_water
+        } else {
+            let feet_in_water = world.state(player.position.block()).and_then(own_fluid).is_some_and(|(fluid, _)| fluid == Fluid::Water);
+            player.sprinting && player.under_water && feet_in_water
+        };
+    }
+}
+
+// -1, 0 or 1, like the keyboard input of the vanilla client
+fn impulse(positive: bool, negative: bool) -> f32{
+    match (positive, negative){
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    }
+}
+
+// Mth.sin and Mth.cos go through a table of 65536 values, which is slightly off from the real thing
+fn sin(value: f32) -> f32{
+    sin_table((value * 10430.378) as i32)
+}
+
+fn cos(value: f32) -> f32{
+    sin_table((value * 10430.378 + 16384.0) as i32)
+}
+
+fn sin_table(index: i32) -> f32{
+    ((index & 0xFFFF) as f64 * std::f64::consts::PI * 2.0 / 65536.0).sin() as f32
+}
+
+// Entity.getInputVector, the input turned from where we look into world directions
+fn input_vector(input: Vec3, speed: f32, yaw: f32) -> Vec3{
+    let length_squared = input.length_squared();
+    if length_squared < EPSILON{
+        return Vec3::ZERO;
+    }
+    let input = if length_squared > 1.0{
+        let length = length_squared.sqrt();
+        Vec3::new(input.x / length, input.y / length, input.z / length)
+    } else {
+        input
+    } * speed as f64;
+    let sin = sin(yaw * (PI / 180.0)) as f64;
+    let cos = cos(yaw * (PI / 180.0)) as f64;
+    Vec3::new(input.x * cos - input.z * sin, input.y, input.z * cos + input.x * sin)
+}
+
+fn below_affecting_movement(player: &LocalPlayer) -> BlockPos{
+    Vec3::new(player.position.x, player.position.y - 0.5000001, player.position.z).block()
+}
+
+fn boxes_at<B: BlockKind>(state: GenericBlockState<B>, position: BlockPos) -> Vec<Aabb>{
+    let corner = Vec3::new(position.x as f64, position.y as f64, position.z as f64);
+    state.collision_boxes().iter()
+        .map(|bounds| Aabb::new(corner + Vec3::new(bounds[0], bounds[1], bounds[2]), corner + Vec3::new(bounds[3], bounds[4], bounds[5])))
+        .collect()
+}
+
+// every loaded block the box touches
+fn blocks_in<'a, B: BlockKind>(world: &'a Blocks<'a, B>, bounds: &Aabb) -> impl Iterator<Item = (BlockPos, GenericBlockState<B>)> + 'a{
+    let (min, max) = (bounds.min.block(), Vec3::new(bounds.max.x.ceil() - 1.0, bounds.max.y.ceil() - 1.0, bounds.max.z.ceil() - 1.0).block());
+    (min.x..=max.x).flat_map(move |x| (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| BlockPos::new(x, y, z))))
+        .filter_map(|position| world.state(position).map(|state| (position, state)))
+}
+
+// the collision boxes of the blocks around bounds that reach into it. one block further out is
+// looked at too, for fences and walls that stick out of their block
+fn collisions<B: BlockKind>(world: &Blocks<'_, B>, bounds: &Aabb) -> Vec<Aabb>{
+    let min = Vec3::new(bounds.min.x - EPSILON, bounds.min.y - EPSILON, bounds.min.z - EPSILON).block();
+    let max = Vec3::new(bounds.max.x + EPSILON, bounds.max.y + EPSILON, bounds.max.z + EPSILON).block();
+    let mut boxes = Vec::new();
+    for x in min.x - 1..=max.x + 1{
+        for y in min.y - 1..=max.y + 1{
+            for z in min.z - 1..=max.z + 1{
+                let position = BlockPos::new(x, y, z);
+                if let Some(state) = world.state(position){
+                    boxes.extend(boxes_at(state, position).into_iter().filter(|other| other.intersects(bounds)));
+                }
+            }
+        }
+    }
+    boxes
+}
+
+fn no_collision<B: BlockKind>(world: &Blocks<'_, B>, bounds: &Aabb) -> bool{
+    collisions(world, bounds).is_empty()
+}
+
+// Entity.collideBoundingBox: y first, then the bigger of x and z, then the other one
+fn collide_with_world<B: BlockKind>(world: &Blocks<'_, B>, bounds: &Aabb, movement: Vec3) -> Vec3{
+    let shapes = collisions(world, &bounds.expand_towards(movement));
+    if shapes.is_empty(){
+        return movement;
+    }
+
+    let mut bounds = *bounds;
+    let (mut dx, mut dy, mut dz) = (movement.x, movement.y, movement.z);
+    if dy != 0.0{
+        dy = collide_axis(&shapes, &bounds, Axis::Y, dy);
+        if dy != 0.0{
+            bounds = bounds.offset(Vec3::new(0.0, dy, 0.0));
+        }
+    }
+    let z_first = dx.abs() < dz.abs();
+    if z_first && dz != 0.0{
+        dz = collide_axis(&shapes, &bounds, Axis::Z, dz);
+        if dz != 0.0{
+            bounds = bounds.offset(Vec3::new(0.0, 0.0, dz));
+        }
+    }
+    if dx != 0.0{
+        dx = collide_axis(&shapes, &bounds, Axis::X, dx);
+        if !z_first && dx != 0.0{
+            bounds = bounds.offset(Vec3::new(dx, 0.0, 0.0));
+        }
+    }
+    if !z_first && dz != 0.0{
+        dz = collide_axis(&shapes, &bounds, Axis::Z, dz);
+    }
+    Vec3::new(dx, dy, dz)
+}
+
+fn collide_axis(shapes: &[Aabb], bounds: &Aabb, axis: Axis, distance: f64) -> f64{
+    let mut distance = distance;
+    for shape in shapes{
+        if distance.abs() < EPSILON{
+            return 0.0;
+        }
+        distance = shape.collide(bounds, axis, distance);
+    }
+    distance
+}
+
+// the fluid in a block and how high it goes, 1 when the same fluid is above it
+fn fluid_at<B: BlockKind>(world: &Blocks<'_, B>, position: BlockPos) -> Option<(Fluid, f32)>{
+    let (fluid, amount) = own_fluid(world.state(position)?)?;
+    let above = world.state(BlockPos::new(position.x, position.y + 1, position.z)).and_then(own_fluid);
+    let height = if above.is_some_and(|(other, _)| other == fluid){ 1.0 } else { amount as f32 / 9.0 };
+    Some((fluid, height))
+}
+
+// a block's fluid and its amount out of 8. level is 0 for a source, 1 to 7 as it flows out and
+// 8 and up when it falls
+fn own_fluid<B: BlockKind>(state: GenericBlockState<B>) -> Option<(Fluid, u32)>{
+    match state.physics().fluid{
+        Some(fluid) => {
+            let level: u32 = state.get_by_name("level").and_then(|level| level.parse().ok()).unwrap_or(0);
+            Some((fluid, if level == 0 || level >= 8{ 8 } else { 8 - level }))
+        },
+        None if state.get_by_name("waterlogged") == Some("true") => Some((Fluid::Water, 8)),
+        None => None,
+    }
+}
+
+// Entity.updateFluidHeightAndDoFluidPushing without the pushing. None when the box isn't in
+// the fluid at all, else how deep it's in it
+fn fluid_height<B: BlockKind>(world: &Blocks<'_, B>, bounds: &Aabb, fluid: Fluid) -> Option<f64>{
+    let bounds = bounds.deflate(0.001);
+    let mut height = None;
+    for (position, _) in blocks_in(world, &bounds){
+        let Some((other, fluid_height)) = fluid_at(world, position) else{
+            continue;
+        };
+        let top = (position.y as f32 + fluid_height) as f64;
+        if other == fluid && top >= bounds.min.y{
+            height = Some(height.unwrap_or(0.0_f64).max(top - bounds.min.y));
+        }
+    }
+    height
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        std::fs,
+        serde_json::Value,
+        crate::{
+            registry::test_blocks::{
+                Block,
+                BlockState,
+            },
+            world::test_world,
+        },
+    };
+
+    // minecraft:ladder[facing=north] in the fixture block registry
+    fn state(text: &str) -> BlockState{
+        let (name, properties) = text.split_once('[').unwrap_or((text, "]"));
+        let block = Block::from_name(name).unwrap_or_else(|| panic!("no block {}", name));
+        let mut state = BlockState::new(block.info().default_state);
+        for property in properties.trim_end_matches(']').split(',').filter(|property| !property.is_empty()){
+            let (property, value) = property.split_once('=').unwrap();
+            let (property, _) = block.info().properties.iter().find(|(other, _)| other.name() == property).unwrap_or_else(|| panic!("{} has no {}", name, property));
+            state = state.with(*property, value).unwrap_or_else(|| panic!("{} can't be {}", text, value));
+        }
+        state
+    }
+
+    fn vec3(value: &Value) -> Vec3{
+        Vec3::new(value[0].as_f64().unwrap(), value[1].as_f64().unwrap(), value[2].as_f64().unwrap())
+    }
+
+    fn flags(player: &LocalPlayer) -> Vec<&'static str>{
+        [
+            ("on_ground", player.on_ground),
+            ("sprinting", player.sprinting),
+            ("swimming", player.swimming),
+            ("in_water", player.in_water),
+            ("under_water", player.under_water),
+            ("in_lava", player.in_lava),
+        ].into_iter().filter(|(_, set)| *set).map(|(flag, _)| flag).collect()
+    }
+
+    // tests/fixtures/physics/<name>.json: the blocks, where the player starts, and for every tick
+    // the keys held and where the player ends up, see sources.txt there
+    fn load(name: &str) -> (Value, World, LocalPlayer){
+        let path = format!("tests/fixtures/physics/{}.json", name);
+        let fixture: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let mut world = test_world(-16, 48, 1);
+        for fill in fixture["blocks"].as_array().unwrap(){
+            let (from, to) = (vec3(&fill["from"]).block(), vec3(&fill["to"]).block());
+            let state = state(fill["state"].as_str().unwrap()).id();
+            for x in from.x..=to.x{
+                for y in from.y..=to.y{
+                    for z in from.z..=to.z{
+                        world.set_block(x, y, z, state).unwrap();
+                    }
+                }
+            }
+        }
+        let start = &fixture["player"];
+        let mut player = LocalPlayer::new(vec3(&start["position"]), start["yaw"].as_f64().unwrap() as f32, start["pitch"].as_f64().unwrap() as f32);
+        player.on_ground = start["on_ground"].as_bool().unwrap();
+        (fixture, world, player)
+    }
+
+    fn inputs(tick: &Value) -> Inputs{
+        let keys: Vec<&str> = tick["keys"].as_array().unwrap().iter().map(|key| key.as_str().unwrap()).collect();
+        Inputs{
+            forward: keys.contains(&"forward"),
+            backward: keys.contains(&"backward"),
+            left: keys.contains(&"left"),
+            right: keys.contains(&"right"),
+            jump: keys.contains(&"jump"),
+            sneak: keys.contains(&"sneak"),
+            sprint: keys.contains(&"sprint"),
+        }
+    }
+
+    // the positions of every tick, checking each against the fixture
+    fn replay(name: &str) -> Vec<Vec3>{
+        let (fixture, world, mut player) = load(name);
+        let physics = Physics::default();
+        let mut positions = Vec::new();
+        for (index, tick) in fixture["ticks"].as_array().unwrap().iter().enumerate(){
+            if let Some(pitch) = tick["pitch"].as_f64(){
+                player.pitch = pitch as f32;
+            }
+            physics.tick::<Block>(&world, &mut player, inputs(tick));
+            let expected = vec3(&tick["position"]);
+            assert!((player.position - expected).length_squared() < 1.0e-18, "{} tick {}: at {:?}, the fixture has {:?}", name, index, player.position, expected);
+            let expected: Vec<&str> = tick["flags"].as_array().unwrap().iter().map(|flag| flag.as_str().unwrap()).collect();
+            assert_eq!(flags(&player), expected, "{} tick {}", name, index);
+            positions.push(player.position);
+        }
+        positions
+    }
+
+    #[test]
+    fn jump(){
+        // the heights of a vanilla jump, as every movement mod knows them
+        let vanilla = [0.41999998688697815, 0.7531999805212024, 1.0013359791121417, 1.1661092609382138, 1.2491870787446828, 1.2522033402537218, 1.1767592750642422];
+        for (position, height) in replay("jump").iter().zip(vanilla){
+            assert!((position.y - height).abs() < 1.0e-9, "{} instead of {}", position.y, height);
+        }
+    }
+
+    #[test]
+    fn walk_and_sprint(){
+        // 4.317 and 5.612 blocks a second once up to speed
+        let positions = replay("walk_and_sprint");
+        assert!((positions[13].z - positions[14].z - 0.21585).abs() < 1.0e-3);
+        assert!((positions[28].z - positions[29].z - 0.2806).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn sprint_jump(){
+        replay("sprint_jump");
+    }
+
+    #[test]
+    fn ice(){
+        replay("ice");
+    }
+
+    #[test]
+    fn soul_sand(){
+        replay("soul_sand");
+    }
+
+    #[test]
+    fn slime_bounce(){
+        let positions = replay("slime_bounce");
+        // the first bounce goes up more than half of the way we fell, sneaking doesn't bounce at all
+        assert!(positions[..30].iter().any(|position| position.y > 4.0));
+        assert!(positions[52..].iter().all(|position| position.y == 1.0));
+    }
+
+    #[test]
+    fn cobweb(){
+        replay("cobweb");
+    }
+
+    #[test]
+    fn ladder(){
+        replay("ladder");
+    }
+
+    #[test]
+    fn step_up(){
+        let positions = replay("step_up");
+        // onto the slab without jumping
+        assert!(positions[..20].iter().any(|position| position.y == 0.5));
+    }
+
+    #[test]
+    fn sneak_edge(){
+        let positions = replay("sneak_edge");
+        // the box stays over the last block, less than one 0.05 back off step from its edge
+        assert!(positions[..40].iter().all(|position| position.y == 0.0 && position.z < 2.3));
+        assert!(positions[39].z > 2.25);
+    }
+
+    #[test]
+    fn water(){
+        replay("water");
+    }
+
+    #[test]
+    fn swim(){
+        let positions = replay("swim");
+        // diving while looking down, coming up while looking up
+        assert!(positions[15].y < positions[0].y);
+        assert!(positions[49].y > positions[20].y);
+    }
+
+    #[test]
+    fn lava(){
+        replay("lava");
+    }
+
+    // where a player dropped onto a 3x3 floor of the state comes to rest, None if it never lands
+    fn land_on<B: BlockKind>(state: u32) -> Option<f64>{
+        let mut world = test_world(-16, 48, 1);
+        for x in -1..=1{
+            for z in -1..=1{
+                world.set_block(x, 0, z, state).unwrap();
+            }
+        }
+        let mut player = LocalPlayer::new(Vec3::new(0.5, 3.0, 0.5), 0.0, 0.0);
+        for _ in 0..40{
+            Physics::default().tick::<B>(&world, &mut player, Inputs::default());
+        }
+        player.on_ground.then_some(player.position.y)
+    }
+
+    // state IDs of a real server go through that version's Block. the checked in 1.20.x reports only
+    // have the blocks up to the planks, water and the rest can only be tested like this once the
+    // full reports are in
+    #[test]
+    fn version_state_ids(){
+        use crate::registry::{
+            v1_20_1,
+            v1_20_4,
+        };
+
+        assert_eq!(v1_20_1::Block::from_state(1), Some(v1_20_1::Block::Stone));
+        assert_eq!(v1_20_4::Block::from_state(9), Some(v1_20_4::Block::GrassBlock));
+        assert_eq!(GenericBlockState::<v1_20_4::Block>::new(9).get_by_name("snowy"), Some("false"));
+        assert_eq!(land_on::<v1_20_1::Block>(1), Some(1.0)); // stone
+        assert_eq!(land_on::<v1_20_4::Block>(8), Some(1.0)); // grass_block[snowy=true]
+        assert_eq!(land_on::<v1_20_4::Block>(23), Some(1.0)); // bamboo_planks
+        assert_eq!(land_on::<v1_20_1::Block>(0), None); // air
+
+        // a state the reports don't have is a full cube rather than something to fall through
+        let unknown = (0..).find(|&id| v1_20_1::Block::from_state(id).is_none()).unwrap();
+        assert_eq!(land_on::<v1_20_1::Block>(unknown), Some(1.0));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    codec::Vec3,
+    packets::{
+        RELATIVE_X,
+        RELATIVE_Y,
+        RELATIVE_Z,
+        SetPlayerOnGround,
+        SetPlayerPosition,
+        SetPlayerPositionRotation,
+        SetPlayerRotation,
+        SynchronizePlayerPosition,
+    },
+    physics::{
+        Aabb,
+        EYE_HEIGHT,
+        Inputs,
+        PLAYER_HEIGHT,
+        PLAYER_WIDTH,
+        SNEAKING_EYE_HEIGHT,
+        SNEAKING_HEIGHT,
+        SWIMMING_EYE_HEIGHT,
+        SWIMMING_HEIGHT,
+    },
+};
+
+// the vanilla client sends its position at least this often (in ticks) even when standing still
+const POSITION_REMINDER_TICKS: u32 = 20;
+// moves shorter than this aren't worth a packet
+const MIN_MOVE: f64 = 2.0e-4;
+
+// what a tick has to tell the server about us, see LocalPlayer::tick
+#[derive(Debug, Clone, Copy)]
+pub enum MovePacket{
+    Position(SetPlayerPosition),
+    PositionRotation(SetPlayerPositionRotation),
+    Rotation(SetPlayerRotation),
+    OnGround(SetPlayerOnGround),
+}
+
+// ourselves. the play loop moves us when the server teleports us, every tick Physics::tick
+// moves us by the inputs and the next tick sends where we ended up
+#[derive(Debug, Clone)]
+pub struct LocalPlayer{
+    pub position: Vec3, // feet
+    pub velocity: Vec3, // blocks per tick
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+    pub inputs: Inputs, // what the next tick does, set these to walk around
+    pub sprinting: bool,
+    pub sneaking: bool,
+    pub swimming: bool, // sprinting under water, the box is only as high as it is wide
+    pub fall_distance: f64,
+    pub horizontal_collision: bool,
+    pub in_water: bool,
+    pub in_lava: bool,
+    pub under_water: bool, // the eyes are
+    // kept between ticks for Physics
+    pub(crate) water_height: f64,
+    pub(crate) lava_height: f64,
+    pub(crate) jump_delay: u32,
+    pub(crate) stuck: Option<Vec3>, // the slowdown of a cobweb or berry bush we're in
+    // what the server last heard from us
+    last_position: Vec3,
+    last_yaw: f32,
+    last_pitch: f32,
+    last_on_ground: bool,
+    ticks_since_position: u32,
+}
+
+impl LocalPlayer{
+    // where the first Synchronize Player Position put us
+    pub fn new(position: Vec3, yaw: f32, pitch: f32) -> Self{
+        LocalPlayer{
+            position,
+            velocity: Vec3::ZERO,
+            yaw,
+            pitch,
+            on_ground: false,
+            inputs: Inputs::default(),
+            sprinting: false,
+            sneaking: false,
+            swimming: false,
+            fall_distance: 0.0,
+            horizontal_collision: false,
+            in_water: false,
+            in_lava: false,
+            under_water: false,
+            water_height: 0.0,
+            lava_height: 0.0,
+            jump_delay: 0,
+            stuck: None,
+            last_position: position,
+            last_yaw: yaw,
+            last_pitch: pitch,
+            last_on_ground: false,
+            ticks_since_position: 0,
+        }
+    }
+
+    // moves us where the sync says. velocity only survives along the relative axes, and the server
+    // expects the new position back with on_ground false like vanilla
+    pub fn teleport(&mut self, sync: &SynchronizePlayerPosition) -> SetPlayerPositionRotation{
+        let (position, yaw, pitch) = sync.apply(self.position, self.yaw, self.pitch);
+        let keep = |flag: u8, value: f64| if sync.relative & flag != 0{ value } else { 0.0 };
+        self.velocity = Vec3::new(keep(RELATIVE_X, self.velocity.x), keep(RELATIVE_Y, self.velocity.y), keep(RELATIVE_Z, self.velocity.z));
+        self.position = position;
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.on_ground = false;
+        self.last_position = position;
+        self.last_yaw = yaw;
+        self.last_pitch = pitch;
+        self.last_on_ground = false;
+        self.ticks_since_position = 0;
+        SetPlayerPositionRotation{position, yaw, pitch, on_ground: false}
+    }
+
+    // the box we collide with, smaller while sneaking and swimming
+    pub fn bounding_box(&self) -> Aabb{
+        let height = if self.swimming{
+            SWIMMING_HEIGHT
+        } else if self.sneaking{
+            SNEAKING_HEIGHT
+        } else {
+            PLAYER_HEIGHT
+        };
+        Aabb::at_feet(self.position, PLAYER_WIDTH, height)
+    }
+
+    // from the feet
+    pub fn eye_height(&self) -> f64{
+        if self.swimming{
+            SWIMMING_EYE_HEIGHT
+        } else if self.sneaking{
+            SNEAKING_EYE_HEIGHT
+        } else {
+            EYE_HEIGHT
+        }
+    }
+
+    // called every tick (20 times a second), picks the smallest packet that says what changed
+    // since the last one the same way LocalPlayer.sendPosition does in vanilla. None if nothing did
+    pub fn tick(&mut self) -> Option<MovePacket>{
+        self.ticks_since_position += 1;
+        let moved = (self.position - self.last_position).length_squared() > MIN_MOVE * MIN_MOVE
+            || self.ticks_since_position >= POSITION_REMINDER_TICKS;
+        let rotated = self.yaw != self.last_yaw || self.pitch != self.last_pitch;
+
+        let packet = match (moved, rotated){
+            (true, true) => Some(MovePacket::PositionRotation(SetPlayerPositionRotation{position: self.position, yaw: self.yaw, pitch: self.pitch, on_ground: self.on_ground})),
+            (true, false) => Some(MovePacket::Position(SetPlayerPosition{position: self.position, on_ground: self.on_ground})),
+            (false, true) => Some(MovePacket::Rotation(SetPlayerRotation{yaw: self.yaw, pitch: self.pitch, on_ground: self.on_ground})),
+            (false, false) if self.on_ground != self.last_on_ground => Some(MovePacket::OnGround(SetPlayerOnGround{on_ground: self.on_ground})),
+            (false, false) => None,
+        };
+
+        if moved{
+            self.last_position = self.position;
+            self.ticks_since_position = 0;
+        }
+        if rotated{
+            self.last_yaw = self.yaw;
+            self.last_pitch = self.pitch;
+        }
+        self.last_on_ground = self.on_ground;
+        packet
+    }
+}
@@ -0,0 +1,245 @@
+use {
+    std::marker::PhantomData,
+    crate::{
+        metadata::{
+            MetadataField,
+            MetadataKind,
+        },
+        version::ProtocolVersion,
+    },
+};
+
+// one module per version in reports/, like v1_20_1, each with the EntityType, Item, Particle,
+// SoundEvent, Block and PropertyName enums of that version, the entity metadata fields and the
+// LANGUAGE table from en_us.json, or the closest version's without one, language picks it for a
+// version. the EntityType, Item, Particle and SoundEvent out here are the newest version's, with
+// from_version_id / version_id for the IDs a server of another version sends. with_block_kind! picks the Block of a version for what is generic over BlockKind, and
+// test_blocks is the made up registry the tests build worlds with. generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/registries.rs"));
+
+// minecraft:area_effect_cloud => Area Effect Cloud, close enough to the real english names
+pub fn display_name(name: &str) -> String{
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    name.split(['_', '.', '/'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next(){
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// what the blocks report says about a block, properties are in the order vanilla numbers states by
+#[derive(Debug)]
+pub struct BlockInfo<P: 'static>{
+    pub name: &'static str,
+    pub min_state: u32,
+    pub max_state: u32,
+    pub default_state: u32,
+    pub properties: &'static [(P, &'static [&'static str])],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fluid{
+    Water,
+    Lava,
+}
+
+// part of a block's collision shape, the boxes (min x, y, z, max x, y, z, in blocks from the
+// block's corner) are there when the state has one of the values for every property in when
+#[derive(Debug)]
+pub struct ShapeRule<P: 'static>{
+    pub when: &'static [(P, &'static [&'static str])],
+    pub boxes: &'static [[f64; 6]],
+}
+
+// how a block affects movement, from reports/<version>/block_physics.json
+#[derive(Debug)]
+pub struct BlockPhysics<P: 'static>{
+    pub friction: f32,
+    pub speed_factor: f32,
+    pub jump_factor: f32,
+    pub bounce: f64, // how much of the fall speed comes back up on landing, 0 for most blocks
+    pub sticky: bool, // slime, slows down anything walking over it
+    pub climbable: bool,
+    pub fluid: Option<Fluid>, // the fluid the block always has, waterlogged is a property
+    pub stuck: Option<[f64; 3]>, // cobwebs and the like multiply movement inside them by this
+    pub shape: Option<&'static [ShapeRule<P>]>, // None for a full cube
+}
+
+impl<P: 'static> BlockPhysics<P>{
+    pub const DEFAULT: BlockPhysics<P> = BlockPhysics{
+        friction: 0.6,
+        speed_factor: 1.0,
+        jump_factor: 1.0,
+        bounce: 0.0,
+        sticky: false,
+        climbable: false,
+        fluid: None,
+        stuck: None,
+        shape: None,
+    };
+}
+
+// the generated Block enum of a version, so GenericBlockState works with any of them
+pub trait BlockKind: Copy + 'static{
+    type Property: Copy + PartialEq + 'static;
+
+    fn from_state(state: u32) -> Option<Self>;
+    fn info(self) -> &'static BlockInfo<Self::Property>;
+    fn physics(self) -> &'static BlockPhysics<Self::Property>;
+    fn property_name(property: Self::Property) -> &'static str;
+
+    fn name(self) -> &'static str{
+        self.info().name
+    }
+}
+
+// a block state ID (what World::block_at gives back) that knows which block it belongs to and
+// what its properties are. each version module has it as BlockState
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GenericBlockState<B>{
+    id: u32,
+    block: PhantomData<B>,
+}
+
+impl<B: BlockKind> GenericBlockState<B>{
+    pub fn new(id: u32) -> Self{
+        GenericBlockState{id, block: PhantomData}
+    }
+
+    pub fn id(self) -> u32{
+        self.id
+    }
+
+    // None for a state ID past the end of the reports
+    pub fn block(self) -> Option<B>{
+        B::from_state(self.id)
+    }
+
+    pub fn is_default(self) -> bool{
+        self.block().is_some_and(|block| block.info().default_state == self.id)
+    }
+
+    pub fn properties(self) -> Vec<(B::Property, &'static str)>{
+        let Some(block) = self.block() else{
+            return Vec::new();
+        };
+
+        // the state's offset in the block is a mixed radix number, one digit per property
+        let mut offset = (self.id - block.info().min_state) as usize;
+        let mut properties: Vec<(B::Property, &'static str)> = block.info().properties.iter().rev().map(|(name, values)| {
+            let value = values[offset % values.len()];
+            offset /= values.len();
+            (*name, value)
+        }).collect();
+        properties.reverse();
+        properties
+    }
+
+    // a state past the end of the reports counts as a plain full block. while the checked in
+    // blocks.json isn't complete that is most of them, build.rs warns about it
+    pub fn physics(self) -> &'static BlockPhysics<B::Property>{
+        match self.block(){
+            Some(block) => block.physics(),
+            None => &BlockPhysics::DEFAULT,
+        }
+    }
+
+    // the collision boxes relative to the block's corner, empty for blocks we walk through
+    pub fn collision_boxes(self) -> Vec<[f64; 6]>{
+        let Some(rules) = self.physics().shape else{
+            return vec![[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]];
+        };
+        let properties = self.properties();
+        rules.iter()
+            .filter(|rule| rule.when.iter().all(|(name, values)| properties.iter().any(|(other, value)| other == name && values.contains(value))))
+            .flat_map(|rule| rule.boxes.iter().copied())
+            .collect()
+    }
+
+    pub fn get(self, property: B::Property) -> Option<&'static str>{
+        self.properties().into_iter().find(|(name, _)| *name == property).map(|(_, value)| value)
+    }
+
+    // get for code that works with every version and can't name the PropertyName
+    pub fn get_by_name(self, property: &str) -> Option<&'static str>{
+        self.properties().into_iter().find(|(name, _)| B::property_name(*name) == property).map(|(_, value)| value)
+    }
+
+    // the same block with one property changed, None if the block doesn't have it or the value is wrong
+    pub fn with(self, property: B::Property, value: &str) -> Option<Self>{
+        let block = self.block()?;
+        let mut offset = 0;
+        let mut found = false;
+        for ((name, values), (_, current)) in block.info().properties.iter().zip(self.properties()){
+            let value = if *name == property{
+                found = true;
+                value
+            } else {
+                current
+            };
+            offset = offset * values.len() + values.iter().position(|other| *other == value)?;
+        }
+        found.then(|| GenericBlockState::new(block.info().min_state + offset as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn ids_follow_the_version(){
+        // 1.20.3 put breeze in at 10 and wind_charge after warden, everything after moved up
+        assert_eq!(EntityType::from_version_id(ProtocolVersion::V1_20_1, 10), Some(EntityType::Camel));
+        assert_eq!(EntityType::from_version_id(ProtocolVersion::V1_20_4, 10), Some(EntityType::Breeze));
+        assert_eq!(EntityType::from_version_id(ProtocolVersion::V1_20_4, 11), Some(EntityType::Camel));
+        assert_eq!(EntityType::Player.version_id(ProtocolVersion::V1_19_4), Some(122));
+        assert_eq!(EntityType::Player.version_id(ProtocolVersion::V1_20_4), Some(124));
+        assert_eq!(EntityType::WindCharge.version_id(ProtocolVersion::V1_20_2), None);
+        assert_eq!(EntityType::from_version_id(ProtocolVersion::V1_20_1, 124), None);
+
+        assert_eq!(Particle::from_version_id(ProtocolVersion::V1_20_1, 24), Some(Particle::SonicBoom));
+        assert_eq!(Particle::from_version_id(ProtocolVersion::V1_20_4, 24), Some(Particle::Gust));
+        assert_eq!(Particle::EggCrack.version_id(ProtocolVersion::V1_19_4), None);
+        assert_eq!(Particle::EggCrack.version_id(ProtocolVersion::V1_20_2), Some(94));
+    }
+
+    #[test]
+    fn every_version_has_a_registry(){
+        for &version in ProtocolVersion::ALL{
+            let ids: Vec<u32> = EntityType::ALL.iter().filter_map(|kind| kind.version_id(version)).collect();
+            assert!(ids.len() >= 124, "{} has {} entity types", version, ids.len());
+            for (expected, id) in ids.iter().enumerate(){
+                assert_eq!(*id, expected as u32, "entity type IDs of {} have a gap", version);
+                assert_eq!(EntityType::from_version_id(version, *id).and_then(|kind| kind.version_id(version)), Some(*id));
+            }
+            assert!(Particle::ALL.iter().any(|particle| particle.version_id(version).is_some()));
+        }
+    }
+
+    #[test]
+    fn block_state_properties(){
+        use v1_20_1::{Block, BlockState, PropertyName};
+
+        let snowy = BlockState::new(8);
+        assert_eq!(snowy.block(), Some(Block::GrassBlock));
+        assert_eq!(snowy.properties(), vec![(PropertyName::Snowy, "true")]);
+        assert!(!snowy.is_default());
+        let plain = snowy.with(PropertyName::Snowy, "false").unwrap();
+        assert_eq!(plain.id(), 9);
+        assert!(plain.is_default());
+        assert_eq!(plain.with(PropertyName::Snowy, "maybe"), None);
+        assert_eq!(BlockState::new(1).with(PropertyName::Snowy, "true"), None);
+
+        assert_eq!(Block::from_name("stone"), Some(Block::Stone));
+        assert_eq!(Block::Stone.display_name(), "Stone");
+        assert_eq!(BlockState::new(1).collision_boxes(), vec![[0.0, 0.0, 0.0, 1.0, 1.0, 1.0]]);
+        assert!(BlockState::new(0).collision_boxes().is_empty());
+    }
+}
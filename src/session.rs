@@ -0,0 +1,234 @@
+use {
+    std::time::Duration,
+    crate::codec::ProtocolError,
+};
+
+pub const DEFAULT_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+// what we need to join an online mode server
+#[derive(Debug, Clone)]
+pub struct Account{
+    pub username: String,
+    pub uuid: u128,
+    pub access_token: String,
+}
+
+// the server that checks our access token before an online mode server lets us in,
+// the url can be pointed somewhere else for testing or for third party auth servers
+#[derive(Debug, Clone)]
+pub struct SessionServer{
+    url: String,
+    timeout: Duration,
+}
+
+impl Default for SessionServer{
+    fn default() -> Self{
+        SessionServer::new(DEFAULT_SESSION_SERVER)
+    }
+}
+
+impl SessionServer{
+    pub fn new(url: &str) -> Self{
+        SessionServer{url: url.trim_end_matches('/').to_string(), timeout: Duration::from_secs(10)}
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self{
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn url(&self) -> &str{
+        &self.url
+    }
+
+    // tells the session server we are about to join the server with this hash (see encryption::server_hash)
+    pub fn join(&self, account: &Account, server_hash: &str) -> Result<(), ProtocolError>{
+        let body = serde_json::json!({
+            "accessToken": account.access_token,
+            "selectedProfile": format!("{:032x}", account.uuid),
+            "serverId": server_hash,
+        });
+
+        let response = ureq::post(&format!("{}/session/minecraft/join", self.url))
+            .timeout(self.timeout)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string());
+
+        match response{
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                Err(ProtocolError::Auth(format!("session server refused the join ({}): {}", code, text)))
+            },
+            Err(err) => Err(ProtocolError::Auth(format!("could not reach the session server: {}", err))),
+        }
+    }
+}
+
+// a tiny http server for tests of the code that talks to mojang and microsoft,
+// it hands every request to the handler and answers with whatever status and body it returns
+#[cfg(test)]
+pub(crate) mod stub{
+    use {
+        serde_json::Value,
+        std::{
+            io::{
+                BufRead,
+                BufReader,
+                Read,
+                Write,
+            },
+            net::TcpListener,
+            sync::{
+                Arc,
+                Mutex,
+            },
+            thread,
+        },
+    };
+
+    #[derive(Debug, Clone)]
+    pub(crate) struct Request{
+        pub method: String,
+        pub path: String,
+        pub headers: Vec<(String, String)>,
+        pub body: String,
+    }
+
+    impl Request{
+        pub fn header(&self, name: &str) -> Option<&str>{
+            self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+        }
+
+        pub fn json(&self) -> Value{
+            serde_json::from_str(&self.body).unwrap_or(Value::Null)
+        }
+
+        // a field of an application/x-www-form-urlencoded body
+        pub fn form(&self, field: &str) -> Option<String>{
+            self.body.split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == field)
+                .map(|(_, value)| value.replace("%3A", ":").replace("%2F", "/").replace('+', " "))
+        }
+    }
+
+    pub(crate) struct HttpStub{
+        pub url: String,
+        requests: Arc<Mutex<Vec<Request>>>,
+    }
+
+    impl HttpStub{
+        pub fn serve<F: Fn(&Request) -> (u16, String) + Send + 'static>(handler: F) -> Self{
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+
+            let log = requests.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming(){
+                    let mut stream = match stream{
+                        Ok(stream) => stream,
+                        Err(_) => return,
+                    };
+                    let request = match read_request(&mut stream){
+                        Some(request) => request,
+                        None => continue,
+                    };
+                    let (status, body) = handler(&request);
+                    log.lock().unwrap().push(request);
+                    let _ = write!(stream,
+                        "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status, body.len(), body,
+                    );
+                }
+            });
+
+            HttpStub{url, requests}
+        }
+
+        pub fn requests(&self) -> Vec<Request>{
+            self.requests.lock().unwrap().clone()
+        }
+
+        pub fn paths(&self) -> Vec<String>{
+            self.requests().into_iter().map(|request| request.path).collect()
+        }
+    }
+
+    fn read_request<S: Read>(stream: S) -> Option<Request>{
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let path = parts.next()?.to_string();
+
+        let mut headers = Vec::new();
+        loop{
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+            match line.trim_end().split_once(':'){
+                Some((key, value)) => headers.push((key.trim().to_string(), value.trim().to_string())),
+                None => break,
+            }
+        }
+
+        let length = headers.iter()
+            .find(|(key, _): &&(String, String)| key.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).ok()?;
+
+        Some(Request{method, path, headers, body: String::from_utf8_lossy(&body).into_owned()})
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        stub::HttpStub,
+    };
+
+    fn account() -> Account{
+        Account{username: "bot".to_string(), uuid: 0x069a79f444e94726a5befca90e38aaf5, access_token: "token".to_string()}
+    }
+
+    #[test]
+    fn join(){
+        let server = HttpStub::serve(|_| (204, String::new()));
+        SessionServer::new(&format!("{}/", server.url)).join(&account(), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1").unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/session/minecraft/join");
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        assert_eq!(requests[0].json(), serde_json::json!({
+            "accessToken": "token",
+            "selectedProfile": "069a79f444e94726a5befca90e38aaf5",
+            "serverId": "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1",
+        }));
+    }
+
+    #[test]
+    fn refused_join(){
+        let server = HttpStub::serve(|_| (403, r#"{"error":"ForbiddenOperationException","errorMessage":"Invalid token"}"#.to_string()));
+        match SessionServer::new(&server.url).join(&account(), "0"){
+            Err(ProtocolError::Auth(message)) => {
+                assert!(message.contains("403"), "{}", message);
+                assert!(message.contains("Invalid token"), "{}", message);
+            },
+            other => panic!("expected an auth error, got {:?}", other),
+        }
+
+        // nothing listening at all
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+        let session = SessionServer::new(&url).with_timeout(Duration::from_secs(2));
+        assert!(matches!(session.join(&account(), "0"), Err(ProtocolError::Auth(message)) if message.contains("could not reach")));
+    }
+}
@@ -0,0 +1,142 @@
+use {
+    std::{
+        collections::HashMap,
+        time::Duration,
+    },
+    crate::{
+        chat::LastSeenMessages,
+        codec::ConnectionState,
+        entity::EntityTracker,
+        packets::{
+            ChatSession,
+            JoinGame,
+            PlayerInfoEntry,
+            Property,
+        },
+        nbt::Tag,
+        pathfinder::Navigator,
+        physics::Physics,
+        player::LocalPlayer,
+        world::World,
+    }
+};
+
+// someone on the tab list
+#[derive(Debug, Clone)]
+pub struct PlayerInfo{
+    pub uuid: u128,
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub chat_session: Option<ChatSession>,
+    pub game_mode: i32,
+    pub listed: bool,
+    pub latency: i32,
+    pub display_name: Option<String>,
+}
+
+impl PlayerInfo{
+    pub fn new(uuid: u128) -> Self{
+        PlayerInfo{
+            uuid,
+            name: String::new(),
+            properties: Vec::new(),
+            chat_session: None,
+            game_mode: 0,
+            listed: false,
+            latency: -1, // not measured yet
+            display_name: None,
+        }
+    }
+
+    // applies whatever fields a Player Info Update entry carried
+    pub fn update(&mut self, entry: &PlayerInfoEntry){
+        if let Some(name) = &entry.name{
+            self.name = name.clone();
+        }
+        if let Some(properties) = &entry.properties{
+            self.properties = properties.clone();
+        }
+        if let Some(chat_session) = &entry.chat_session{
+            self.chat_session = chat_session.clone();
+        }
+        if let Some(game_mode) = entry.game_mode{
+            self.game_mode = game_mode;
+        }
+        if let Some(listed) = entry.listed{
+            self.listed = listed;
+        }
+        if let Some(latency) = entry.latency{
+            self.latency = latency;
+        }
+        if let Some(display_name) = &entry.display_name{
+            self.display_name = display_name.clone();
+        }
+    }
+}
+
+// everything the client knows about the game, kept up to date by the play loop.
+// get at it through Client::state
+#[derive(Debug)]
+pub struct ClientState{
+    pub uuid: u128,
+    pub connection_state: ConnectionState, // Configuration or Play
+    pub join_game: Option<JoinGame>, // None until the server sent Login (play)
+    pub registry_codec: Option<Tag>, // from Login (play), or the configuration state since 1.20.2
+    pub feature_flags: Vec<String>,
+    pub tags: HashMap<String, HashMap<String, Vec<i32>>>, // see packets::UpdateTags
+    pub players: HashMap<u128, PlayerInfo>,
+    pub last_seen: LastSeenMessages, // the signed messages our chat messages acknowledge
+    pub world: World,
+    pub entities: EntityTracker,
+    pub player: Option<LocalPlayer>, // None until the server told us where we are
+    pub physics: Physics,
+    pub navigator: Navigator, // sets player.inputs while it has a goal
+}
+
+impl ClientState{
+    pub fn new(uuid: u128) -> Self{
+        ClientState{
+            uuid,
+            connection_state: ConnectionState::Play,
+            join_game: None,
+            registry_codec: None,
+            feature_flags: Vec::new(),
+            tags: HashMap::new(),
+            players: HashMap::new(),
+            last_seen: LastSeenMessages::default(),
+            world: World::default(),
+            entities: EntityTracker::new(),
+            player: None,
+            physics: Physics::default(),
+            navigator: Navigator::default(),
+        }
+    }
+
+    // the round trip time the server measured with its keep alives, as it put it in our own tab
+    // list entry. the server averages it and only sends it every so often, so it lags behind.
+    // None until the server told us about it
+    pub fn tab_list_latency(&self) -> Option<Duration>{
+        let player = self.players.get(&self.uuid)?;
+        if player.latency < 0{
+            return None;
+        }
+        Some(Duration::from_millis(player.latency as u64))
+    }
+
+    // our own entity ID, known once we joined the game
+    pub fn entity_id(&self) -> Option<i32>{
+        self.join_game.as_ref().map(|join_game| join_game.entity_id)
+    }
+
+    pub fn player_name(&self, uuid: u128) -> Option<&str>{
+        self.players.get(&uuid).map(|player| player.name.as_str())
+    }
+
+    // an empty world with the height the registry codec gives the dimension type
+    pub fn dimension_world(&self, dimension_type: &str) -> World{
+        match &self.registry_codec{
+            Some(codec) => World::for_dimension(codec, dimension_type),
+            None => World::default(),
+        }
+    }
+}
@@ -0,0 +1,892 @@
+use {
+    std::fmt,
+    serde_json::{
+        Map,
+        Value,
+    },
+    crate::{
+        codec::ProtocolError,
+        nbt::Tag,
+        registry,
+        version::ProtocolVersion,
+    },
+};
+
+// the 16 colors chat had before hex colors, in the order of their legacy § codes 0-f
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedColor{
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl NamedColor{
+    pub const ALL: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::DarkBlue,
+        NamedColor::DarkGreen,
+        NamedColor::DarkAqua,
+        NamedColor::DarkRed,
+        NamedColor::DarkPurple,
+        NamedColor::Gold,
+        NamedColor::Gray,
+        NamedColor::DarkGray,
+        NamedColor::Blue,
+        NamedColor::Green,
+        NamedColor::Aqua,
+        NamedColor::Red,
+        NamedColor::LightPurple,
+        NamedColor::Yellow,
+        NamedColor::White,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self>{
+        NamedColor::ALL.into_iter().find(|color| color.name() == name)
+    }
+
+    pub fn from_code(code: char) -> Option<Self>{
+        code.to_digit(16).and_then(|index| NamedColor::ALL.get(index as usize)).copied()
+    }
+
+    pub fn name(self) -> &'static str{
+        match self{
+            NamedColor::Black => "black",
+            NamedColor::DarkBlue => "dark_blue",
+            NamedColor::DarkGreen => "dark_green",
+            NamedColor::DarkAqua => "dark_aqua",
+            NamedColor::DarkRed => "dark_red",
+            NamedColor::DarkPurple => "dark_purple",
+            NamedColor::Gold => "gold",
+            NamedColor::Gray => "gray",
+            NamedColor::DarkGray => "dark_gray",
+            NamedColor::Blue => "blue",
+            NamedColor::Green => "green",
+            NamedColor::Aqua => "aqua",
+            NamedColor::Red => "red",
+            NamedColor::LightPurple => "light_purple",
+            NamedColor::Yellow => "yellow",
+            NamedColor::White => "white",
+        }
+    }
+
+    // the character after § that picks this color
+    pub fn code(self) -> char{
+        std::char::from_digit(self as u32, 16).unwrap()
+    }
+
+    pub fn rgb(self) -> u32{
+        match self{
+            NamedColor::Black => 0x000000,
+            NamedColor::DarkBlue => 0x0000aa,
+            NamedColor::DarkGreen => 0x00aa00,
+            NamedColor::DarkAqua => 0x00aaaa,
+            NamedColor::DarkRed => 0xaa0000,
+            NamedColor::DarkPurple => 0xaa00aa,
+            NamedColor::Gold => 0xffaa00,
+            NamedColor::Gray => 0xaaaaaa,
+            NamedColor::DarkGray => 0x555555,
+            NamedColor::Blue => 0x5555ff,
+            NamedColor::Green => 0x55ff55,
+            NamedColor::Aqua => 0x55ffff,
+            NamedColor::Red => 0xff5555,
+            NamedColor::LightPurple => 0xff55ff,
+            NamedColor::Yellow => 0xffff55,
+            NamedColor::White => 0xffffff,
+        }
+    }
+
+    // the matching one of the 16 terminal colors, so they follow the terminal's theme
+    fn ansi(self) -> u8{
+        [30, 34, 32, 36, 31, 35, 33, 37, 90, 94, 92, 96, 91, 95, 93, 97][self as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextColor{
+    Named(NamedColor),
+    Rgb(u32),
+}
+
+impl TextColor{
+    // "red" or "#ff5555"
+    pub fn parse(color: &str) -> Option<Self>{
+        match color.strip_prefix('#'){
+            Some(hex) if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => u32::from_str_radix(hex, 16).ok().map(TextColor::Rgb),
+            Some(_) => None,
+            None => NamedColor::from_name(color).map(TextColor::Named),
+        }
+    }
+
+    pub fn rgb(self) -> u32{
+        match self{
+            TextColor::Named(color) => color.rgb(),
+            TextColor::Rgb(rgb) => rgb,
+        }
+    }
+
+    // legacy codes only have the named colors, a hex color gets the closest one
+    pub fn to_named(self) -> NamedColor{
+        match self{
+            TextColor::Named(color) => color,
+            TextColor::Rgb(rgb) => {
+                let channels = |rgb: u32| [(rgb >> 16) as i32 & 0xff, (rgb >> 8) as i32 & 0xff, rgb as i32 & 0xff];
+                let [r, g, b] = channels(rgb);
+                NamedColor::ALL.into_iter().min_by_key(|color| {
+                    let [cr, cg, cb] = channels(color.rgb());
+                    (r - cr).pow(2) + (g - cg).pow(2) + (b - cb).pow(2)
+                }).unwrap()
+            },
+        }
+    }
+}
+
+// what clicking the text does. action is open_url, run_command, suggest_command, change_page,
+// copy_to_clipboard or open_file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClickEvent{
+    pub action: String,
+    pub value: String,
+}
+
+// what shows up when hovering over the text
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoverEvent{
+    ShowText(Box<TextComponent>),
+    ShowItem{id: String, count: i32, tag: Option<String>}, // tag is snbt
+    ShowEntity{kind: String, id: u128, name: Option<Box<TextComponent>>},
+}
+
+// anything left None comes from the parent component
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style{
+    pub color: Option<TextColor>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub insertion: Option<String>, // put in the chat box on shift click
+    pub font: Option<String>,
+    pub click_event: Option<Box<ClickEvent>>,
+    pub hover_event: Option<Box<HoverEvent>>,
+}
+
+impl Style{
+    // this style with what it leaves unset taken from parent, the way children inherit
+    pub fn inherit(&self, parent: &Style) -> Style{
+        Style{
+            color: self.color.or(parent.color),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            click_event: self.click_event.clone().or_else(|| parent.click_event.clone()),
+            hover_event: self.hover_event.clone().or_else(|| parent.hover_event.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content{
+    Text(String),
+    // args fill in the %s in the translation
+    Translate{key: String, fallback: Option<String>, args: Vec<TextComponent>},
+    // value is filled in by the server, a score without one shows nothing
+    Score{name: String, objective: String, value: Option<String>},
+    // the server turns selectors into the names they match before sending, this is for the
+    // ones it doesn't
+    Selector{pattern: String, separator: Option<Box<TextComponent>>},
+    // a key binding like key.jump, shows the key it's bound to
+    Keybind(String),
+}
+
+// a chat component, what every bit of text the server sends is made of. parses the json
+// and (since 1.20.3) nbt forms, renders to plain text, terminal colors or legacy § codes
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextComponent{
+    pub content: Content,
+    pub style: Style,
+    pub extra: Vec<TextComponent>, // appended after content, inheriting its style
+}
+
+impl Default for TextComponent{
+    fn default() -> Self{
+        TextComponent::text("")
+    }
+}
+
+// plain text, translated for ProtocolVersion::DEFAULT. to_plain takes the version the text came from
+impl fmt::Display for TextComponent{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        f.write_str(&self.to_plain(ProtocolVersion::DEFAULT))
+    }
+}
+
+impl TextComponent{
+    pub fn text(text: impl Into<String>) -> Self{
+        TextComponent{
+            content: Content::Text(text.into()),
+            style: Style::default(),
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn translate(key: impl Into<String>, args: Vec<TextComponent>) -> Self{
+        TextComponent{
+            content: Content::Translate{key: key.into(), fallback: None, args},
+            style: Style::default(),
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProtocolError>{
+        let value: Value = serde_json::from_str(json).map_err(|err| ProtocolError::InvalidValue(format!("chat component: {}", err)))?;
+        TextComponent::from_value(&value)
+    }
+
+    // nbt chat is the json one with bools as 0 / 1 bytes and lists of mixed types wrapped
+    // in {"": value} compounds, from_value copes with both
+    pub fn from_nbt(tag: &Tag) -> Result<Self, ProtocolError>{
+        let value = serde_json::to_value(tag).map_err(|err| ProtocolError::InvalidValue(format!("chat component: {}", err)))?;
+        TextComponent::from_value(&value)
+    }
+
+    // a string is plain text, an array is its first element with the rest as extra
+    pub fn from_value(value: &Value) -> Result<Self, ProtocolError>{
+        match value{
+            Value::String(text) => Ok(TextComponent::text(text.as_str())),
+            Value::Number(number) => Ok(TextComponent::text(number.to_string())),
+            Value::Bool(value) => Ok(TextComponent::text(value.to_string())),
+            Value::Array(items) => {
+                let (first, rest) = items.split_first()
+                    .ok_or_else(|| ProtocolError::InvalidValue("empty chat component list".to_string()))?;
+                let mut component = TextComponent::from_value(first)?;
+                for item in rest{
+                    component.extra.push(TextComponent::from_value(item)?);
+                }
+                Ok(component)
+            },
+            Value::Object(object) => TextComponent::from_object(object),
+            Value::Null => Err(ProtocolError::InvalidValue("chat component is null".to_string())),
+        }
+    }
+
+    fn from_object(object: &Map<String, Value>) -> Result<Self, ProtocolError>{
+        if object.len() == 1{
+            if let Some(value) = object.get(""){
+                return TextComponent::from_value(value);
+            }
+        }
+
+        let string = |name: &str| object.get(name).and_then(primitive);
+        let content = if let Some(text) = string("text"){
+            Content::Text(text)
+        }else if let Some(key) = string("translate"){
+            let args = match object.get("with"){
+                Some(Value::Array(args)) => args.iter().map(TextComponent::from_value).collect::<Result<_, _>>()?,
+                _ => Vec::new(),
+            };
+            Content::Translate{key, fallback: string("fallback"), args}
+        }else if let Some(score) = object.get("score"){
+            Content::Score{
+                name: score.get("name").and_then(primitive).unwrap_or_default(),
+                objective: score.get("objective").and_then(primitive).unwrap_or_default(),
+                value: score.get("value").and_then(primitive),
+            }
+        }else if let Some(pattern) = string("selector"){
+            let separator = object.get("separator").map(TextComponent::from_value).transpose()?;
+            Content::Selector{pattern, separator: separator.map(Box::new)}
+        }else if let Some(key) = string("keybind"){
+            Content::Keybind(key)
+        }else{
+            return Err(ProtocolError::InvalidValue(format!("chat component without content: {}", Value::Object(object.clone()))));
+        };
+
+        let extra = match object.get("extra"){
+            Some(Value::Array(extra)) => extra.iter().map(TextComponent::from_value).collect::<Result<_, _>>()?,
+            _ => Vec::new(),
+        };
+        Ok(TextComponent{content, style: parse_style(object)?, extra})
+    }
+
+    // without any formatting, § codes in the text taken out too. translations come from the
+    // version's language table
+    pub fn to_plain(&self, version: ProtocolVersion) -> String{
+        self.segments(version).into_iter().map(|(text, _)| text).collect()
+    }
+
+    // with escape codes for a terminal, hex colors need one that does 24 bit color
+    pub fn to_ansi(&self, version: ProtocolVersion) -> String{
+        let mut out = String::new();
+        let mut current = Format::default();
+        for (text, format) in self.segments(version){
+            if format != current{
+                if current != Format::default(){
+                    out.push_str("\x1b[0m");
+                }
+                out.push_str(&format.ansi());
+                current = format;
+            }
+            out.push_str(&text);
+        }
+        if current != Format::default(){
+            out.push_str("\x1b[0m");
+        }
+        out
+    }
+
+    // with § codes, the way chat was before components. a color code turns bold and the rest
+    // off again, so the flags come after it
+    pub fn to_legacy(&self, version: ProtocolVersion) -> String{
+        let mut out = String::new();
+        let mut current = Format::default();
+        for (text, format) in self.segments(version){
+            if format != current{
+                match format.color{
+                    Some(color) => {
+                        out.push('§');
+                        out.push(color.to_named().code());
+                    },
+                    None if current != Format::default() => out.push_str("§r"),
+                    None => {},
+                }
+                out.push_str(&format.legacy());
+                current = format;
+            }
+            out.push_str(&text);
+        }
+        out
+    }
+
+    // the text it shows in order, each with the formatting it's shown with
+    fn segments(&self, version: ProtocolVersion) -> Vec<(String, Format)>{
+        let mut segments = Vec::new();
+        self.visit(version, Format::default(), &mut segments);
+        segments
+    }
+
+    fn visit(&self, version: ProtocolVersion, parent: Format, out: &mut Vec<(String, Format)>){
+        let format = parent.apply(&self.style);
+        match &self.content{
+            Content::Text(text) => push_formatted(text, format, out),
+            Content::Translate{key, fallback, args} => {
+                let template = translation(version, key, fallback.as_deref());
+                match decompose(&template, args.len()){
+                    Some(parts) => for part in parts{
+                        match part{
+                            Part::Literal(text) => push_formatted(text, format, out),
+                            Part::Arg(index) => args[index].visit(version, format, out),
+                        }
+                    },
+                    // vanilla shows a broken translation as is
+                    None => push_formatted(&template, format, out),
+                }
+            },
+            Content::Score{value, ..} => push_formatted(value.as_deref().unwrap_or(""), format, out),
+            Content::Selector{pattern, ..} => push_formatted(pattern, format, out),
+            Content::Keybind(key) => {
+                let bound = DEFAULT_KEYS.iter().find(|(name, _)| name == key).map(|&(_, bound)| bound);
+                push_formatted(bound.unwrap_or(key), format, out);
+            },
+        }
+        for child in &self.extra{
+            child.visit(version, format, out);
+        }
+    }
+}
+
+// a key from the en_us.json bundled for the version, see reports/<version>/en_us.json. a version
+// without one uses the closest version's
+pub fn translate(version: ProtocolVersion, key: &str) -> Option<&'static str>{
+    let language = registry::language(version);
+    language.binary_search_by_key(&key, |&(key, _)| key).ok().map(|index| language[index].1)
+}
+
+// what a translate component shows: the translation, the fallback it came with, a made up
+// name for blocks, items and entities or else the key itself, like vanilla
+fn translation(version: ProtocolVersion, key: &str, fallback: Option<&str>) -> String{
+    if let Some(text) = translate(version, key).or(fallback){
+        return text.to_string();
+    }
+    ["block.minecraft.", "item.minecraft.", "entity.minecraft."].iter()
+        .find_map(|prefix| key.strip_prefix(prefix))
+        .map(registry::display_name)
+        .unwrap_or_else(|| key.to_string())
+}
+
+// the default controls, what a keybind component shows when nobody rebound anything
+const DEFAULT_KEYS: &[(&str, &str)] = &[
+    ("key.advancements", "L"),
+    ("key.attack", "Left Button"),
+    ("key.back", "S"),
+    ("key.chat", "T"),
+    ("key.command", "/"),
+    ("key.drop", "Q"),
+    ("key.forward", "W"),
+    ("key.fullscreen", "F11"),
+    ("key.hotbar.1", "1"),
+    ("key.hotbar.2", "2"),
+    ("key.hotbar.3", "3"),
+    ("key.hotbar.4", "4"),
+    ("key.hotbar.5", "5"),
+    ("key.hotbar.6", "6"),
+    ("key.hotbar.7", "7"),
+    ("key.hotbar.8", "8"),
+    ("key.hotbar.9", "9"),
+    ("key.inventory", "E"),
+    ("key.jump", "Space"),
+    ("key.left", "A"),
+    ("key.loadToolbarActivator", "X"),
+    ("key.pickItem", "Middle Button"),
+    ("key.playerlist", "Tab"),
+    ("key.right", "D"),
+    ("key.saveToolbarActivator", "C"),
+    ("key.screenshot", "F2"),
+    ("key.sneak", "Left Shift"),
+    ("key.socialInteractions", "P"),
+    ("key.sprint", "Left Control"),
+    ("key.swapOffhand", "F"),
+    ("key.togglePerspective", "F5"),
+    ("key.use", "Right Button"),
+];
+
+enum Part<'a>{
+    Literal(&'a str),
+    Arg(usize),
+}
+
+// splits a translation at its placeholders: %s is the next argument, %2$s the second one and
+// %% a percent sign. None for anything else, or an argument that isn't there
+fn decompose(template: &str, args: usize) -> Option<Vec<Part<'_>>>{
+    let mut parts = Vec::new();
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('%'){
+        if start > 0{
+            parts.push(Part::Literal(&rest[..start]));
+        }
+        let spec = &rest[start + 1..];
+        if let Some(after) = spec.strip_prefix('%'){
+            parts.push(Part::Literal("%"));
+            rest = after;
+            continue;
+        }
+        let digits = spec.bytes().take_while(u8::is_ascii_digit).count();
+        let (index, after) = match spec[digits..].strip_prefix('$'){
+            Some(after) if digits > 0 => (spec[..digits].parse::<usize>().ok()?.checked_sub(1)?, after),
+            _ => {
+                next += 1;
+                (next - 1, spec)
+            },
+        };
+        rest = after.strip_prefix('s')?;
+        if index >= args{
+            return None;
+        }
+        parts.push(Part::Arg(index));
+    }
+    if !rest.is_empty(){
+        parts.push(Part::Literal(rest));
+    }
+    Some(parts)
+}
+
+// how text looks, the part of a style that's inherited down to what gets rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format{
+    color: Option<TextColor>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+    obfuscated: bool,
+}
+
+impl Format{
+    fn apply(self, style: &Style) -> Format{
+        Format{
+            color: style.color.or(self.color),
+            bold: style.bold.unwrap_or(self.bold),
+            italic: style.italic.unwrap_or(self.italic),
+            underlined: style.underlined.unwrap_or(self.underlined),
+            strikethrough: style.strikethrough.unwrap_or(self.strikethrough),
+            obfuscated: style.obfuscated.unwrap_or(self.obfuscated),
+        }
+    }
+
+    // obfuscated text is scrambled in game, blinking is the closest a terminal has
+    fn ansi(self) -> String{
+        let mut codes = Vec::new();
+        match self.color{
+            Some(TextColor::Named(color)) => codes.push(color.ansi().to_string()),
+            Some(TextColor::Rgb(rgb)) => codes.push(format!("38;2;{};{};{}", rgb >> 16, (rgb >> 8) & 0xff, rgb & 0xff)),
+            None => {},
+        }
+        for (set, code) in [(self.bold, "1"), (self.italic, "3"), (self.underlined, "4"), (self.obfuscated, "5"), (self.strikethrough, "9")]{
+            if set{
+                codes.push(code.to_string());
+            }
+        }
+        if codes.is_empty(){
+            return String::new();
+        }
+        format!("\x1b[{}m", codes.join(";"))
+    }
+
+    // the § codes for the flags, the color is up to the caller
+    fn legacy(self) -> String{
+        [(self.obfuscated, "§k"), (self.bold, "§l"), (self.strikethrough, "§m"), (self.underlined, "§n"), (self.italic, "§o")].iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, code)| *code)
+            .collect()
+    }
+}
+
+// text can still have § codes in it (server list messages love them), the vanilla client
+// draws those on top of the component's style: a color resets the flags, §r goes back to the
+// component's style and a code it doesn't know is dropped
+fn push_formatted(text: &str, base: Format, out: &mut Vec<(String, Format)>){
+    let mut format = base;
+    let mut chars = text.chars();
+    let mut start = 0;
+    let mut end = 0;
+    while let Some(c) = chars.next(){
+        if c != '§'{
+            end += c.len_utf8();
+            continue;
+        }
+        push_segment(&text[start..end], format, out);
+        let code = chars.next();
+        end += c.len_utf8() + code.map_or(0, char::len_utf8);
+        start = end;
+        match code.map(|code| code.to_ascii_lowercase()){
+            Some('r') => format = base,
+            Some('k') => format.obfuscated = true,
+            Some('l') => format.bold = true,
+            Some('m') => format.strikethrough = true,
+            Some('n') => format.underlined = true,
+            Some('o') => format.italic = true,
+            Some(code) => if let Some(color) = NamedColor::from_code(code){
+                format = Format{color: Some(TextColor::Named(color)), ..Format::default()};
+            },
+            None => {},
+        }
+    }
+    push_segment(&text[start..end], format, out);
+}
+
+// next to the last segment if it looks the same
+fn push_segment(text: &str, format: Format, out: &mut Vec<(String, Format)>){
+    if text.is_empty(){
+        return;
+    }
+    match out.last_mut(){
+        Some((last, last_format)) if *last_format == format => last.push_str(text),
+        _ => out.push((text.to_string(), format)),
+    }
+}
+
+fn parse_style(object: &Map<String, Value>) -> Result<Style, ProtocolError>{
+    let flag = |name: &str| match object.get(name){
+        Some(Value::Bool(value)) => Some(*value),
+        Some(Value::Number(value)) => value.as_f64().map(|value| value != 0.0),
+        _ => None,
+    };
+    let string = |name: &str| object.get(name).and_then(Value::as_str).map(str::to_string);
+
+    let click_event = object.get("clickEvent").and_then(|event| Some(Box::new(ClickEvent{
+        action: event.get("action")?.as_str()?.to_string(),
+        value: event.get("value").and_then(primitive)?,
+    })));
+    let hover_event = match object.get("hoverEvent"){
+        Some(event) => parse_hover_event(event)?.map(Box::new),
+        None => None,
+    };
+    Ok(Style{
+        color: object.get("color").and_then(Value::as_str).and_then(TextColor::parse),
+        bold: flag("bold"),
+        italic: flag("italic"),
+        underlined: flag("underlined"),
+        strikethrough: flag("strikethrough"),
+        obfuscated: flag("obfuscated"),
+        insertion: string("insertion"),
+        font: string("font"),
+        click_event,
+        hover_event,
+    })
+}
+
+// items and entities in the old "value" form are snbt strings, those come out as None
+fn parse_hover_event(event: &Value) -> Result<Option<HoverEvent>, ProtocolError>{
+    let contents = event.get("contents");
+    Ok(match (event.get("action").and_then(Value::as_str), contents){
+        (Some("show_text"), _) => match contents.or_else(|| event.get("value")){
+            Some(text) => Some(HoverEvent::ShowText(Box::new(TextComponent::from_value(text)?))),
+            None => None,
+        },
+        (Some("show_item"), Some(Value::String(id))) => Some(HoverEvent::ShowItem{id: id.clone(), count: 1, tag: None}),
+        (Some("show_item"), Some(item)) => item.get("id").and_then(Value::as_str).map(|id| HoverEvent::ShowItem{
+            id: id.to_string(),
+            count: item.get("count").and_then(Value::as_i64).unwrap_or(1) as i32,
+            tag: item.get("tag").and_then(Value::as_str).map(str::to_string),
+        }),
+        (Some("show_entity"), Some(entity)) => {
+            let name = entity.get("name").map(TextComponent::from_value).transpose()?;
+            match (entity.get("type").and_then(Value::as_str), entity.get("id").and_then(parse_uuid)){
+                (Some(kind), Some(id)) => Some(HoverEvent::ShowEntity{kind: kind.to_string(), id, name: name.map(Box::new)}),
+                _ => None,
+            }
+        },
+        _ => None,
+    })
+}
+
+// a hyphenated string in json, four ints in nbt
+fn parse_uuid(value: &Value) -> Option<u128>{
+    match value{
+        Value::String(uuid) => u128::from_str_radix(&uuid.replace('-', ""), 16).ok(),
+        Value::Array(ints) if ints.len() == 4 => ints.iter().try_fold(0u128, |uuid, int| {
+            Some((uuid << 32) | int.as_i64()? as u32 as u128)
+        }),
+        _ => None,
+    }
+}
+
+// strings, and the numbers and bools nbt hands out where json would have strings
+fn primitive(value: &Value) -> Option<String>{
+    match value{
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const VERSION: ProtocolVersion = ProtocolVersion::V1_20_1;
+
+    fn json(json: &str) -> TextComponent{
+        TextComponent::from_json(json).unwrap()
+    }
+
+    fn compound(entries: Vec<(&str, Tag)>) -> Tag{
+        Tag::Compound(entries.into_iter().map(|(name, tag)| (name.to_string(), tag)).collect())
+    }
+
+    #[test]
+    fn parses_strings_lists_and_objects(){
+        assert_eq!(json("\"hello\""), TextComponent::text("hello"));
+        assert_eq!(json("12").to_plain(VERSION), "12");
+        assert_eq!(json("true").to_plain(VERSION), "true");
+
+        // the first element of a list is the parent of the rest
+        let list = json(r#"[{"text": "a", "color": "red"}, "b", {"text": "c", "bold": true}]"#);
+        assert_eq!(list.style.color, Some(TextColor::Named(NamedColor::Red)));
+        assert_eq!(list.extra.len(), 2);
+        assert_eq!(list.to_plain(VERSION), "abc");
+
+        let object = json(r#"{"text": "x", "extra": [{"text": "y", "extra": ["z"]}]}"#);
+        assert_eq!(object.to_plain(VERSION), "xyz");
+        assert_eq!(object.to_string(), "xyz");
+    }
+
+    #[test]
+    fn parses_styles_and_events(){
+        let component = json(r##"{
+            "text": "click", "color": "#102030", "italic": false, "underlined": true, "insertion": "hi", "font": "minecraft:uniform",
+            "clickEvent": {"action": "run_command", "value": "/help"},
+            "hoverEvent": {"action": "show_text", "contents": {"text": "tip", "color": "gold"}},
+            "extra": [{"text": "child", "color": "blue"}]
+        }"##);
+        let style = &component.style;
+        assert_eq!(style.color, Some(TextColor::Rgb(0x102030)));
+        assert_eq!((style.bold, style.italic, style.underlined), (None, Some(false), Some(true)));
+        assert_eq!(style.insertion.as_deref(), Some("hi"));
+        assert_eq!(style.font.as_deref(), Some("minecraft:uniform"));
+        assert_eq!(style.click_event.as_deref(), Some(&ClickEvent{action: "run_command".to_string(), value: "/help".to_string()}));
+        let Some(HoverEvent::ShowText(tip)) = style.hover_event.as_deref() else{
+            panic!("not show_text: {:?}", style.hover_event);
+        };
+        assert_eq!(tip.to_plain(VERSION), "tip");
+        assert_eq!(tip.style.color, Some(TextColor::Named(NamedColor::Gold)));
+
+        let child = component.extra[0].style.inherit(&component.style);
+        assert_eq!(child.color, Some(TextColor::Named(NamedColor::Blue)));
+        assert_eq!(child.underlined, Some(true));
+        assert_eq!(child.click_event, component.style.click_event);
+
+        let item = json(r#"{"text": "", "hoverEvent": {"action": "show_item", "contents": {"id": "minecraft:stone", "count": 3, "tag": "{a:1b}"}}}"#);
+        assert_eq!(item.style.hover_event.as_deref(), Some(&HoverEvent::ShowItem{id: "minecraft:stone".to_string(), count: 3, tag: Some("{a:1b}".to_string())}));
+        let entity = json(r#"{"text": "", "hoverEvent": {"action": "show_entity", "contents": {"type": "minecraft:pig", "id": "00000001-0000-0002-0000-0003ffffffff", "name": "Pig"}}}"#);
+        let Some(HoverEvent::ShowEntity{kind, id, name}) = entity.style.hover_event.as_deref() else{
+            panic!("not show_entity: {:?}", entity.style.hover_event);
+        };
+        assert_eq!((kind.as_str(), *id), ("minecraft:pig", 0x00000001_00000002_00000003_ffffffff));
+        assert_eq!(name.as_ref().map(|name| name.to_plain(VERSION)).as_deref(), Some("Pig"));
+
+        // an unknown color is left unset rather than failing the whole component
+        assert_eq!(json(r#"{"text": "", "color": "reddish"}"#).style.color, None);
+    }
+
+    #[test]
+    fn parses_nbt(){
+        let tag = compound(vec![
+            ("text", Tag::String("a".to_string())),
+            ("bold", Tag::Byte(1)),
+            ("color", Tag::String("green".to_string())),
+            // mixed lists are compounds with the value under an empty name
+            ("extra", Tag::List(vec![
+                compound(vec![("", Tag::String("b".to_string()))]),
+                compound(vec![("text", Tag::String("c".to_string())), ("bold", Tag::Byte(0))]),
+            ])),
+            ("hoverEvent", compound(vec![
+                ("action", Tag::String("show_entity".to_string())),
+                ("contents", compound(vec![
+                    ("type", Tag::String("minecraft:zombie".to_string())),
+                    ("id", Tag::IntArray(vec![1, 2, 3, -1])),
+                ])),
+            ])),
+        ]);
+        let component = TextComponent::from_nbt(&tag).unwrap();
+        assert_eq!(component.style.bold, Some(true));
+        assert_eq!(component.style.color, Some(TextColor::Named(NamedColor::Green)));
+        assert_eq!(component.extra[1].style.bold, Some(false));
+        assert_eq!(component.to_plain(VERSION), "abc");
+        assert_eq!(component.style.hover_event.as_deref(), Some(&HoverEvent::ShowEntity{
+            kind: "minecraft:zombie".to_string(),
+            id: 0x00000001_00000002_00000003_ffffffff,
+            name: None,
+        }));
+
+        // a lone string tag is plain text, and translate arguments can be numbers
+        assert_eq!(TextComponent::from_nbt(&Tag::String("plain".to_string())).unwrap().to_plain(VERSION), "plain");
+        let translate = compound(vec![
+            ("translate", Tag::String("translation.test.args".to_string())),
+            ("with", Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+        ]);
+        assert_eq!(TextComponent::from_nbt(&translate).unwrap().to_plain(VERSION), "1 2");
+    }
+
+    #[test]
+    fn rejects_malformed_components(){
+        for bad in ["null", "[]", r#"{"color": "red"}"#, r#"{"text": "a", "extra": [null]}"#, "{", "not json"]{
+            assert!(TextComponent::from_json(bad).is_err(), "{} parsed", bad);
+        }
+        assert!(TextComponent::from_nbt(&compound(vec![("bold", Tag::Byte(1))])).is_err());
+    }
+
+    #[test]
+    fn fills_in_translations(){
+        let translate = |key: &str, args: &[&str]| TextComponent::translate(key, args.iter().map(|&arg| TextComponent::text(arg)).collect()).to_plain(VERSION);
+
+        assert_eq!(translate("translation.test.none", &[]), "Hello, world!");
+        assert_eq!(translate("chat.type.text", &["Steve", "hi"]), "<Steve> hi");
+        assert_eq!(translate("death.attack.arrow", &["Steve", "Skeleton"]), "Steve was shot by Skeleton");
+        // the strings vanilla tests its own formatter with
+        assert_eq!(translate("translation.test.complex", &["a", "b", "c"]), "Prefix, ab again b and a lastly c and also a again!");
+        assert_eq!(translate("translation.test.escape", &["A", "B"]), "%s %A %%s %%B");
+        assert_eq!(translate("translation.test.invalid", &["A"]), "hi %");
+        assert_eq!(translate("translation.test.invalid2", &["A"]), "hi %  s");
+        // too few arguments shows the translation as is
+        assert_eq!(translate("chat.type.text", &["Steve"]), "<%s> %s");
+
+        assert_eq!(translate("block.minecraft.oak_planks", &[]), "Oak Planks");
+        assert_eq!(translate("entity.minecraft.glow_squid", &[]), "Glow Squid");
+        assert_eq!(translate("no.such.key", &[]), "no.such.key");
+        assert_eq!(json(r#"{"translate": "no.such.key", "fallback": "Fallback %s", "with": ["x"]}"#).to_plain(VERSION), "Fallback x");
+        assert_eq!(json(r#"{"translate": "translation.test.none", "fallback": "ignored"}"#).to_plain(VERSION), "Hello, world!");
+
+        assert_eq!(translate("multiplayer.disconnect.kicked", &[]), "Kicked by an operator");
+        assert_eq!(super::translate(VERSION, "translation.test.world"), Some("world"));
+        assert_eq!(super::translate(VERSION, "no.such.key"), None);
+    }
+
+    #[test]
+    fn language_per_version(){
+        // only 1.20.1 has an en_us.json, the others borrow it
+        assert!(!registry::v1_20_1::LANGUAGE.is_empty());
+        assert_eq!(registry::language(ProtocolVersion::V1_20_1), registry::v1_20_1::LANGUAGE);
+        assert_eq!(registry::language(ProtocolVersion::V1_20_4), registry::v1_20_1::LANGUAGE);
+        for &version in ProtocolVersion::ALL{
+            let language = registry::language(version);
+            assert!(language.windows(2).all(|pair| pair[0].0 < pair[1].0), "{} isn't sorted", version);
+            assert_eq!(super::translate(version, "multiplayer.disconnect.kicked"), Some("Kicked by an operator"));
+            assert_eq!(TextComponent::translate("no.such.key", Vec::new()).to_plain(version), "no.such.key");
+        }
+    }
+
+    #[test]
+    fn renders_other_contents(){
+        assert_eq!(json(r#"{"keybind": "key.jump"}"#).to_plain(VERSION), "Space");
+        assert_eq!(json(r#"{"keybind": "key.custom"}"#).to_plain(VERSION), "key.custom");
+        assert_eq!(json(r#"{"score": {"name": "Steve", "objective": "kills", "value": "12"}}"#).to_plain(VERSION), "12");
+        assert_eq!(json(r#"{"score": {"name": "Steve", "objective": "kills"}}"#).to_plain(VERSION), "");
+        assert_eq!(json(r#"{"selector": "@p", "separator": ", "}"#).to_plain(VERSION), "@p");
+    }
+
+    #[test]
+    fn renders_plain_text_without_section_codes(){
+        assert_eq!(TextComponent::text("§cred §lbold§r plain§").to_plain(VERSION), "red bold plain");
+        assert_eq!(TextComponent::text("§zunknown §Cupper").to_plain(VERSION), "unknown upper");
+        assert_eq!(TextComponent::text("héllo §aw§örld").to_plain(VERSION), "héllo wrld");
+    }
+
+    #[test]
+    fn renders_ansi(){
+        assert_eq!(TextComponent::text("plain").to_ansi(VERSION), "plain");
+        assert_eq!(json(r#"["", {"text": "x", "color": "red"}, "y"]"#).to_ansi(VERSION), "\x1b[91mx\x1b[0my");
+        assert_eq!(json(r##"{"text": "z", "color": "#102030", "italic": true}"##).to_ansi(VERSION), "\x1b[38;2;16;32;48;3mz\x1b[0m");
+        assert_eq!(json(r#"{"text": "f", "bold": true, "underlined": true, "strikethrough": true, "obfuscated": true}"#).to_ansi(VERSION), "\x1b[1;4;5;9mf\x1b[0m");
+        // a § color in the text starts over from the terminal's default
+        assert_eq!(json(r#"{"text": "a§9b", "bold": true}"#).to_ansi(VERSION), "\x1b[1ma\x1b[0m\x1b[94mb\x1b[0m");
+    }
+
+    #[test]
+    fn renders_legacy_codes(){
+        let component = json(r##"{"text": "a", "color": "red", "bold": true, "extra": [{"text": "b", "bold": false}, {"text": "c", "color": "#00ff00"}]}"##);
+        // #00ff00 is closer to dark green than to green
+        assert_eq!(component.to_legacy(VERSION), "§c§la§cb§2§lc");
+        assert_eq!(json(r#"["", {"text": "x", "color": "red"}, "y"]"#).to_legacy(VERSION), "§cx§ry");
+        assert_eq!(json(r#"{"text": "all", "obfuscated": true, "bold": true, "strikethrough": true, "underlined": true, "italic": true}"#).to_legacy(VERSION), "§k§l§m§n§oall");
+
+        let chat = json(r#"{"translate": "chat.type.text", "color": "gray", "with": [{"text": "Steve", "color": "yellow"}, "hi"]}"#);
+        assert_eq!(chat.to_legacy(VERSION), "§7<§eSteve§7> hi");
+        assert_eq!(TextComponent::text("§cred §lbold§r plain").to_legacy(VERSION), "§cred §c§lbold§r plain");
+    }
+
+    #[test]
+    fn colors(){
+        for (index, color) in NamedColor::ALL.into_iter().enumerate(){
+            assert_eq!(NamedColor::from_code(color.code()), Some(color));
+            assert_eq!(NamedColor::from_name(color.name()), Some(color));
+            assert_eq!(color.code().to_digit(16), Some(index as u32));
+            assert_eq!(TextColor::Rgb(color.rgb()).to_named(), color);
+        }
+        assert_eq!(NamedColor::from_code('g'), None);
+        assert_eq!(TextColor::parse("dark_aqua"), Some(TextColor::Named(NamedColor::DarkAqua)));
+        assert_eq!(TextColor::parse("#FF5555"), Some(TextColor::Rgb(0xff5555)));
+        for bad in ["#fff", "#gggggg", "#ff55555", "Red", ""]{
+            assert_eq!(TextColor::parse(bad), None, "{}", bad);
+        }
+        assert_eq!(TextColor::Rgb(0xfe5050).to_named(), NamedColor::Red);
+    }
+}
@@ -0,0 +1,514 @@
+use {
+    std::fmt,
+};
+
+// the game versions we can talk to. play packet IDs move around between versions, so packets are
+// looked up through Clientbound / Serverbound here instead of having one fixed ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion{
+    V1_19_4,
+    V1_20_1,
+    V1_20_2,
+    V1_20_4,
+}
+
+impl ProtocolVersion{
+    pub const ALL: &'static [ProtocolVersion] = &[
+        ProtocolVersion::V1_19_4,
+        ProtocolVersion::V1_20_1,
+        ProtocolVersion::V1_20_2,
+        ProtocolVersion::V1_20_4,
+    ];
+    // used when the server doesn't tell us what it runs
+    pub const DEFAULT: ProtocolVersion = ProtocolVersion::V1_20_1;
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_20_4;
+
+    // 1.20 and 1.20.1 share a protocol number, so do 1.20.3 and 1.20.4
+    pub fn from_protocol(protocol: i32) -> Option<Self>{
+        ProtocolVersion::ALL.iter().copied().find(|version| version.protocol() == protocol)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self>{
+        match name{
+            "1.20" => Some(ProtocolVersion::V1_20_1),
+            "1.20.3" => Some(ProtocolVersion::V1_20_4),
+            name => ProtocolVersion::ALL.iter().copied().find(|version| version.name() == name),
+        }
+    }
+
+    pub fn protocol(&self) -> i32{
+        match self{
+            ProtocolVersion::V1_19_4 => 762,
+            ProtocolVersion::V1_20_1 => 763,
+            ProtocolVersion::V1_20_2 => 764,
+            ProtocolVersion::V1_20_4 => 765,
+        }
+    }
+
+    pub fn name(&self) -> &'static str{
+        match self{
+            ProtocolVersion::V1_19_4 => "1.19.4",
+            ProtocolVersion::V1_20_1 => "1.20.1",
+            ProtocolVersion::V1_20_2 => "1.20.2",
+            ProtocolVersion::V1_20_4 => "1.20.4",
+        }
+    }
+
+    // login -> configuration -> play instead of going straight to play, 1.20.2+
+    pub fn has_configuration(&self) -> bool{
+        *self >= ProtocolVersion::V1_20_2
+    }
+
+    // nbt in packets lost the (always empty) name of its root tag in 1.20.2
+    pub fn nameless_nbt(&self) -> bool{
+        *self >= ProtocolVersion::V1_20_2
+    }
+
+    // chat components are sent as nbt instead of json strings since 1.20.3
+    pub fn nbt_chat(&self) -> bool{
+        *self >= ProtocolVersion::V1_20_4
+    }
+
+    fn clientbound_ids(&self) -> &'static [(Clientbound, i32)]{
+        match self{
+            // 1.20 didn't move any packets, only changed a few of them
+            ProtocolVersion::V1_19_4 | ProtocolVersion::V1_20_1 => V1_20_1_CLIENTBOUND,
+            ProtocolVersion::V1_20_2 => V1_20_2_CLIENTBOUND,
+            ProtocolVersion::V1_20_4 => V1_20_4_CLIENTBOUND,
+        }
+    }
+
+    fn serverbound_ids(&self) -> &'static [(Serverbound, i32)]{
+        match self{
+            ProtocolVersion::V1_19_4 | ProtocolVersion::V1_20_1 => V1_20_1_SERVERBOUND,
+            ProtocolVersion::V1_20_2 => V1_20_2_SERVERBOUND,
+            ProtocolVersion::V1_20_4 => V1_20_4_SERVERBOUND,
+        }
+    }
+
+    // which play packet an ID is in this version, None for the ones we don't know
+    pub fn clientbound(&self, id: i32) -> Option<Clientbound>{
+        self.clientbound_ids().iter().find(|(_, packet_id)| *packet_id == id).map(|(packet, _)| *packet)
+    }
+
+    // None if the packet doesn't exist in this version
+    pub fn clientbound_id(&self, packet: Clientbound) -> Option<i32>{
+        self.clientbound_ids().iter().find(|(known, _)| *known == packet).map(|(_, id)| *id)
+    }
+
+    pub fn serverbound_id(&self, packet: Serverbound) -> Option<i32>{
+        self.serverbound_ids().iter().find(|(known, _)| *known == packet).map(|(_, id)| *id)
+    }
+
+    // same as clientbound but for the configuration state, always None before 1.20.2
+    pub fn configuration(&self, id: i32) -> Option<Configuration>{
+        let ids = match self{
+            ProtocolVersion::V1_19_4 | ProtocolVersion::V1_20_1 => return None,
+            ProtocolVersion::V1_20_2 => V1_20_2_CONFIGURATION,
+            ProtocolVersion::V1_20_4 => V1_20_4_CONFIGURATION,
+        };
+        ids.iter().find(|(_, packet_id)| *packet_id == id).map(|(packet, _)| *packet)
+    }
+}
+
+impl Default for ProtocolVersion{
+    fn default() -> Self{
+        ProtocolVersion::DEFAULT
+    }
+}
+
+impl fmt::Display for ProtocolVersion{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        write!(f, "{} (protocol {})", self.name(), self.protocol())
+    }
+}
+
+// the play packets we understand, coming from the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Clientbound{
+    BundleDelimiter,
+    SpawnEntity,
+    SpawnPlayer, // gone in 1.20.2, players come with Spawn Entity there
+    EntityAnimation,
+    AcknowledgeBlockChange,
+    BlockUpdate,
+    ChunkBatchFinished,
+    ChunkBatchStart,
+    Disconnect,
+    DisguisedChat,
+    UnloadChunk,
+    KeepAlive,
+    ChunkData,
+    UpdateLight,
+    Login,
+    PluginMessage,
+    FeatureFlags, // moved to the configuration state in 1.20.2
+    UpdateTags,
+    UpdateEntityPosition,
+    UpdateEntityPositionRotation,
+    UpdateEntityRotation,
+    Ping,
+    PlayerChat,
+    PlayerInfoRemove,
+    PlayerInfoUpdate,
+    SynchronizePlayerPosition,
+    RemoveEntities,
+    Respawn,
+    SetHeadRotation,
+    UpdateSectionBlocks,
+    SetEntityMetadata,
+    SetEntityVelocity,
+    StartConfiguration,
+    SystemChat,
+    TeleportEntity,
+}
+
+// the configuration packets coming from the server. the ones we send kept their IDs from 1.20.2
+// to 1.20.4, so they're plain codec::Packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Configuration{
+    PluginMessage,
+    Disconnect,
+    FinishConfiguration,
+    KeepAlive,
+    Ping,
+    RegistryData,
+    ResourcePack, // Add Resource Pack since 1.20.3
+    RemoveResourcePack, // 1.20.3+
+    FeatureFlags,
+    UpdateTags,
+}
+
+// the play packets we send
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Serverbound{
+    ConfirmTeleportation,
+    MessageAcknowledgment,
+    ChatCommand,
+    ChatMessage,
+    PlayerSession,
+    ChunkBatchReceived,
+    ClientCommand,
+    ClientInformation,
+    AcknowledgeConfiguration,
+    KeepAlive,
+    SetPlayerPosition,
+    SetPlayerPositionRotation,
+    SetPlayerRotation,
+    SetPlayerOnGround,
+    Pong,
+    PlayerAction,
+    SwingArm,
+    UseItemOn,
+}
+
+// a packet we send in the play state, ClientHandle::send finds its ID for the connection's version
+pub trait PlayPacket{
+    const PACKET: Serverbound;
+}
+
+const V1_20_1_CLIENTBOUND: &[(Clientbound, i32)] = &[
+    (Clientbound::BundleDelimiter, 0x00),
+    (Clientbound::SpawnEntity, 0x01),
+    (Clientbound::SpawnPlayer, 0x03),
+    (Clientbound::EntityAnimation, 0x04),
+    (Clientbound::AcknowledgeBlockChange, 0x06),
+    (Clientbound::BlockUpdate, 0x0A),
+    (Clientbound::PluginMessage, 0x17),
+    (Clientbound::Disconnect, 0x1A),
+    (Clientbound::DisguisedChat, 0x1B),
+    (Clientbound::UnloadChunk, 0x1E),
+    (Clientbound::KeepAlive, 0x23),
+    (Clientbound::ChunkData, 0x24),
+    (Clientbound::UpdateLight, 0x27),
+    (Clientbound::Login, 0x28),
+    (Clientbound::UpdateEntityPosition, 0x2B),
+    (Clientbound::UpdateEntityPositionRotation, 0x2C),
+    (Clientbound::UpdateEntityRotation, 0x2D),
+    (Clientbound::Ping, 0x32),
+    (Clientbound::PlayerChat, 0x35),
+    (Clientbound::PlayerInfoRemove, 0x39),
+    (Clientbound::PlayerInfoUpdate, 0x3A),
+    (Clientbound::SynchronizePlayerPosition, 0x3C),
+    (Clientbound::RemoveEntities, 0x3E),
+    (Clientbound::Respawn, 0x41),
+    (Clientbound::SetHeadRotation, 0x42),
+    (Clientbound::UpdateSectionBlocks, 0x43),
+    (Clientbound::SetEntityMetadata, 0x52),
+    (Clientbound::SetEntityVelocity, 0x54),
+    (Clientbound::SystemChat, 0x64),
+    (Clientbound::TeleportEntity, 0x68),
+    (Clientbound::FeatureFlags, 0x6B),
+    (Clientbound::UpdateTags, 0x6E),
+];
+
+const V1_20_2_CLIENTBOUND: &[(Clientbound, i32)] = &[
+    (Clientbound::BundleDelimiter, 0x00),
+    (Clientbound::SpawnEntity, 0x01),
+    (Clientbound::EntityAnimation, 0x03),
+    (Clientbound::AcknowledgeBlockChange, 0x05),
+    (Clientbound::BlockUpdate, 0x09),
+    (Clientbound::ChunkBatchFinished, 0x0C),
+    (Clientbound::ChunkBatchStart, 0x0D),
+    (Clientbound::PluginMessage, 0x18),
+    (Clientbound::Disconnect, 0x1B),
+    (Clientbound::DisguisedChat, 0x1C),
+    (Clientbound::UnloadChunk, 0x1F),
+    (Clientbound::KeepAlive, 0x24),
+    (Clientbound::ChunkData, 0x25),
+    (Clientbound::UpdateLight, 0x28),
+    (Clientbound::Login, 0x29),
+    (Clientbound::UpdateEntityPosition, 0x2C),
+    (Clientbound::UpdateEntityPositionRotation, 0x2D),
+    (Clientbound::UpdateEntityRotation, 0x2E),
+    (Clientbound::Ping, 0x33),
+    (Clientbound::PlayerChat, 0x37),
+    (Clientbound::PlayerInfoRemove, 0x3B),
+    (Clientbound::PlayerInfoUpdate, 0x3C),
+    (Clientbound::SynchronizePlayerPosition, 0x3E),
+    (Clientbound::RemoveEntities, 0x40),
+    (Clientbound::Respawn, 0x43),
+    (Clientbound::SetHeadRotation, 0x44),
+    (Clientbound::UpdateSectionBlocks, 0x45),
+    (Clientbound::SetEntityMetadata, 0x54),
+    (Clientbound::SetEntityVelocity, 0x56),
+    (Clientbound::StartConfiguration, 0x65),
+    (Clientbound::SystemChat, 0x67),
+    (Clientbound::TeleportEntity, 0x6B),
+    (Clientbound::UpdateTags, 0x70),
+];
+
+// 1.20.3 added Reset Score and split the resource pack packet in two, which moves everything after them
+const V1_20_4_CLIENTBOUND: &[(Clientbound, i32)] = &[
+    (Clientbound::BundleDelimiter, 0x00),
+    (Clientbound::SpawnEntity, 0x01),
+    (Clientbound::EntityAnimation, 0x03),
+    (Clientbound::AcknowledgeBlockChange, 0x05),
+    (Clientbound::BlockUpdate, 0x09),
+    (Clientbound::ChunkBatchFinished, 0x0C),
+    (Clientbound::ChunkBatchStart, 0x0D),
+    (Clientbound::PluginMessage, 0x18),
+    (Clientbound::Disconnect, 0x1B),
+    (Clientbound::DisguisedChat, 0x1C),
+    (Clientbound::UnloadChunk, 0x1F),
+    (Clientbound::KeepAlive, 0x24),
+    (Clientbound::ChunkData, 0x25),
+    (Clientbound::UpdateLight, 0x28),
+    (Clientbound::Login, 0x29),
+    (Clientbound::UpdateEntityPosition, 0x2C),
+    (Clientbound::UpdateEntityPositionRotation, 0x2D),
+    (Clientbound::UpdateEntityRotation, 0x2E),
+    (Clientbound::Ping, 0x33),
+    (Clientbound::PlayerChat, 0x37),
+    (Clientbound::PlayerInfoRemove, 0x3B),
+    (Clientbound::PlayerInfoUpdate, 0x3C),
+    (Clientbound::SynchronizePlayerPosition, 0x3E),
+    (Clientbound::RemoveEntities, 0x40),
+    (Clientbound::Respawn, 0x45),
+    (Clientbound::SetHeadRotation, 0x46),
+    (Clientbound::UpdateSectionBlocks, 0x47),
+    (Clientbound::SetEntityMetadata, 0x56),
+    (Clientbound::SetEntityVelocity, 0x58),
+    (Clientbound::StartConfiguration, 0x67),
+    (Clientbound::SystemChat, 0x69),
+    (Clientbound::TeleportEntity, 0x6D),
+    (Clientbound::UpdateTags, 0x74),
+];
+
+const V1_20_2_CONFIGURATION: &[(Configuration, i32)] = &[
+    (Configuration::PluginMessage, 0x00),
+    (Configuration::Disconnect, 0x01),
+    (Configuration::FinishConfiguration, 0x02),
+    (Configuration::KeepAlive, 0x03),
+    (Configuration::Ping, 0x04),
+    (Configuration::RegistryData, 0x05),
+    (Configuration::ResourcePack, 0x06),
+    (Configuration::FeatureFlags, 0x07),
+    (Configuration::UpdateTags, 0x08),
+];
+
+const V1_20_4_CONFIGURATION: &[(Configuration, i32)] = &[
+    (Configuration::PluginMessage, 0x00),
+    (Configuration::Disconnect, 0x01),
+    (Configuration::FinishConfiguration, 0x02),
+    (Configuration::KeepAlive, 0x03),
+    (Configuration::Ping, 0x04),
+    (Configuration::RegistryData, 0x05),
+    (Configuration::RemoveResourcePack, 0x06),
+    (Configuration::ResourcePack, 0x07),
+    (Configuration::FeatureFlags, 0x08),
+    (Configuration::UpdateTags, 0x09),
+];
+
+const V1_20_1_SERVERBOUND: &[(Serverbound, i32)] = &[
+    (Serverbound::ConfirmTeleportation, 0x00),
+    (Serverbound::MessageAcknowledgment, 0x03),
+    (Serverbound::ChatCommand, 0x04),
+    (Serverbound::ChatMessage, 0x05),
+    (Serverbound::PlayerSession, 0x06),
+    (Serverbound::ClientCommand, 0x07),
+    (Serverbound::ClientInformation, 0x08),
+    (Serverbound::KeepAlive, 0x12),
+    (Serverbound::SetPlayerPosition, 0x14),
+    (Serverbound::SetPlayerPositionRotation, 0x15),
+    (Serverbound::SetPlayerRotation, 0x16),
+    (Serverbound::SetPlayerOnGround, 0x17),
+    (Serverbound::PlayerAction, 0x1D),
+    (Serverbound::Pong, 0x20),
+    (Serverbound::SwingArm, 0x2F),
+    (Serverbound::UseItemOn, 0x31),
+];
+
+const V1_20_2_SERVERBOUND: &[(Serverbound, i32)] = &[
+    (Serverbound::ConfirmTeleportation, 0x00),
+    (Serverbound::MessageAcknowledgment, 0x03),
+    (Serverbound::ChatCommand, 0x04),
+    (Serverbound::ChatMessage, 0x05),
+    (Serverbound::PlayerSession, 0x06),
+    (Serverbound::ChunkBatchReceived, 0x07),
+    (Serverbound::ClientCommand, 0x08),
+    (Serverbound::ClientInformation, 0x09),
+    (Serverbound::AcknowledgeConfiguration, 0x0B),
+    (Serverbound::KeepAlive, 0x14),
+    (Serverbound::SetPlayerPosition, 0x16),
+    (Serverbound::SetPlayerPositionRotation, 0x17),
+    (Serverbound::SetPlayerRotation, 0x18),
+    (Serverbound::SetPlayerOnGround, 0x19),
+    (Serverbound::PlayerAction, 0x20),
+    (Serverbound::Pong, 0x23),
+    (Serverbound::SwingArm, 0x32),
+    (Serverbound::UseItemOn, 0x34),
+];
+
+// 1.20.3 added Change Container Slot State at 0x0F
+const V1_20_4_SERVERBOUND: &[(Serverbound, i32)] = &[
+    (Serverbound::ConfirmTeleportation, 0x00),
+    (Serverbound::MessageAcknowledgment, 0x03),
+    (Serverbound::ChatCommand, 0x04),
+    (Serverbound::ChatMessage, 0x05),
+    (Serverbound::PlayerSession, 0x06),
+    (Serverbound::ChunkBatchReceived, 0x07),
+    (Serverbound::ClientCommand, 0x08),
+    (Serverbound::ClientInformation, 0x09),
+    (Serverbound::AcknowledgeConfiguration, 0x0B),
+    (Serverbound::KeepAlive, 0x15),
+    (Serverbound::SetPlayerPosition, 0x17),
+    (Serverbound::SetPlayerPositionRotation, 0x18),
+    (Serverbound::SetPlayerRotation, 0x19),
+    (Serverbound::SetPlayerOnGround, 0x1A),
+    (Serverbound::PlayerAction, 0x21),
+    (Serverbound::Pong, 0x24),
+    (Serverbound::SwingArm, 0x33),
+    (Serverbound::UseItemOn, 0x35),
+];
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const V1_19_4: ProtocolVersion = ProtocolVersion::V1_19_4;
+    const V1_20_1: ProtocolVersion = ProtocolVersion::V1_20_1;
+    const V1_20_2: ProtocolVersion = ProtocolVersion::V1_20_2;
+    const V1_20_4: ProtocolVersion = ProtocolVersion::V1_20_4;
+
+    // from the protocol pages of each version on wiki.vg. 1.19.4 goes through the 1.20.1 tables,
+    // 1.20 moved no packets
+    const CLIENTBOUND: &[(ProtocolVersion, Clientbound, i32)] = &[
+        (V1_19_4, Clientbound::KeepAlive, 0x23),
+        (V1_19_4, Clientbound::ChunkData, 0x24),
+        (V1_19_4, Clientbound::Login, 0x28),
+        (V1_19_4, Clientbound::SynchronizePlayerPosition, 0x3C),
+        (V1_20_1, Clientbound::KeepAlive, 0x23),
+        (V1_20_1, Clientbound::ChunkData, 0x24),
+        (V1_20_1, Clientbound::Login, 0x28),
+        (V1_20_1, Clientbound::SynchronizePlayerPosition, 0x3C),
+        (V1_20_2, Clientbound::KeepAlive, 0x24),
+        (V1_20_2, Clientbound::ChunkData, 0x25),
+        (V1_20_2, Clientbound::Login, 0x29),
+        (V1_20_2, Clientbound::SynchronizePlayerPosition, 0x3E),
+        (V1_20_4, Clientbound::KeepAlive, 0x24),
+        (V1_20_4, Clientbound::ChunkData, 0x25),
+        (V1_20_4, Clientbound::Login, 0x29),
+        (V1_20_4, Clientbound::SynchronizePlayerPosition, 0x3E),
+    ];
+
+    const SERVERBOUND: &[(ProtocolVersion, Serverbound, i32)] = &[
+        (V1_19_4, Serverbound::KeepAlive, 0x12),
+        (V1_19_4, Serverbound::SetPlayerPosition, 0x14),
+        (V1_20_1, Serverbound::KeepAlive, 0x12),
+        (V1_20_1, Serverbound::SetPlayerPosition, 0x14),
+        (V1_20_2, Serverbound::KeepAlive, 0x14),
+        (V1_20_2, Serverbound::SetPlayerPosition, 0x16),
+        (V1_20_4, Serverbound::KeepAlive, 0x15),
+        (V1_20_4, Serverbound::SetPlayerPosition, 0x17),
+    ];
+
+    const CONFIGURATION: &[(ProtocolVersion, Configuration, i32)] = &[
+        (V1_20_2, Configuration::FinishConfiguration, 0x02),
+        (V1_20_2, Configuration::KeepAlive, 0x03),
+        (V1_20_2, Configuration::RegistryData, 0x05),
+        (V1_20_2, Configuration::ResourcePack, 0x06),
+        (V1_20_4, Configuration::FinishConfiguration, 0x02),
+        (V1_20_4, Configuration::KeepAlive, 0x03),
+        (V1_20_4, Configuration::RegistryData, 0x05),
+        (V1_20_4, Configuration::RemoveResourcePack, 0x06),
+        (V1_20_4, Configuration::ResourcePack, 0x07),
+    ];
+
+    #[test]
+    fn packet_ids(){
+        for &(version, packet, id) in CLIENTBOUND{
+            assert_eq!(version.clientbound_id(packet), Some(id), "{:?} in {}", packet, version);
+            assert_eq!(version.clientbound(id), Some(packet), "0x{:02x} in {}", id, version);
+        }
+        for &(version, packet, id) in SERVERBOUND{
+            assert_eq!(version.serverbound_id(packet), Some(id), "{:?} in {}", packet, version);
+        }
+        for &(version, packet, id) in CONFIGURATION{
+            assert_eq!(version.configuration(id), Some(packet), "0x{:02x} in {}", id, version);
+        }
+        for version in [V1_19_4, V1_20_1]{
+            assert_eq!(version.configuration(0x02), None);
+            assert_eq!(version.serverbound_id(Serverbound::AcknowledgeConfiguration), None);
+            assert_eq!(version.clientbound_id(Clientbound::StartConfiguration), None);
+        }
+    }
+
+    // two packets on one ID would make the second one unreachable
+    fn assert_one_to_one<P: PartialEq + fmt::Debug>(table: &[(P, i32)]){
+        for (index, (packet, id)) in table.iter().enumerate(){
+            assert!(table[index + 1..].iter().all(|(other, other_id)| other != packet && other_id != id), "{:?} 0x{:02x}", packet, id);
+        }
+    }
+
+    #[test]
+    fn tables_are_one_to_one(){
+        for table in [V1_20_1_CLIENTBOUND, V1_20_2_CLIENTBOUND, V1_20_4_CLIENTBOUND]{
+            assert_one_to_one(table);
+        }
+        for table in [V1_20_1_SERVERBOUND, V1_20_2_SERVERBOUND, V1_20_4_SERVERBOUND]{
+            assert_one_to_one(table);
+        }
+        assert_one_to_one(V1_20_2_CONFIGURATION);
+        assert_one_to_one(V1_20_4_CONFIGURATION);
+    }
+
+    #[test]
+    fn from_protocol(){
+        assert_eq!(ProtocolVersion::from_protocol(762), Some(V1_19_4));
+        assert_eq!(ProtocolVersion::from_protocol(763), Some(V1_20_1));
+        assert_eq!(ProtocolVersion::from_protocol(764), Some(V1_20_2));
+        assert_eq!(ProtocolVersion::from_protocol(765), Some(V1_20_4));
+        // 1.19.3, 1.20.5 and snapshots (0x40000000 and up)
+        for protocol in [761, 766, 0x40000000 | 140, -1, 0]{
+            assert_eq!(ProtocolVersion::from_protocol(protocol), None, "{}", protocol);
+        }
+        for &version in ProtocolVersion::ALL{
+            assert_eq!(ProtocolVersion::from_protocol(version.protocol()), Some(version));
+            assert_eq!(ProtocolVersion::from_name(version.name()), Some(version));
+        }
+        assert_eq!(ProtocolVersion::from_name("1.20"), Some(V1_20_1));
+        assert_eq!(ProtocolVersion::from_name("1.20.3"), Some(V1_20_4));
+        assert_eq!(ProtocolVersion::from_name("1.20.5"), None);
+    }
+}
@@ -0,0 +1,719 @@
+use {
+    std::{
+        collections::HashMap,
+        marker::PhantomData,
+        ops::Deref,
+    },
+    crate::{
+        codec::{
+            BlockPos,
+            PacketReader,
+            ProtocolError,
+        },
+        nbt::Tag,
+        packets::{
+            ChunkData,
+            LightData,
+        },
+        registry::{
+            BlockKind,
+            GenericBlockState,
+        },
+    },
+};
+
+// what the overworld looks like since 1.18, used until the registry codec says otherwise
+pub const DEFAULT_MIN_Y: i32 = -64;
+pub const DEFAULT_HEIGHT: i32 = 384;
+
+// bits a direct palette uses, the log2 of the number of block states and biomes in 1.20.1
+const BLOCK_STATE_BITS: u8 = 15;
+const BIOME_BITS: u8 = 6;
+
+// the two kinds of paletted container a chunk section has, they differ in size and in
+// how many bits per entry they allow before switching to the global palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind{
+    Blocks,
+    Biomes,
+}
+
+impl ContainerKind{
+    fn size(self) -> usize{
+        match self{
+            ContainerKind::Blocks => 16 * 16 * 16,
+            ContainerKind::Biomes => 4 * 4 * 4,
+        }
+    }
+
+    fn min_indirect_bits(self) -> u8{
+        match self{
+            ContainerKind::Blocks => 4,
+            ContainerKind::Biomes => 1,
+        }
+    }
+
+    fn max_indirect_bits(self) -> u8{
+        match self{
+            ContainerKind::Blocks => 8,
+            ContainerKind::Biomes => 3,
+        }
+    }
+
+    fn direct_bits(self) -> u8{
+        match self{
+            ContainerKind::Blocks => BLOCK_STATE_BITS,
+            ContainerKind::Biomes => BIOME_BITS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Palette{
+    // every entry is this value and there is no data array
+    Single(u32),
+    // the data array holds indexes into this list
+    Indirect(Vec<u32>),
+    // the data array holds the global IDs themselves
+    Direct,
+}
+
+// block states or biomes of one section, packed the same way the protocol sends them
+// (entries never straddle two longs) so a loaded world doesn't take up more memory than needed
+#[derive(Debug, Clone)]
+pub struct PalettedContainer{
+    kind: ContainerKind,
+    bits: u8,
+    palette: Palette,
+    data: Vec<u64>,
+}
+
+impl PalettedContainer{
+    pub fn single(kind: ContainerKind, value: u32) -> Self{
+        PalettedContainer{kind, bits: 0, palette: Palette::Single(value), data: Vec::new()}
+    }
+
+    pub fn read(reader: &mut PacketReader<'_>, kind: ContainerKind) -> Result<Self, ProtocolError>{
+        let bits = reader.read_u8()?;
+        let palette = if bits == 0{
+            Palette::Single(read_id(reader)?)
+        } else if bits <= kind.max_indirect_bits(){
+            let length = reader.read_varint()?;
+            let mut palette = Vec::new();
+            for _ in 0..length{
+                palette.push(read_id(reader)?);
+            }
+            Palette::Indirect(palette)
+        } else {
+            Palette::Direct
+        };
+        // vanilla sends fewer bits than the minimum sometimes and uses the minimum anyway
+        let bits = match palette{
+            Palette::Indirect(_) => bits.max(kind.min_indirect_bits()),
+            _ => bits,
+        };
+        // global IDs are u32s, anything wider can only be garbage (and 64 would overflow the masks)
+        if bits > 32{
+            return Err(ProtocolError::InvalidValue(format!("paletted container with {} bits per entry", bits)));
+        }
+
+        let length = reader.read_varint()?;
+        if length < 0{
+            return Err(ProtocolError::NegativeLength(length));
+        }
+        let mut data = Vec::with_capacity((length as usize).min(reader.remaining() / 8));
+        for _ in 0..length{
+            data.push(reader.read_i64()? as u64);
+        }
+
+        if bits > 0{
+            let expected = kind.size().div_ceil(64 / bits as usize);
+            if data.len() != expected{
+                return Err(ProtocolError::InvalidValue(format!("paletted container with {} bits per entry has {} longs instead of {}", bits, data.len(), expected)));
+            }
+        }
+        Ok(PalettedContainer{kind, bits, palette, data})
+    }
+
+    pub fn kind(&self) -> ContainerKind{
+        self.kind
+    }
+
+    pub fn bits(&self) -> u8{
+        self.bits
+    }
+
+    pub fn palette(&self) -> &Palette{
+        &self.palette
+    }
+
+    pub fn get(&self, index: usize) -> u32{
+        match &self.palette{
+            Palette::Single(value) => *value,
+            Palette::Indirect(palette) => palette.get(self.raw(index) as usize).copied().unwrap_or(0),
+            Palette::Direct => self.raw(index),
+        }
+    }
+
+    // sets an entry and gives back what was there, growing the palette if it has to
+    pub fn set(&mut self, index: usize, value: u32) -> u32{
+        let old = self.get(index);
+        if old == value{
+            return old;
+        }
+
+        let raw = match &mut self.palette{
+            Palette::Single(_) => {
+                self.resize(self.kind.min_indirect_bits());
+                return self.set(index, value);
+            },
+            Palette::Indirect(palette) => match palette.iter().position(|entry| *entry == value){
+                Some(position) => position as u32,
+                None if palette.len() < 1 << self.bits => {
+                    palette.push(value);
+                    (palette.len() - 1) as u32
+                },
+                None => {
+                    self.resize(self.bits + 1);
+                    return self.set(index, value);
+                },
+            },
+            Palette::Direct => value,
+        };
+        self.set_raw(index, raw);
+        old
+    }
+
+    fn raw(&self, index: usize) -> u32{
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        let mask = (1u64 << self.bits) - 1;
+        self.data.get(index / per_long).map_or(0, |long| (long >> shift & mask) as u32)
+    }
+
+    fn set_raw(&mut self, index: usize, raw: u32){
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        let mask = (1u64 << self.bits) - 1;
+        let long = &mut self.data[index / per_long];
+        *long = (*long & !(mask << shift)) | ((raw as u64 & mask) << shift);
+    }
+
+    // repacks everything with more bits, past the indirect limit that means the global palette
+    fn resize(&mut self, bits: u8){
+        let values: Vec<u32> = (0..self.kind.size()).map(|index| self.get(index)).collect();
+
+        let (bits, palette) = if bits > self.kind.max_indirect_bits(){
+            (self.kind.direct_bits(), Palette::Direct)
+        } else {
+            let mut palette: Vec<u32> = Vec::new();
+            for value in &values{
+                if !palette.contains(value){
+                    palette.push(*value);
+                }
+            }
+            (bits.max(self.kind.min_indirect_bits()), Palette::Indirect(palette))
+        };
+
+        self.bits = bits;
+        self.palette = palette;
+        self.data = vec![0; self.kind.size().div_ceil(64 / bits as usize)];
+        for (index, value) in values.into_iter().enumerate(){
+            let raw = match &self.palette{
+                Palette::Indirect(palette) => palette.iter().position(|entry| *entry == value).unwrap_or(0) as u32,
+                _ => value,
+            };
+            self.set_raw(index, raw);
+        }
+    }
+}
+
+fn read_id(reader: &mut PacketReader<'_>) -> Result<u32, ProtocolError>{
+    let id = reader.read_varint()?;
+    if id < 0{
+        return Err(ProtocolError::InvalidValue(format!("negative palette entry: {}", id)));
+    }
+    Ok(id as u32)
+}
+
+// a 16x16x16 cube of the world
+#[derive(Debug, Clone)]
+pub struct ChunkSection{
+    pub block_count: i16, // non air blocks
+    pub blocks: PalettedContainer,
+    pub biomes: PalettedContainer,
+}
+
+impl ChunkSection{
+    pub fn read(reader: &mut PacketReader<'_>) -> Result<Self, ProtocolError>{
+        Ok(ChunkSection{
+            block_count: reader.read_i16()?,
+            blocks: PalettedContainer::read(reader, ContainerKind::Blocks)?,
+            biomes: PalettedContainer::read(reader, ContainerKind::Biomes)?,
+        })
+    }
+
+    // coordinates are relative to the section, 0 to 15
+    pub fn block(&self, x: usize, y: usize, z: usize) -> u32{
+        self.blocks.get((y * 16 + z) * 16 + x)
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: u32) -> u32{
+        self.blocks.set((y * 16 + z) * 16 + x, state)
+    }
+
+    // biomes are stored per 4x4x4 cell, coordinates here are still in blocks
+    pub fn biome(&self, x: usize, y: usize, z: usize) -> u32{
+        self.biomes.get((y / 4 * 4 + z / 4) * 4 + x / 4)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockEntity{
+    pub position: BlockPos,
+    pub kind: i32,
+    pub data: Option<Tag>,
+}
+
+// a 16 block wide column from the bottom of the world to the top
+#[derive(Debug, Clone)]
+pub struct Chunk{
+    pub x: i32,
+    pub z: i32,
+    pub sections: Vec<ChunkSection>,
+    pub heightmaps: Option<Tag>,
+    pub block_entities: HashMap<BlockPos, BlockEntity>,
+    // one more section than the world has below it and one above, None where it is all dark
+    pub sky_light: Vec<Option<Vec<u8>>>,
+    pub block_light: Vec<Option<Vec<u8>>>,
+}
+
+impl Chunk{
+    pub fn from_packet(packet: ChunkData, section_count: usize) -> Result<Self, ProtocolError>{
+        let mut reader = PacketReader::new(&packet.data);
+        // every section is at least 8 bytes, so a bogus section count can't make us allocate much
+        let mut sections = Vec::with_capacity(section_count.min(reader.remaining() / 8));
+        for _ in 0..section_count{
+            sections.push(ChunkSection::read(&mut reader)?);
+        }
+
+        let block_entities = packet.block_entities.into_iter().map(|entity| {
+            let position = BlockPos::new(packet.x * 16 + entity.x as i32, entity.y as i32, packet.z * 16 + entity.z as i32);
+            (position, BlockEntity{position, kind: entity.kind, data: entity.data})
+        }).collect();
+
+        let mut chunk = Chunk{
+            x: packet.x,
+            z: packet.z,
+            sections,
+            heightmaps: packet.heightmaps,
+            block_entities,
+            sky_light: vec![None; section_count + 2],
+            block_light: vec![None; section_count + 2],
+        };
+        chunk.update_light(packet.light);
+        Ok(chunk)
+    }
+
+    pub fn update_light(&mut self, light: LightData){
+        apply_light(&mut self.sky_light, &light.sky_light_mask, &light.empty_sky_light_mask, light.sky_light);
+        apply_light(&mut self.block_light, &light.block_light_mask, &light.empty_block_light_mask, light.block_light);
+    }
+}
+
+fn apply_light(sections: &mut [Option<Vec<u8>>], mask: &[i64], empty_mask: &[i64], arrays: Vec<Vec<u8>>){
+    let bit = |mask: &[i64], index: usize| mask.get(index / 64).is_some_and(|long| long >> (index % 64) & 1 != 0);
+
+    let mut arrays = arrays.into_iter();
+    for (index, section) in sections.iter_mut().enumerate(){
+        if bit(mask, index){
+            *section = arrays.next();
+        } else if bit(empty_mask, index){
+            *section = None;
+        }
+    }
+}
+
+fn light_at(sections: &[Option<Vec<u8>>], section: usize, x: usize, y: usize, z: usize) -> u8{
+    let index = (y * 16 + z) * 16 + x;
+    match sections.get(section){
+        Some(Some(array)) => array.get(index / 2).map_or(0, |byte| byte >> (index % 2 * 4) & 0x0F),
+        _ => 0,
+    }
+}
+
+// a block that changed from one state to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChange{
+    pub position: BlockPos,
+    pub old: u32,
+    pub new: u32,
+}
+
+// a block we changed ourselves before the server agreed to it, see World::predict_block
+#[derive(Debug, Clone, Copy)]
+struct Prediction{
+    sequence: i32,
+    server_state: u32,
+}
+
+// the chunks the server sent us for the dimension we are in
+#[derive(Debug, Clone)]
+pub struct World{
+    min_y: i32,
+    height: i32,
+    chunks: HashMap<(i32, i32), Chunk>,
+    sequence: i32,
+    predictions: HashMap<BlockPos, Prediction>,
+}
+
+impl Default for World{
+    fn default() -> Self{
+        World::new(DEFAULT_MIN_Y, DEFAULT_HEIGHT)
+    }
+}
+
+impl World{
+    pub fn new(min_y: i32, height: i32) -> Self{
+        World{min_y, height, chunks: HashMap::new(), sequence: 0, predictions: HashMap::new()}
+    }
+
+    // sizes the world after the dimension type with this name in the registry codec from Login (play)
+    pub fn for_dimension(registry_codec: &Tag, dimension_type: &str) -> Self{
+        let element = registry_codec.get("minecraft:dimension_type")
+            .and_then(|registry| registry.get("value"))
+            .and_then(Tag::as_list)
+            .and_then(|entries| entries.iter().find(|entry| entry.get("name").and_then(Tag::as_str) == Some(dimension_type)))
+            .and_then(|entry| entry.get("element"));
+
+        let min_y = element.and_then(|element| element.get("min_y")).and_then(Tag::as_i64);
+        let height = element.and_then(|element| element.get("height")).and_then(Tag::as_i64);
+        match (min_y, height){
+            (Some(min_y), Some(height)) => World::new(min_y as i32, height as i32),
+            _ => World::default(),
+        }
+    }
+
+    pub fn min_y(&self) -> i32{
+        self.min_y
+    }
+
+    pub fn height(&self) -> i32{
+        self.height
+    }
+
+    pub fn section_count(&self) -> usize{
+        (self.height.max(0) / 16) as usize
+    }
+
+    pub fn chunk(&self, x: i32, z: i32) -> Option<&Chunk>{
+        self.chunks.get(&(x, z))
+    }
+
+    pub fn chunk_mut(&mut self, x: i32, z: i32) -> Option<&mut Chunk>{
+        self.chunks.get_mut(&(x, z))
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk>{
+        self.chunks.values()
+    }
+
+    pub fn is_loaded(&self, x: i32, z: i32) -> bool{
+        self.chunks.contains_key(&(x, z))
+    }
+
+    pub fn load_chunk(&mut self, packet: ChunkData) -> Result<(), ProtocolError>{
+        let chunk = Chunk::from_packet(packet, self.section_count())?;
+        self.chunks.insert((chunk.x, chunk.z), chunk);
+        Ok(())
+    }
+
+    pub fn unload_chunk(&mut self, x: i32, z: i32) -> Option<Chunk>{
+        self.predictions.retain(|position, _| (position.x.div_euclid(16), position.z.div_euclid(16)) != (x, z));
+        self.chunks.remove(&(x, z))
+    }
+
+    // light for a chunk that isn't loaded yet is dropped, the server sends it again with the chunk
+    pub fn update_light(&mut self, x: i32, z: i32, light: LightData){
+        if let Some(chunk) = self.chunks.get_mut(&(x, z)){
+            chunk.update_light(light);
+        }
+    }
+
+    pub fn clear(&mut self){
+        self.chunks.clear();
+        self.predictions.clear();
+    }
+
+    // the block state ID at these world coordinates, None if the chunk isn't loaded or y is out of the world
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<u32>{
+        let (section, local_y) = self.section_index(y)?;
+        let chunk = self.chunk(x.div_euclid(16), z.div_euclid(16))?;
+        Some(chunk.sections.get(section)?.block(x.rem_euclid(16) as usize, local_y, z.rem_euclid(16) as usize))
+    }
+
+    // the world with its states looked up in a version's block registry, B is the Block of the
+    // version the server runs, see registry::with_block_kind
+    pub fn blocks<B: BlockKind>(&self) -> Blocks<'_, B>{
+        Blocks{world: self, block: PhantomData}
+    }
+
+    // the loaded blocks within radius (a cube) of center whose state matches, nearest first.
+    // for going to some type of block, see pathfinder::GoalAny
+    pub fn find_blocks<F: Fn(u32) -> bool>(&self, center: BlockPos, radius: i32, matches: F) -> Vec<BlockPos>{
+        let mut found = Vec::new();
+        for x in center.x - radius..=center.x + radius{
+            for z in center.z - radius..=center.z + radius{
+                if !self.is_loaded(x.div_euclid(16), z.div_euclid(16)){
+                    continue;
+                }
+                for y in (center.y - radius).max(self.min_y)..=(center.y + radius).min(self.min_y + self.height - 1){
+                    if self.block_at(x, y, z).is_some_and(&matches){
+                        found.push(BlockPos::new(x, y, z));
+                    }
+                }
+            }
+        }
+        let distance = |position: &BlockPos| {
+            let (dx, dy, dz) = ((position.x - center.x) as i64, (position.y - center.y) as i64, (position.z - center.z) as i64);
+            dx * dx + dy * dy + dz * dz
+        };
+        found.sort_by_key(distance);
+        found
+    }
+
+    // changes a block and gives back the old state, None (and nothing changes) if it isn't loaded
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, state: u32) -> Option<u32>{
+        let (section, local_y) = self.section_index(y)?;
+        let chunk = self.chunks.get_mut(&(x.div_euclid(16), z.div_euclid(16)))?;
+        let old = chunk.sections.get_mut(section)?.set_block(x.rem_euclid(16) as usize, local_y, z.rem_euclid(16) as usize, state);
+        if old != state{
+            // whatever block entity was there belonged to the old block
+            chunk.block_entities.remove(&BlockPos::new(x, y, z));
+        }
+        Some(old)
+    }
+
+    // a Block Update or an entry of Update Section Blocks. a block we predicted ourselves keeps
+    // its predicted state until the server acknowledges the prediction, the server's state is
+    // remembered and put in place then (like vanilla does, so blocks don't flicker)
+    pub fn server_block_update(&mut self, position: BlockPos, state: u32) -> Option<BlockChange>{
+        if let Some(prediction) = self.predictions.get_mut(&position){
+            prediction.server_state = state;
+            return None;
+        }
+
+        let old = self.set_block(position.x, position.y, position.z, state)?;
+        (old != state).then_some(BlockChange{position, old, new: state})
+    }
+
+    // changes a block before the server confirms it, for digging and placing. the returned
+    // sequence number goes in the packet that asks the server for the change
+    pub fn predict_block(&mut self, position: BlockPos, state: u32) -> i32{
+        self.sequence += 1;
+        let sequence = self.sequence;
+
+        if let Some(old) = self.set_block(position.x, position.y, position.z, state){
+            let prediction = self.predictions.entry(position).or_insert(Prediction{sequence, server_state: old});
+            prediction.sequence = sequence;
+        }
+        sequence
+    }
+
+    // the sequence number the last prediction used
+    pub fn sequence(&self) -> i32{
+        self.sequence
+    }
+
+    // Acknowledge Block Change, every prediction up to this sequence number is settled and the
+    // state the server sent for it in the meantime (or the original one) is put back in
+    pub fn acknowledge(&mut self, sequence: i32) -> Vec<BlockChange>{
+        let settled: Vec<(BlockPos, Prediction)> = self.predictions.iter()
+            .filter(|(_, prediction)| prediction.sequence <= sequence)
+            .map(|(position, prediction)| (*position, *prediction))
+            .collect();
+
+        let mut changes = Vec::new();
+        for (position, prediction) in settled{
+            self.predictions.remove(&position);
+            if let Some(change) = self.server_block_update(position, prediction.server_state){
+                changes.push(change);
+            }
+        }
+        changes
+    }
+
+    pub fn biome_at(&self, x: i32, y: i32, z: i32) -> Option<u32>{
+        let (section, local_y) = self.section_index(y)?;
+        let chunk = self.chunk(x.div_euclid(16), z.div_euclid(16))?;
+        Some(chunk.sections.get(section)?.biome(x.rem_euclid(16) as usize, local_y, z.rem_euclid(16) as usize))
+    }
+
+    pub fn block_entity_at(&self, x: i32, y: i32, z: i32) -> Option<&BlockEntity>{
+        self.chunk(x.div_euclid(16), z.div_euclid(16))?.block_entities.get(&BlockPos::new(x, y, z))
+    }
+
+    pub fn sky_light_at(&self, x: i32, y: i32, z: i32) -> Option<u8>{
+        let (section, local_y) = self.section_index(y)?;
+        let chunk = self.chunk(x.div_euclid(16), z.div_euclid(16))?;
+        Some(light_at(&chunk.sky_light, section + 1, x.rem_euclid(16) as usize, local_y, z.rem_euclid(16) as usize))
+    }
+
+    pub fn block_light_at(&self, x: i32, y: i32, z: i32) -> Option<u8>{
+        let (section, local_y) = self.section_index(y)?;
+        let chunk = self.chunk(x.div_euclid(16), z.div_euclid(16))?;
+        Some(light_at(&chunk.block_light, section + 1, x.rem_euclid(16) as usize, local_y, z.rem_euclid(16) as usize))
+    }
+
+    fn section_index(&self, y: i32) -> Option<(usize, usize)>{
+        if y < self.min_y || y >= self.min_y + self.height{
+            return None;
+        }
+        let y = (y - self.min_y) as usize;
+        Some((y / 16, y % 16))
+    }
+}
+
+// what physics and the pathfinder see of the world, see World::blocks
+#[derive(Debug, Clone, Copy)]
+pub struct Blocks<'a, B>{
+    world: &'a World,
+    block: PhantomData<B>,
+}
+
+impl<B: BlockKind> Blocks<'_, B>{
+    // None where the world isn't loaded
+    pub fn state(&self, position: BlockPos) -> Option<GenericBlockState<B>>{
+        self.world.block_at(position.x, position.y, position.z).map(GenericBlockState::new)
+    }
+}
+
+impl<B> Deref for Blocks<'_, B>{
+    type Target = World;
+
+    fn deref(&self) -> &World{
+        self.world
+    }
+}
+
+// an all air world with the chunks from -radius to radius around the origin loaded, for tests
+// to put their blocks in with set_block
+#[cfg(test)]
+pub(crate) fn test_world(min_y: i32, height: i32, radius: i32) -> World{
+    let mut world = World::new(min_y, height);
+    // no blocks, then a single entry palette without data for the blocks and for the biomes
+    let section = [0, 0, 0, 0, 0, 0, 0, 0];
+    for x in -radius..=radius{
+        for z in -radius..=radius{
+            let data = section.repeat(world.section_count());
+            world.load_chunk(ChunkData{x, z, heightmaps: None, data, block_entities: Vec::new(), light: LightData::default()}).unwrap();
+        }
+    }
+    world
+}
+
+#[cfg(test)]
+mod tests{
+    use {
+        super::*,
+        crate::codec::varint_write,
+    };
+
+    fn container(bits: u8, palette: &[i32], longs: &[u64]) -> Vec<u8>{
+        let mut buf = vec![bits];
+        if !palette.is_empty(){
+            if bits != 0{
+                buf.extend(varint_write(palette.len() as i32));
+            }
+            for id in palette{
+                buf.extend(varint_write(*id));
+            }
+        }
+        buf.extend(varint_write(longs.len() as i32));
+        for long in longs{
+            buf.extend(long.to_be_bytes());
+        }
+        buf
+    }
+
+    fn read(data: &[u8], kind: ContainerKind) -> Result<PalettedContainer, ProtocolError>{
+        PalettedContainer::read(&mut PacketReader::new(data), kind)
+    }
+
+    #[test]
+    fn single_and_indirect(){
+        let single = read(&container(0, &[9], &[]), ContainerKind::Blocks).unwrap();
+        assert_eq!(single.palette(), &Palette::Single(9));
+        assert_eq!(single.get(4095), 9);
+
+        // 4 bits, 16 entries per long, entry i is palette index i % 2
+        let longs = vec![0x1010_1010_1010_1010; 256];
+        let indirect = read(&container(4, &[1, 2], &longs), ContainerKind::Blocks).unwrap();
+        assert_eq!((indirect.get(0), indirect.get(1), indirect.get(4095)), (1, 2, 2));
+
+        // 1 bit is bumped up to the minimum of 4, and the long count has to fit that
+        assert!(read(&container(1, &[1, 2], &longs), ContainerKind::Blocks).is_ok());
+        assert!(read(&container(1, &[1, 2], &[0; 64]), ContainerKind::Blocks).is_err());
+    }
+
+    #[test]
+    fn biomes_use_their_own_sizes(){
+        // 64 entries at 2 bits fit in 2 longs
+        let biomes = read(&container(2, &[0, 5, 7], &[0xFFFF_FFFF_FFFF_FFFE, 0]), ContainerKind::Biomes).unwrap();
+        assert_eq!((biomes.get(0), biomes.get(1), biomes.get(63)), (7, 0, 0));
+        // past 3 bits biomes are direct
+        let direct = read(&container(6, &[], &[42; 7]), ContainerKind::Biomes).unwrap();
+        assert_eq!(direct.palette(), &Palette::Direct);
+        assert_eq!(direct.get(0), 42);
+    }
+
+    #[test]
+    fn malformed_containers_are_errors(){
+        for bits in [33, 63, 64, 65, 200, 255]{
+            assert!(matches!(read(&container(bits, &[], &[0; 4096]), ContainerKind::Blocks), Err(ProtocolError::InvalidValue(_))), "{} bits", bits);
+        }
+        // direct with 32 bits is fine as long as the data is the right size
+        assert!(read(&container(32, &[], &[0; 2048]), ContainerKind::Blocks).is_ok());
+        assert!(read(&container(32, &[], &[0; 2047]), ContainerKind::Blocks).is_err());
+
+        assert!(read(&container(0, &[-1], &[]), ContainerKind::Blocks).is_err());
+        let mut negative = vec![4];
+        negative.extend(varint_write(0));
+        negative.extend(varint_write(-1));
+        assert!(matches!(read(&negative, ContainerKind::Blocks), Err(ProtocolError::NegativeLength(-1))));
+
+        let full = container(4, &[1, 2], &[0; 256]);
+        for end in 0..full.len(){
+            assert!(read(&full[..end], ContainerKind::Blocks).is_err());
+        }
+    }
+
+    #[test]
+    fn set_grows_the_palette(){
+        let mut container = PalettedContainer::single(ContainerKind::Blocks, 0);
+        for index in 0..4096{
+            assert_eq!(container.set(index, index as u32), 0);
+        }
+        assert_eq!(container.palette(), &Palette::Direct);
+        assert_eq!(container.bits(), BLOCK_STATE_BITS);
+        for index in 0..4096{
+            assert_eq!(container.get(index), index as u32);
+        }
+
+        let mut container = PalettedContainer::single(ContainerKind::Blocks, 7);
+        assert_eq!(container.set(100, 8), 7);
+        assert_eq!(container.bits(), 4);
+        for value in 9..30{
+            container.set(value as usize, value);
+        }
+        assert_eq!(container.bits(), 5);
+        assert_eq!((container.get(0), container.get(100), container.get(29)), (7, 8, 29));
+    }
+
+    #[test]
+    fn bogus_heights_dont_allocate(){
+        let packet = ChunkData{x: 0, z: 0, heightmaps: None, data: container(0, &[1], &[]), block_entities: Vec::new(), light: LightData::default()};
+        assert!(Chunk::from_packet(packet, usize::MAX / 2).is_err());
+        assert_eq!(World::new(0, -16).section_count(), 0);
+    }
+}